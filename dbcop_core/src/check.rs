@@ -0,0 +1,1752 @@
+//! Checks a history against any consistency level.
+//!
+//! Prefix consistency, snapshot isolation and serializability all start from the causal partial
+//! order. A [`CheckSession`] saturates it once and reuses it for every level checked on the same
+//! history. Before a linearization search, the transactions that cannot take part in a violation
+//! are [pruned](crate::solver::pruning) and put back in the witness.
+//!
+//! [`check_prefixes`] finds the earliest prefix of a history at which a violation is detectable.
+//! [`check_exhaustive`] reports the violations of every independent part of a history.
+//! [`strongest_level`] finds the strongest level a history satisfies.
+
+use alloc::collections::BTreeSet;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter, Result as FmtResult};
+use core::hash::Hash;
+
+use hashbrown::{HashMap, HashSet};
+
+use crate::history::atomic::types::TransactionId;
+use crate::history::atomic::AtomicTransactionPO;
+use crate::history::initial::InitialValuePolicy;
+use crate::history::intern::intern;
+use crate::history::non_atomic::error::Error as NonAtomicError;
+use crate::history::non_atomic::types::{Event, EventId, Session};
+use crate::history::non_atomic::validate_history;
+use crate::history::project::project_variables;
+use crate::history::stats::session_components;
+use crate::shard::round_order;
+use crate::solver::atomic_read::check_atomic_read;
+use crate::solver::canonical::canonical_linearization;
+use crate::solver::causal::{check_causal_read, saturate_causal};
+use crate::solver::committed_read::check_committed_read;
+use crate::solver::constrained_linearization::{
+    ConstrainedLinearizationSolver, LinearizationStepper, SearchStats,
+};
+use crate::solver::error::Error;
+use crate::solver::polygraph::serializable_order;
+use crate::solver::prefix::PrefixConsistencySolver;
+use crate::solver::pruning::{prune, Pruning, Reduction};
+use crate::solver::repeatable_read::check_repeatable_read;
+use crate::solver::serializable::SerializabilitySolver;
+use crate::solver::snapshot_isolation::SnapshotIsolationSolver;
+use crate::Consistency;
+
+/// Evidence that a history satisfies a consistency level.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Witness {
+    /// The saturated visibility relation is acyclic
+    Saturated,
+    /// A commit order of the transactions
+    CommitOrder(Vec<TransactionId>),
+    /// A commit order of the read (`false`) and write (`true`) sections of the transactions
+    SplitCommitOrder(Vec<(TransactionId, bool)>),
+    /// The events of a commit order, in the order they take effect. See
+    /// [`Witness::into_operation_order`].
+    OperationOrder(Vec<EventId>),
+}
+
+impl Witness {
+    /// Expands a commit order into the events of its transactions, for following a witness one
+    /// read or write at a time.
+    ///
+    /// A transaction of a commit order places all its events at its position. A split commit order
+    /// places the reads of a transaction at its read section, and its writes at its write section,
+    /// along with the reads of the variables it wrote before, which read its own writes. The
+    /// transactions that are not in `histories`, such as the root, have no events. Saturation and
+    /// operation witnesses are returned as is.
+    #[must_use]
+    pub fn into_operation_order<Variable, Version>(
+        self,
+        histories: &[Session<Variable, Version>],
+    ) -> Self
+    where
+        Variable: Eq,
+    {
+        // the transactions in order, with the section placed, if split
+        let sections: Vec<(TransactionId, Option<bool>)> = match self {
+            Self::Saturated | Self::OperationOrder(_) => return self,
+            Self::CommitOrder(order) => order.into_iter().map(|id| (id, None)).collect(),
+            Self::SplitCommitOrder(order) => order
+                .into_iter()
+                .map(|(id, write)| (id, Some(write)))
+                .collect(),
+        };
+
+        let mut order = Vec::new();
+        for (id, section) in sections {
+            let Some(transaction) = id
+                .session_id
+                .checked_sub(1)
+                .and_then(|session| histories.get(usize::try_from(session).ok()?))
+                .and_then(|session| session.get(usize::try_from(id.session_height).ok()?))
+            else {
+                continue;
+            };
+            let mut written = Vec::new();
+            for (transaction_height, event) in (0..).zip(&transaction.events) {
+                let write = match event {
+                    Event::Write { variable, .. } => {
+                        written.push(variable);
+                        true
+                    }
+                    Event::Read { variable, .. } => written.contains(&variable),
+                };
+                if section.map_or(true, |section| section == write) {
+                    order.push(EventId {
+                        session_id: id.session_id,
+                        session_height: id.session_height,
+                        transaction_height,
+                    });
+                }
+            }
+        }
+        Self::OperationOrder(order)
+    }
+}
+
+/// Renders a commit order as `s1.t0 < s2.t0`, with `:r` and `:w` for the read and write sections,
+/// and an operation order as `s1.t0.e0 < s2.t0.e0`.
+impl Display for Witness {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Saturated => write!(f, "saturated"),
+            Self::CommitOrder(order) => {
+                for (i, id) in order.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " < ")?;
+                    }
+                    write!(f, "{id}")?;
+                }
+                Ok(())
+            }
+            Self::SplitCommitOrder(order) => {
+                for (i, (id, write)) in order.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " < ")?;
+                    }
+                    write!(f, "{id}:{}", if *write { "w" } else { "r" })?;
+                }
+                Ok(())
+            }
+            Self::OperationOrder(order) => {
+                for (i, id) in order.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " < ")?;
+                    }
+                    write!(f, "{id}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// How serializability is decided.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Backend {
+    /// Searches a commit order one transaction at a time
+    #[default]
+    Linearization,
+    /// Propagates the write order constraints of the polygraph, and searches only the undecided
+    /// ones. See [`crate::solver::polygraph`].
+    Polygraph,
+}
+
+/// A transaction known to happen before another one.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderHint {
+    pub before: TransactionId,
+    pub after: TransactionId,
+}
+
+/// Checks one history against several levels, sharing the causal partial order between them.
+#[derive(Debug)]
+pub struct CheckSession<'a, Variable, Version>
+where
+    Variable: Clone + Eq + Hash,
+{
+    histories: &'a [Session<Variable, Version>],
+    causal: Option<Result<AtomicTransactionPO<Variable>, Error<Variable, Version>>>,
+    backend: Backend,
+    search: Search,
+    operations: bool,
+}
+
+impl<'a, Variable, Version> CheckSession<'a, Variable, Version>
+where
+    Variable: Clone + Eq + Ord + Hash,
+    Version: Clone + Eq + Hash,
+{
+    #[must_use]
+    pub const fn new(histories: &'a [Session<Variable, Version>]) -> Self {
+        Self {
+            histories,
+            causal: None,
+            backend: Backend::Linearization,
+            search: Search {
+                canonical: false,
+                memo_capacity: None,
+            },
+            operations: false,
+        }
+    }
+
+    /// Decides serializability with `backend`.
+    #[must_use]
+    pub const fn with_backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Returns the lexicographically smallest commit orders as witnesses, whatever the backend.
+    /// See [`crate::solver::canonical`].
+    #[must_use]
+    pub const fn with_canonical_witness(mut self, canonical: bool) -> Self {
+        self.search.canonical = canonical;
+        self
+    }
+
+    /// Returns the commit orders expanded into the events of their transactions as witnesses. See
+    /// [`Witness::into_operation_order`].
+    ///
+    /// An operation witness is not accepted by [`verify_witness`], which replays the transactions.
+    #[must_use]
+    pub const fn with_operation_witness(mut self, operations: bool) -> Self {
+        self.operations = operations;
+        self
+    }
+
+    /// Bounds the memo of the linearization searches to about `capacity` sets of choices. See
+    /// [`LinearizationStepper::with_memo_capacity`].
+    #[must_use]
+    pub const fn with_memo_capacity(mut self, capacity: usize) -> Self {
+        self.search.memo_capacity = Some(capacity);
+        self
+    }
+
+    /// Checks the levels from causal consistency up on `po` instead of the causal partial order of
+    /// the history. `po` is the partial order of the same history, with a visibility relation
+    /// extended by external knowledge, such as with
+    /// [`AtomicTransactionPO::vis_includes_external`]. It is saturated again first.
+    ///
+    /// The levels weaker than causal consistency are still checked on the history alone.
+    #[must_use]
+    pub fn with_causal_po(mut self, po: AtomicTransactionPO<Variable>) -> Self {
+        self.causal = Some(saturate_causal(po));
+        self
+    }
+
+    /// Adds happened-before pairs known from outside the history, such as the order of commit
+    /// timestamps or log sequence numbers, to the causal partial order. See
+    /// [`CheckSession::with_causal_po`]. Hints from the real-time order of the transactions check
+    /// strict serializability.
+    ///
+    /// A hint naming a transaction that is not in the history makes every level from causal
+    /// consistency up fail with [`Error::UnknownTransaction`].
+    #[must_use]
+    pub fn with_order_hints(mut self, hints: &[OrderHint]) -> Self {
+        self.causal = Some(check_causal_read(self.histories).and_then(|mut po| {
+            let known = |id: &TransactionId| *id == po.root || po.history.0.contains_key(id);
+            if let Some(hint) = hints
+                .iter()
+                .find(|hint| !known(&hint.before) || !known(&hint.after))
+            {
+                let id = if known(&hint.before) {
+                    hint.after
+                } else {
+                    hint.before
+                };
+                return Err(Error::UnknownTransaction(id));
+            }
+            po.vis_includes_external(hints.iter().map(|hint| (hint.before, hint.after)));
+            saturate_causal(po)
+        }));
+        self
+    }
+
+    /// Returns the causal partial order, saturating it on first use.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of [`check_causal_read`].
+    pub fn causal_po(
+        &mut self,
+    ) -> Result<&AtomicTransactionPO<Variable>, Error<Variable, Version>> {
+        self.causal
+            .get_or_insert_with(|| check_causal_read(self.histories))
+            .as_ref()
+            .map_err(Clone::clone)
+    }
+
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the history does not satisfy `level`.
+    pub fn check(&mut self, level: Consistency) -> Result<Witness, Error<Variable, Version>> {
+        self.check_with_report(level).result
+    }
+
+    /// Checks `level` like [`CheckSession::check`], and reports the work of the linearization
+    /// search, if the level needs one.
+    pub fn check_with_report(&mut self, level: Consistency) -> CheckReport<Variable, Version> {
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!(
+            "check",
+            consistency = %level,
+            valid = tracing::field::Empty,
+            pruned = tracing::field::Empty,
+            placed = tracing::field::Empty,
+            rejected = tracing::field::Empty,
+            backtracks = tracing::field::Empty,
+            memo_hits = tracing::field::Empty,
+            memo_evictions = tracing::field::Empty,
+        );
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+        let saturated = |result: Result<(), Error<Variable, Version>>| CheckReport {
+            result: result.map(|()| Witness::Saturated),
+            stats: SearchStats::default(),
+            reduction: Reduction::default(),
+        };
+        let report = match level {
+            Consistency::CommittedRead => saturated(check_committed_read(self.histories)),
+            // causal consistency implies repeatable read and atomic read
+            Consistency::RepeatableRead => match &self.causal {
+                Some(Ok(_)) => saturated(Ok(())),
+                _ => saturated(check_repeatable_read(self.histories)),
+            },
+            Consistency::AtomicRead => match &self.causal {
+                Some(Ok(_)) => saturated(Ok(())),
+                _ => saturated(check_atomic_read(self.histories).map(|_| ())),
+            },
+            Consistency::Causal => saturated(self.causal_po().map(|_| ())),
+            Consistency::Prefix => self.linearize(level, |po, search| {
+                let (order, stats) = search.run(PrefixConsistencySolver::from(po));
+                (order.map(Witness::SplitCommitOrder), stats)
+            }),
+            Consistency::SnapshotIsolation => self.linearize(level, |po, search| {
+                let (order, stats) = search.run(SnapshotIsolationSolver::from(po));
+                (order.map(Witness::SplitCommitOrder), stats)
+            }),
+            Consistency::Serializable => match self.backend {
+                Backend::Polygraph if !self.search.canonical => self.linearize(level, |po, _| {
+                    (
+                        serializable_order(&po).map(Witness::CommitOrder),
+                        SearchStats::default(),
+                    )
+                }),
+                _ => self.linearize(level, |po, search| {
+                    let (order, stats) = search.run(SerializabilitySolver::from(po));
+                    (order.map(Witness::CommitOrder), stats)
+                }),
+            },
+        };
+        #[cfg(feature = "tracing")]
+        {
+            span.record("valid", report.result.is_ok());
+            span.record("pruned", report.reduction.pruned);
+            report.stats.record(&span);
+        }
+        if self.operations {
+            return CheckReport {
+                result: report
+                    .result
+                    .map(|witness| witness.into_operation_order(self.histories)),
+                ..report
+            };
+        }
+        report
+    }
+
+    /// Starts a check of `level` that runs in chunks, like [`CheckSession::check_with_report`].
+    ///
+    /// Only the linearization searches of prefix consistency, snapshot isolation and
+    /// serializability are split in chunks. The other levels, the causal partial order, and the
+    /// canonical and polygraph searches, which are not resumable, are checked here at once.
+    pub fn check_chunked(&mut self, level: Consistency) -> ChunkedCheck<'a, Variable, Version> {
+        let resumable = !self.search.canonical
+            && match level {
+                Consistency::Prefix | Consistency::SnapshotIsolation => true,
+                Consistency::Serializable => self.backend == Backend::Linearization,
+                _ => false,
+            };
+        let po = if resumable {
+            self.causal_po().ok().cloned()
+        } else {
+            None
+        };
+        let Some(mut po) = po else {
+            return ChunkedCheck {
+                level,
+                state: Chunked::Done(self.check_with_report(level)),
+                operations: None,
+            };
+        };
+        let pruning = prune(&mut po);
+        let state = match level {
+            Consistency::Prefix => Chunked::Prefix(
+                self.search.stepper(PrefixConsistencySolver::from(po)),
+                pruning,
+            ),
+            Consistency::SnapshotIsolation => Chunked::SnapshotIsolation(
+                self.search.stepper(SnapshotIsolationSolver::from(po)),
+                pruning,
+            ),
+            _ => Chunked::Serializable(
+                self.search.stepper(SerializabilitySolver::from(po)),
+                pruning,
+            ),
+        };
+        ChunkedCheck {
+            level,
+            state,
+            operations: self.operations.then_some(self.histories),
+        }
+    }
+
+    /// Checks a consistency level defined by a custom solver, built by `solver_factory` from the
+    /// causal partial order. Returns the linearization the solver finds, if any.
+    ///
+    /// The partial order is not pruned: pruning is sound only for the levels of [`Consistency`].
+    /// A custom level is checked only on causally consistent histories.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of [`check_causal_read`] if the history is not causally consistent.
+    pub fn check_custom<S, F>(
+        &mut self,
+        solver_factory: F,
+    ) -> Result<Option<Vec<S::Vertex>>, Error<Variable, Version>>
+    where
+        S: ConstrainedLinearizationSolver,
+        F: FnOnce(AtomicTransactionPO<Variable>) -> S,
+    {
+        let search = self.search;
+        let po = self.causal_po()?.clone();
+        Ok(search.run(solver_factory(po)).0)
+    }
+
+    /// Searches a linearization of the pruned causal partial order with `linearize`.
+    fn linearize(
+        &mut self,
+        level: Consistency,
+        linearize: impl FnOnce(AtomicTransactionPO<Variable>, Search) -> (Option<Witness>, SearchStats),
+    ) -> CheckReport<Variable, Version> {
+        let search = self.search;
+        match self.causal_po() {
+            Ok(po) => {
+                let mut po = po.clone();
+                let pruning = prune(&mut po);
+                let (witness, stats) = linearize(po, search);
+                let witness = witness.map(|witness| restore(&pruning, witness));
+                CheckReport {
+                    result: witness.ok_or(Error::Invalid(level)),
+                    stats,
+                    reduction: pruning.reduction(),
+                }
+            }
+            Err(error) => CheckReport {
+                result: Err(error),
+                stats: SearchStats::default(),
+                reduction: Reduction::default(),
+            },
+        }
+    }
+}
+
+/// Puts the transactions removed by `pruning` back in a witness.
+fn restore(pruning: &Pruning, witness: Witness) -> Witness {
+    match witness {
+        Witness::Saturated => Witness::Saturated,
+        Witness::CommitOrder(order) => {
+            Witness::CommitOrder(pruning.restore(order, |id| *id, |id| vec![id]))
+        }
+        Witness::SplitCommitOrder(order) => Witness::SplitCommitOrder(pruning.restore(
+            order,
+            |(id, _)| *id,
+            |id| vec![(id, false), (id, true)],
+        )),
+        Witness::OperationOrder(order) => Witness::OperationOrder(order),
+    }
+}
+
+/// How a linearization is searched.
+#[derive(Debug, Clone, Copy)]
+struct Search {
+    canonical: bool,
+    memo_capacity: Option<usize>,
+}
+
+impl Search {
+    /// Runs the search of `solver`, or the canonical search, which reports no counters.
+    fn run<S>(self, mut solver: S) -> (Option<Vec<S::Vertex>>, SearchStats)
+    where
+        S: ConstrainedLinearizationSolver,
+    {
+        if self.canonical {
+            return (canonical_linearization(&mut solver), SearchStats::default());
+        }
+        self.stepper(solver).run_with_stats()
+    }
+
+    /// Starts the search of `solver`, which it owns. The canonical search is not resumable.
+    fn stepper<'a, S>(self, solver: S) -> LinearizationStepper<'a, S>
+    where
+        S: ConstrainedLinearizationSolver,
+    {
+        let stepper = LinearizationStepper::owned(solver);
+        match self.memo_capacity {
+            Some(capacity) => stepper.with_memo_capacity(capacity),
+            None => stepper,
+        }
+    }
+}
+
+/// A check of a consistency level that runs in chunks of bounded work, so that the caller can
+/// yield between them, such as to an event loop. See [`CheckSession::check_chunked`].
+#[derive(Debug)]
+pub struct ChunkedCheck<'a, Variable, Version>
+where
+    Variable: Clone + Eq + Ord + Hash,
+{
+    level: Consistency,
+    state: Chunked<'a, Variable, Version>,
+    /// The history to expand the witness with, for operation witnesses
+    operations: Option<&'a [Session<Variable, Version>]>,
+}
+
+/// The linearization search of a chunked check, with the pruning before it, or its outcome.
+#[derive(Debug)]
+enum Chunked<'a, Variable, Version>
+where
+    Variable: Clone + Eq + Ord + Hash,
+{
+    Prefix(
+        LinearizationStepper<'a, PrefixConsistencySolver<Variable>>,
+        Pruning,
+    ),
+    SnapshotIsolation(
+        LinearizationStepper<'a, SnapshotIsolationSolver<Variable>>,
+        Pruning,
+    ),
+    Serializable(
+        LinearizationStepper<'a, SerializabilitySolver<Variable>>,
+        Pruning,
+    ),
+    Done(CheckReport<Variable, Version>),
+}
+
+impl<Variable, Version> ChunkedCheck<'_, Variable, Version>
+where
+    Variable: Clone + Eq + Ord + Hash,
+    Version: Clone,
+{
+    /// Takes at most `budget` steps of the linearization search. Returns the report of the check
+    /// once it is over, and on every later call, or `None` if the budget runs out before.
+    pub fn step(&mut self, budget: u64) -> Option<CheckReport<Variable, Version>> {
+        let (witness, stats, pruning) = match &mut self.state {
+            Chunked::Done(report) => return Some(report.clone()),
+            Chunked::Prefix(stepper, pruning) => (
+                stepper.run_budget(budget)?.map(Witness::SplitCommitOrder),
+                stepper.stats(),
+                pruning,
+            ),
+            Chunked::SnapshotIsolation(stepper, pruning) => (
+                stepper.run_budget(budget)?.map(Witness::SplitCommitOrder),
+                stepper.stats(),
+                pruning,
+            ),
+            Chunked::Serializable(stepper, pruning) => (
+                stepper.run_budget(budget)?.map(Witness::CommitOrder),
+                stepper.stats(),
+                pruning,
+            ),
+        };
+        let report = CheckReport {
+            result: witness
+                .map(|witness| restore(pruning, witness))
+                .map(|witness| match self.operations {
+                    Some(histories) => witness.into_operation_order(histories),
+                    None => witness,
+                })
+                .ok_or(Error::Invalid(self.level)),
+            stats,
+            reduction: pruning.reduction(),
+        };
+        self.state = Chunked::Done(report.clone());
+        Some(report)
+    }
+
+    /// Returns the counters of the linearization search so far.
+    #[must_use]
+    pub const fn stats(&self) -> SearchStats {
+        match &self.state {
+            Chunked::Prefix(stepper, _) => stepper.stats(),
+            Chunked::SnapshotIsolation(stepper, _) => stepper.stats(),
+            Chunked::Serializable(stepper, _) => stepper.stats(),
+            Chunked::Done(report) => report.stats,
+        }
+    }
+}
+
+/// The outcome of a check, with the counters of its linearization search and of the pruning
+/// before it.
+#[derive(Debug, Clone)]
+pub struct CheckReport<Variable, Version> {
+    pub result: Result<Witness, Error<Variable, Version>>,
+    /// All zero for the levels checked by saturation alone
+    pub stats: SearchStats,
+    /// All zero for the levels checked by saturation alone
+    pub reduction: Reduction,
+}
+
+/// Checks a history against a single consistency level, and reports the work of the search.
+#[must_use]
+pub fn check_with_report<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    level: Consistency,
+) -> CheckReport<Variable, Version>
+where
+    Variable: Clone + Eq + Ord + Hash,
+    Version: Clone + Eq + Hash,
+{
+    CheckSession::new(histories).check_with_report(level)
+}
+
+/// Checks a history against a single consistency level.
+///
+/// # Errors
+///
+/// Returns an [`Error`] if the history does not satisfy `level`.
+pub fn check<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    level: Consistency,
+) -> Result<Witness, Error<Variable, Version>>
+where
+    Variable: Clone + Eq + Ord + Hash,
+    Version: Clone + Eq + Hash,
+{
+    CheckSession::new(histories).check(level)
+}
+
+/// Checks a history against a single consistency level on its [interned](intern) copy, which
+/// saves cloning and hashing large variables and versions, such as strings.
+///
+/// # Errors
+///
+/// Returns an [`Error`] if the history does not satisfy `level`, in terms of the original
+/// variables and versions.
+pub fn check_interned<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    level: Consistency,
+) -> Result<Witness, Error<Variable, Version>>
+where
+    Variable: Clone + Eq + Hash,
+    Version: Clone + Eq + Hash,
+{
+    let (interned, table) = intern(histories);
+    check(&interned, level).map_err(|error| table.error(error))
+}
+
+/// Checks a history against a single consistency level, with its reads of uninitialized variables
+/// interpreted under `policy`. See [`InitialValuePolicy::apply`].
+///
+/// # Errors
+///
+/// Returns an [`Error`] if the history does not satisfy `level` under `policy`.
+pub fn check_with_initial_values<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    level: Consistency,
+    policy: InitialValuePolicy,
+) -> Result<Witness, Error<Variable, Version>>
+where
+    Variable: Clone + Eq + Ord + Hash,
+    Version: Clone + Eq + Hash,
+{
+    check(&policy.apply(histories)?, level)
+}
+
+/// Checks a history against a custom consistency level. See [`CheckSession::check_custom`].
+///
+/// # Errors
+///
+/// Returns the error of [`check_causal_read`] if the history is not causally consistent.
+pub fn check_custom<Variable, Version, S, F>(
+    histories: &[Session<Variable, Version>],
+    solver_factory: F,
+) -> Result<Option<Vec<S::Vertex>>, Error<Variable, Version>>
+where
+    Variable: Clone + Eq + Ord + Hash,
+    Version: Clone + Eq + Hash,
+    S: ConstrainedLinearizationSolver,
+    F: FnOnce(AtomicTransactionPO<Variable>) -> S,
+{
+    CheckSession::new(histories).check_custom(solver_factory)
+}
+
+/// Replaces a witness of `level` by the canonical one, so that the witnesses of different searches
+/// compare equal. See [`CheckSession::with_canonical_witness`].
+///
+/// A witness of saturation, or a witness of a history that does not satisfy `level`, is returned
+/// as is.
+#[must_use]
+pub fn normalize_witness<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    level: Consistency,
+    witness: Witness,
+) -> Witness
+where
+    Variable: Clone + Eq + Ord + Hash,
+    Version: Clone + Eq + Hash,
+{
+    match witness {
+        Witness::Saturated => witness,
+        _ => CheckSession::new(histories)
+            .with_canonical_witness(true)
+            .check(level)
+            .unwrap_or(witness),
+    }
+}
+
+/// Returns true if `witness` shows that the history satisfies `level`: a commit order is replayed
+/// by the search of `level`, without backtracking, and a saturation witness is checked again.
+#[must_use]
+pub fn verify_witness<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    level: Consistency,
+    witness: &Witness,
+) -> bool
+where
+    Variable: Clone + Eq + Ord + Hash,
+    Version: Clone + Eq + Hash,
+{
+    match (level, witness) {
+        (
+            Consistency::CommittedRead
+            | Consistency::RepeatableRead
+            | Consistency::AtomicRead
+            | Consistency::Causal,
+            Witness::Saturated,
+        ) => check(histories, level).is_ok(),
+        (Consistency::Prefix, Witness::SplitCommitOrder(order)) => check_causal_read(histories)
+            .is_ok_and(|po| replays(PrefixConsistencySolver::from(po), order)),
+        (Consistency::SnapshotIsolation, Witness::SplitCommitOrder(order)) => {
+            check_causal_read(histories)
+                .is_ok_and(|po| replays(SnapshotIsolationSolver::from(po), order))
+        }
+        (Consistency::Serializable, Witness::CommitOrder(order)) => check_causal_read(histories)
+            .is_ok_and(|po| replays(SerializabilitySolver::from(po), order)),
+        _ => false,
+    }
+}
+
+/// Returns true if `order` places every vertex of `solver`, each allowed in turn.
+fn replays<S>(mut solver: S, order: &[S::Vertex]) -> bool
+where
+    S: ConstrainedLinearizationSolver,
+{
+    LinearizationStepper::with_prefix(&mut solver, order)
+        .is_some_and(|stepper| stepper.is_complete())
+}
+
+/// The differences between two witnesses of the same history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WitnessDiff {
+    /// Whether each witness passes [`verify_witness`]
+    pub valid: [bool; 2],
+    /// The pairs of transactions that the witnesses commit in opposite orders, as ordered by the
+    /// first witness
+    pub inversions: Vec<(TransactionId, TransactionId)>,
+}
+
+/// Compares two witnesses of `level` for the same history.
+///
+/// A transaction commits at its position in a commit order, at its write section in a split
+/// commit order, or at its last event in an operation order.
+#[must_use]
+pub fn diff_witnesses<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    level: Consistency,
+    first: &Witness,
+    second: &Witness,
+) -> WitnessDiff
+where
+    Variable: Clone + Eq + Ord + Hash,
+    Version: Clone + Eq + Hash,
+{
+    let second_position: HashMap<TransactionId, usize> = commits(second)
+        .into_iter()
+        .enumerate()
+        .map(|(i, id)| (id, i))
+        .collect();
+    // the transactions of both witnesses, in the first commit order, with their second position
+    let common: Vec<(TransactionId, usize)> = commits(first)
+        .into_iter()
+        .filter_map(|id| Some((id, *second_position.get(&id)?)))
+        .collect();
+
+    let mut inversions = Vec::new();
+    for (i, (earlier, earlier_position)) in common.iter().enumerate() {
+        for (later, later_position) in &common[i + 1..] {
+            if later_position < earlier_position {
+                inversions.push((*earlier, *later));
+            }
+        }
+    }
+
+    WitnessDiff {
+        valid: [
+            verify_witness(histories, level, first),
+            verify_witness(histories, level, second),
+        ],
+        inversions,
+    }
+}
+
+/// Returns the transactions in commit order.
+fn commits(witness: &Witness) -> Vec<TransactionId> {
+    match witness {
+        Witness::Saturated => Vec::new(),
+        Witness::CommitOrder(order) => order.clone(),
+        Witness::SplitCommitOrder(order) => order
+            .iter()
+            .filter(|(_, write)| *write)
+            .map(|(id, _)| *id)
+            .collect(),
+        Witness::OperationOrder(order) => {
+            let mut committed = HashSet::new();
+            let mut commits: Vec<TransactionId> = order
+                .iter()
+                .rev()
+                .map(EventId::transaction_id)
+                .filter(|id| committed.insert(*id))
+                .collect();
+            commits.reverse();
+            commits
+        }
+    }
+}
+
+/// The part of a history a violation is found in.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scope<Variable> {
+    /// The whole history, for the errors of [`validate_history`]
+    History,
+    /// A connected component of the communication graph. See [`session_components`].
+    Sessions(Vec<u64>),
+    /// The events on a single variable of a component
+    Variable(Variable),
+}
+
+/// A violation of a consistency level, with the part of the history that shows it.
+#[derive(Debug, Clone)]
+pub struct Violation<Variable, Version> {
+    pub scope: Scope<Variable>,
+    pub error: Error<Variable, Version>,
+}
+
+/// Checks a history against a consistency level, and returns every violation instead of the first
+/// one. The history satisfies `level` if and only if there is none.
+///
+/// A history with structural errors has them all returned, as it can not be checked further.
+/// Otherwise, each component of the communication graph is checked on its own, and the events of
+/// each variable of a violating component are checked on their own too. The transaction ids of the
+/// errors are those of the whole history.
+#[must_use]
+pub fn check_exhaustive<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    level: Consistency,
+) -> Vec<Violation<Variable, Version>>
+where
+    Variable: Clone + Eq + Ord + Hash,
+    Version: Clone + Eq + Hash,
+{
+    let structural = validate_history(histories);
+    if !structural.is_empty() {
+        return structural
+            .into_iter()
+            .map(|error| Violation {
+                scope: Scope::History,
+                error: error.into(),
+            })
+            .collect();
+    }
+
+    let mut violations = Vec::new();
+    for sessions in session_components(histories) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("component", sessions = ?sessions).entered();
+        // the other sessions are emptied rather than dropped, to keep the transaction ids
+        let component: Vec<Session<Variable, Version>> = (1..)
+            .zip(histories)
+            .map(|(session_id, session)| {
+                if sessions.binary_search(&session_id).is_ok() {
+                    session.clone()
+                } else {
+                    Vec::new()
+                }
+            })
+            .collect();
+        let Err(error) = check(&component, level) else {
+            continue;
+        };
+        violations.push(Violation {
+            scope: Scope::Sessions(sessions),
+            error,
+        });
+
+        let variables: BTreeSet<Variable> = component
+            .iter()
+            .flatten()
+            .flat_map(|transaction| &transaction.events)
+            .map(Event::variable)
+            .collect();
+        for variable in variables {
+            let projected = project_variables(&component, &[variable.clone()].into());
+            if let Err(error) = check(&projected, level) {
+                violations.push(Violation {
+                    scope: Scope::Variable(variable),
+                    error,
+                });
+            }
+        }
+    }
+    violations
+}
+
+/// The strongest level a history satisfies and the weakest one it violates, adjacent in
+/// [`Consistency::LEVELS`].
+#[derive(Debug, Clone)]
+pub struct Classification<Variable, Version> {
+    /// `None` if the history violates even read committed
+    pub strongest: Option<(Consistency, Witness)>,
+    /// `None` if the history is serializable
+    pub weakest_violated: Option<(Consistency, Error<Variable, Version>)>,
+}
+
+/// Classifies a history on the hierarchy of [`Consistency::LEVELS`].
+///
+/// As each level implies the weaker ones, the levels are binary searched, on a single
+/// [`CheckSession`] that saturates the causal partial order once.
+#[must_use]
+pub fn strongest_level<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+) -> Classification<Variable, Version>
+where
+    Variable: Clone + Eq + Ord + Hash,
+    Version: Clone + Eq + Hash,
+{
+    let mut session = CheckSession::new(histories);
+    let mut results: Vec<Option<Result<Witness, Error<Variable, Version>>>> =
+        Consistency::LEVELS.iter().map(|_| None).collect();
+    let mut check = |i: usize| {
+        results[i]
+            .get_or_insert_with(|| session.check(Consistency::LEVELS[i]))
+            .is_ok()
+    };
+
+    // the first violated level is in `low..=high`, and `LEVELS.len()` stands for none
+    let (mut low, mut high) = (0, Consistency::LEVELS.len());
+    while low < high {
+        let middle = (low + high) / 2;
+        if check(middle) {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
+    if let Some(i) = low.checked_sub(1) {
+        check(i);
+    }
+    if low < Consistency::LEVELS.len() {
+        check(low);
+    }
+
+    let mut result = |i: usize| results.get_mut(i)?.take();
+    Classification {
+        strongest: low
+            .checked_sub(1)
+            .and_then(|i| Some((Consistency::LEVELS[i], result(i)?.ok()?))),
+        weakest_violated: result(low).and_then(|r| Some((Consistency::LEVELS[low], r.err()?))),
+    }
+}
+
+/// The earliest prefix of a history that violates a consistency level.
+#[derive(Debug, Clone)]
+pub struct PrefixViolation<Variable, Version> {
+    /// Number of transactions of each session in the prefix
+    pub cut: Vec<u64>,
+    /// The transaction whose addition made the violation detectable
+    pub last: TransactionId,
+    pub error: Error<Variable, Version>,
+}
+
+/// Checks the prefixes of a history in [`round_order`]. See [`check_prefixes_in_order`].
+#[must_use]
+pub fn check_prefixes<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    level: Consistency,
+) -> Option<PrefixViolation<Variable, Version>>
+where
+    Variable: Clone + Eq + Ord + Hash,
+    Version: Clone + Eq + Hash,
+{
+    check_prefixes_in_order(histories, level, round_order(histories))
+}
+
+/// Adds the transactions one at a time in `order`, such as their commit times, and returns the
+/// first prefix that violates `level`, or `None` if the whole history satisfies it.
+///
+/// A transaction extends its session up to it. Prefixes with a read of a write that is not in the
+/// prefix yet are skipped, as the read is only explained by a later transaction.
+#[must_use]
+pub fn check_prefixes_in_order<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    level: Consistency,
+    order: impl IntoIterator<Item = TransactionId>,
+) -> Option<PrefixViolation<Variable, Version>>
+where
+    Variable: Clone + Eq + Ord + Hash,
+    Version: Clone + Eq + Hash,
+{
+    let mut cut: Vec<u64> = histories.iter().map(|_| 0).collect();
+    let mut last = TransactionId::root();
+
+    for id in order {
+        let Some(length) = usize::try_from(id.session_id)
+            .ok()
+            .and_then(|session_id| cut.get_mut(session_id.checked_sub(1)?))
+        else {
+            continue;
+        };
+        *length = (*length).max(id.session_height + 1);
+        last = id;
+
+        let prefix: Vec<Session<Variable, Version>> = histories
+            .iter()
+            .zip(&cut)
+            .map(|(session, length)| {
+                session
+                    .iter()
+                    .take(usize::try_from(*length).unwrap_or(usize::MAX))
+                    .cloned()
+                    .collect()
+            })
+            .collect();
+        match check(&prefix, level) {
+            Ok(_) | Err(Error::NonAtomic(NonAtomicError::IncompleteHistory { .. })) => {}
+            Err(error) => {
+                return Some(PrefixViolation {
+                    cut: cut.clone(),
+                    last,
+                    error,
+                })
+            }
+        }
+    }
+
+    // the whole history may not have been covered by `order`
+    check(histories, level).err().map(|error| PrefixViolation {
+        cut: histories
+            .iter()
+            .map(|session| session.len() as u64)
+            .collect(),
+        last,
+        error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::non_atomic::types::{Event, Transaction};
+
+    #[test]
+    fn test_empty_history() {
+        let histories: Vec<Session<&str, u64>> = Vec::new();
+        for level in Consistency::LEVELS {
+            assert!(check(&histories, level).is_ok(), "{level:?}");
+            for backend in [Backend::Linearization, Backend::Polygraph] {
+                let mut session = CheckSession::new(&histories)
+                    .with_backend(backend)
+                    .with_canonical_witness(backend == Backend::Polygraph);
+                assert!(session.check(level).is_ok(), "{level:?} {backend:?}");
+            }
+        }
+        let classification = strongest_level(&histories);
+        assert!(matches!(
+            classification.strongest,
+            Some((Consistency::Serializable, _))
+        ));
+        assert!(classification.weakest_violated.is_none());
+        assert!(crate::delta::DeltaState::prepare(histories, Consistency::Serializable).is_ok());
+    }
+
+    #[test]
+    fn test_strongest_level() {
+        let write_skew = vec![
+            vec![Transaction::committed(vec![
+                Event::read_empty("x"),
+                Event::read_empty("y"),
+                Event::write("x", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read_empty("x"),
+                Event::read_empty("y"),
+                Event::write("y", 1),
+            ])],
+        ];
+        let classification = strongest_level(&write_skew);
+        assert!(matches!(
+            classification.strongest,
+            Some((Consistency::SnapshotIsolation, Witness::SplitCommitOrder(_)))
+        ));
+        assert!(matches!(
+            classification.weakest_violated,
+            Some((Consistency::Serializable, _))
+        ));
+
+        let serializable = vec![vec![Transaction::committed(vec![Event::write("x", 1)])]];
+        let classification = strongest_level(&serializable);
+        assert!(matches!(
+            classification.strongest,
+            Some((Consistency::Serializable, Witness::CommitOrder(_)))
+        ));
+        assert!(classification.weakest_violated.is_none());
+
+        // a read of a version nobody writes violates every level
+        let invalid = vec![vec![Transaction::committed(vec![Event::read("x", 1)])]];
+        let classification = strongest_level(&invalid);
+        assert!(classification.strongest.is_none());
+        assert!(matches!(
+            classification.weakest_violated,
+            Some((Consistency::CommittedRead, Error::NonAtomic(_)))
+        ));
+    }
+
+    #[test]
+    fn test_aborted_write() {
+        // s1.t1 aborts, so s1.t2 does not miss its write of x
+        let histories = vec![
+            vec![
+                Transaction::committed(vec![Event::read("x", 1)]),
+                Transaction::uncommitted(vec![Event::write("x", 2)]),
+                Transaction::committed(vec![Event::read("x", 1)]),
+            ],
+            vec![Transaction::committed(vec![Event::write("x", 1)])],
+        ];
+        for level in Consistency::LEVELS {
+            assert!(check(&histories, level).is_ok(), "{level}");
+        }
+    }
+
+    #[test]
+    fn test_check_session() {
+        // write skew: snapshot isolation, but not serializable
+        let histories = vec![
+            vec![Transaction::committed(vec![
+                Event::read_empty("x"),
+                Event::read_empty("y"),
+                Event::write("x", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read_empty("x"),
+                Event::read_empty("y"),
+                Event::write("y", 1),
+            ])],
+        ];
+
+        let mut session = CheckSession::new(&histories);
+
+        for level in [
+            Consistency::CommittedRead,
+            Consistency::RepeatableRead,
+            Consistency::Causal,
+            Consistency::AtomicRead,
+        ] {
+            assert!(matches!(session.check(level), Ok(Witness::Saturated)));
+        }
+        assert!(matches!(
+            session.check(Consistency::SnapshotIsolation),
+            Ok(Witness::SplitCommitOrder(order)) if !order.is_empty()
+        ));
+        assert!(matches!(
+            session.check(Consistency::Serializable),
+            Err(Error::Invalid(Consistency::Serializable))
+        ));
+
+        let report = session.check_with_report(Consistency::Serializable);
+        assert!(report.result.is_err());
+        assert!(report.stats.placed + report.stats.rejected > 0);
+        assert_eq!(report.stats.placed, report.stats.backtracks);
+        assert_eq!(report.reduction.transactions, 2);
+
+        assert_eq!(Witness::Saturated.to_string(), "saturated");
+        assert_eq!(
+            Witness::SplitCommitOrder(vec![
+                (TransactionId::root(), true),
+                (
+                    TransactionId {
+                        session_id: 1,
+                        session_height: 0,
+                    },
+                    false,
+                ),
+            ])
+            .to_string(),
+            "root:w < s1.t0:r"
+        );
+
+        let mut polygraph = CheckSession::new(&histories).with_backend(Backend::Polygraph);
+        assert!(matches!(
+            polygraph.check(Consistency::Serializable),
+            Err(Error::Invalid(Consistency::Serializable))
+        ));
+        assert_eq!(
+            session.check_with_report(Consistency::Causal).stats,
+            SearchStats::default()
+        );
+    }
+
+    /// Serializability, with the transactions in increasing order of session
+    struct SessionSerial(SerializabilitySolver<&'static str>);
+
+    impl ConstrainedLinearizationSolver for SessionSerial {
+        type Vertex = TransactionId;
+
+        fn get_root(&self) -> Self::Vertex {
+            self.0.get_root()
+        }
+
+        fn children_of(&self, source: &Self::Vertex) -> Option<Vec<Self::Vertex>> {
+            self.0.children_of(source)
+        }
+
+        fn allow_next(&self, linearization: &[Self::Vertex], v: &Self::Vertex) -> bool {
+            linearization
+                .last()
+                .map_or(true, |last| last.session_id <= v.session_id)
+                && self.0.allow_next(linearization, v)
+        }
+
+        fn vertices(&self) -> Vec<Self::Vertex> {
+            self.0.vertices()
+        }
+
+        fn forward_book_keeping(&mut self, linearization: &[Self::Vertex]) {
+            self.0.forward_book_keeping(linearization);
+        }
+
+        fn backtrack_book_keeping(&mut self, linearization: &[Self::Vertex]) {
+            self.0.backtrack_book_keeping(linearization);
+        }
+    }
+
+    #[test]
+    fn test_check_custom() {
+        let session_serial = |po| SessionSerial(SerializabilitySolver::from(po));
+
+        let histories = vec![
+            vec![Transaction::committed(vec![Event::write("x", 1)])],
+            vec![Transaction::committed(vec![Event::read("x", 1)])],
+        ];
+        assert!(matches!(
+            check_custom(&histories, session_serial),
+            Ok(Some(order)) if order.len() == 2
+        ));
+
+        // serializable, but only with the second session first
+        let histories = vec![
+            vec![Transaction::committed(vec![Event::read("x", 1)])],
+            vec![Transaction::committed(vec![Event::write("x", 1)])],
+        ];
+        let mut session = CheckSession::new(&histories);
+        assert!(matches!(session.check_custom(session_serial), Ok(None)));
+        assert!(session.check(Consistency::Serializable).is_ok());
+
+        let histories = vec![vec![Transaction::committed(vec![Event::read("x", 1)])]];
+        assert!(matches!(
+            check_custom(&histories, session_serial),
+            Err(Error::NonAtomic(_))
+        ));
+    }
+
+    #[test]
+    fn test_check_chunked() {
+        // write skew, with a third transaction so that the search takes several steps
+        let histories = vec![
+            vec![Transaction::committed(vec![
+                Event::read_empty("x"),
+                Event::read_empty("y"),
+                Event::write("x", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read_empty("x"),
+                Event::read_empty("y"),
+                Event::write("y", 1),
+            ])],
+            vec![Transaction::committed(vec![Event::write("z", 1)])],
+        ];
+        let mut session = CheckSession::new(&histories);
+
+        for level in [Consistency::SnapshotIsolation, Consistency::Serializable] {
+            let mut chunked = session.check_chunked(level);
+            let mut chunks = 1;
+            let report = loop {
+                if let Some(report) = chunked.step(1) {
+                    break report;
+                }
+                chunks += 1;
+            };
+            assert!(chunks > 1);
+            assert_eq!(
+                report.result.is_ok(),
+                session.check(level).is_ok(),
+                "{level:?}"
+            );
+            assert_eq!(report.stats, session.check_with_report(level).stats);
+            assert_eq!(chunked.stats(), report.stats);
+            assert!(chunked.step(0).is_some());
+        }
+
+        let mut chunked = session.check_chunked(Consistency::Causal);
+        assert!(matches!(
+            chunked.step(0),
+            Some(CheckReport {
+                result: Ok(Witness::Saturated),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_operation_witness() {
+        let event = |session_id, transaction_height| EventId {
+            session_id,
+            session_height: 0,
+            transaction_height,
+        };
+        // s2 reads its own write of x after writing it
+        let histories = vec![
+            vec![Transaction::committed(vec![Event::write("x", 1)])],
+            vec![Transaction::committed(vec![
+                Event::read("x", 1),
+                Event::write("x", 2),
+                Event::read("x", 2),
+                Event::read_empty("y"),
+            ])],
+            vec![Transaction::committed(vec![Event::write("y", 1)])],
+        ];
+        let mut session = CheckSession::new(&histories).with_operation_witness(true);
+
+        let Ok(Witness::OperationOrder(order)) = session.check(Consistency::Serializable) else {
+            panic!("expected an operation order");
+        };
+        assert_eq!(order.len(), 6);
+        assert_eq!(
+            order[..5],
+            [
+                event(1, 0),
+                event(2, 0),
+                event(2, 1),
+                event(2, 2),
+                event(2, 3)
+            ]
+        );
+
+        // the reads of s2 but its own are placed at its read section
+        let Ok(Witness::OperationOrder(order)) = session.check(Consistency::SnapshotIsolation)
+        else {
+            panic!("expected an operation order");
+        };
+        let position = |id| order.iter().position(|event| *event == id).unwrap();
+        assert!(position(event(2, 0)) < position(event(2, 3)));
+        assert!(position(event(2, 3)) < position(event(2, 1)));
+        assert_eq!(position(event(2, 1)) + 1, position(event(2, 2)));
+
+        let mut chunked = session.check_chunked(Consistency::SnapshotIsolation);
+        let report = loop {
+            if let Some(report) = chunked.step(1) {
+                break report;
+            }
+        };
+        assert!(matches!(report.result, Ok(Witness::OperationOrder(_))));
+        assert!(matches!(
+            session.check(Consistency::Causal),
+            Ok(Witness::Saturated)
+        ));
+
+        let witness = Witness::OperationOrder(vec![event(1, 0), event(2, 0), event(2, 1)]);
+        assert_eq!(witness.to_string(), "s1.t0.e0 < s2.t0.e0 < s2.t0.e1");
+        // a transaction commits at its last event
+        let commit_order = Witness::CommitOrder(vec![
+            TransactionId {
+                session_id: 1,
+                session_height: 0,
+            },
+            TransactionId {
+                session_id: 2,
+                session_height: 0,
+            },
+        ]);
+        let diff = diff_witnesses(
+            &histories,
+            Consistency::Serializable,
+            &witness,
+            &commit_order,
+        );
+        assert!(diff.inversions.is_empty());
+        assert_eq!(diff.valid, [false, false]);
+    }
+
+    #[test]
+    fn test_normalize_witness() {
+        let histories = vec![
+            vec![
+                Transaction::committed(vec![Event::write("x", 1)]),
+                Transaction::committed(vec![Event::read("x", 2), Event::write("y", 1)]),
+            ],
+            vec![Transaction::committed(vec![
+                Event::read("x", 1),
+                Event::write("x", 2),
+            ])],
+            vec![Transaction::committed(vec![Event::write("z", 1)])],
+        ];
+
+        let linearization = check(&histories, Consistency::Serializable).unwrap();
+        let polygraph = CheckSession::new(&histories)
+            .with_backend(Backend::Polygraph)
+            .check(Consistency::Serializable)
+            .unwrap();
+        let canonical = CheckSession::new(&histories)
+            .with_backend(Backend::Polygraph)
+            .with_canonical_witness(true)
+            .check(Consistency::Serializable)
+            .unwrap();
+
+        assert_eq!(canonical.to_string(), "s1.t0 < s2.t0 < s1.t1 < s3.t0");
+        assert_eq!(
+            normalize_witness(&histories, Consistency::Serializable, linearization),
+            canonical
+        );
+        assert_eq!(
+            normalize_witness(&histories, Consistency::Serializable, polygraph),
+            canonical
+        );
+    }
+
+    #[test]
+    fn test_diff_witnesses() {
+        let histories = vec![
+            vec![Transaction::committed(vec![Event::write("x", 1)])],
+            vec![Transaction::committed(vec![Event::write("y", 1)])],
+            vec![Transaction::committed(vec![Event::read("x", 1)])],
+        ];
+        let id = |session_id| TransactionId {
+            session_id,
+            session_height: 0,
+        };
+
+        let witness = check(&histories, Consistency::Serializable).unwrap();
+        assert!(verify_witness(
+            &histories,
+            Consistency::Serializable,
+            &witness
+        ));
+
+        let first = Witness::CommitOrder(vec![id(1), id(2), id(3)]);
+        let second = Witness::CommitOrder(vec![id(2), id(3), id(1)]);
+        let diff = diff_witnesses(&histories, Consistency::Serializable, &first, &second);
+        assert_eq!(diff.valid, [true, false]);
+        assert_eq!(diff.inversions, vec![(id(1), id(2)), (id(1), id(3))]);
+
+        assert!(!verify_witness(
+            &histories,
+            Consistency::Serializable,
+            &Witness::CommitOrder(vec![id(1), id(2)])
+        ));
+        assert!(!verify_witness(
+            &histories,
+            Consistency::Causal,
+            &Witness::CommitOrder(vec![id(1), id(2), id(3)])
+        ));
+    }
+
+    #[test]
+    fn test_with_causal_po() {
+        // s2.t0 reads the initial version of x, so it is serialized before s1.t0
+        let histories = vec![
+            vec![Transaction::committed(vec![Event::write("x", 1)])],
+            vec![
+                Transaction::committed(vec![Event::read_empty("x")]),
+                Transaction::committed(vec![Event::write("y", 1)]),
+            ],
+        ];
+        let t = |session_id, session_height| TransactionId {
+            session_id,
+            session_height,
+        };
+        assert!(matches!(
+            check(&histories, Consistency::Serializable),
+            Ok(Witness::CommitOrder(order)) if order[0] == t(2, 0)
+        ));
+
+        // s2.t1 is known to commit before s1.t0
+        let mut po = check_causal_read(&histories).unwrap();
+        po.vis_includes_external([(t(2, 1), t(1, 0))]);
+        let mut session = CheckSession::new(&histories).with_causal_po(po);
+        assert!(matches!(
+            session.check(Consistency::Serializable),
+            Ok(Witness::CommitOrder(order)) if order == vec![t(2, 0), t(2, 1), t(1, 0)]
+        ));
+
+        // s1.t0 is known to commit before s2.t0, which does not see it
+        let mut po = check_causal_read(&histories).unwrap();
+        po.vis_includes_external([(t(1, 0), t(2, 0))]);
+        let mut session = CheckSession::new(&histories).with_causal_po(po);
+        assert!(session.check(Consistency::AtomicRead).is_ok());
+        assert!(matches!(
+            session.check(Consistency::Serializable),
+            Err(Error::Cycle {
+                level: Consistency::Causal,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_order_hints() {
+        // a stale read: s2.t0 starts after s1.t0 commits, but reads the initial version of x
+        let histories = vec![
+            vec![Transaction::committed(vec![Event::write("x", 1)])],
+            vec![Transaction::committed(vec![Event::read_empty("x")])],
+        ];
+        let t = |session_id| TransactionId {
+            session_id,
+            session_height: 0,
+        };
+        let hint = |before, after| OrderHint {
+            before: t(before),
+            after: t(after),
+        };
+        assert!(check(&histories, Consistency::Serializable).is_ok());
+
+        let mut session = CheckSession::new(&histories).with_order_hints(&[hint(2, 1)]);
+        assert!(session.check(Consistency::Serializable).is_ok());
+
+        let mut session = CheckSession::new(&histories).with_order_hints(&[hint(1, 2)]);
+        assert!(session.check(Consistency::CommittedRead).is_ok());
+        assert!(session.check(Consistency::Serializable).is_err());
+
+        let mut session = CheckSession::new(&histories).with_order_hints(&[hint(1, 3)]);
+        assert!(matches!(
+            session.check(Consistency::Causal),
+            Err(Error::UnknownTransaction(id)) if id == t(3)
+        ));
+    }
+
+    #[test]
+    fn test_check_exhaustive() {
+        let lost_update = |x| {
+            [1, 2].map(|version| {
+                vec![Transaction::committed(vec![
+                    Event::read_empty(x),
+                    Event::write(x, version),
+                ])]
+            })
+        };
+        // two lost updates, on x and on z, around a write skew on y and w
+        let mut histories = Vec::from(lost_update("x"));
+        histories.extend([
+            vec![Transaction::committed(vec![
+                Event::read_empty("y"),
+                Event::read_empty("w"),
+                Event::write("y", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read_empty("y"),
+                Event::read_empty("w"),
+                Event::write("w", 1),
+            ])],
+        ]);
+        histories.extend(lost_update("z"));
+
+        assert!(check_exhaustive(&histories, Consistency::Prefix).is_empty());
+        let scopes: Vec<_> = check_exhaustive(&histories, Consistency::SnapshotIsolation)
+            .into_iter()
+            .map(|violation| violation.scope)
+            .collect();
+        assert_eq!(
+            scopes,
+            vec![
+                Scope::Sessions(vec![1, 2]),
+                Scope::Variable("x"),
+                Scope::Sessions(vec![5, 6]),
+                Scope::Variable("z"),
+            ]
+        );
+        // the write skew shows on both variables together only
+        let violations = check_exhaustive(&histories, Consistency::Serializable);
+        assert_eq!(violations.len(), 5);
+        assert_eq!(violations[2].scope, Scope::Sessions(vec![3, 4]));
+
+        histories.push(vec![Transaction::committed(vec![Event::read("x", 3)])]);
+        histories.push(vec![Transaction::committed(vec![Event::read("z", 3)])]);
+        let violations = check_exhaustive(&histories, Consistency::SnapshotIsolation);
+        assert_eq!(violations.len(), 2);
+        assert!(violations
+            .iter()
+            .all(|violation| violation.scope == Scope::History
+                && matches!(
+                    violation.error,
+                    Error::NonAtomic(NonAtomicError::IncompleteHistory { .. })
+                )));
+    }
+
+    #[test]
+    fn test_check_prefixes() {
+        // the lost update is complete after the second round
+        let histories = vec![
+            vec![
+                Transaction::committed(vec![Event::write("x", 1)]),
+                Transaction::committed(vec![Event::read("x", 1), Event::write("x", 2)]),
+            ],
+            vec![
+                Transaction::committed(vec![Event::write("y", 1)]),
+                Transaction::committed(vec![Event::read("x", 1), Event::write("x", 3)]),
+            ],
+        ];
+
+        assert!(check_prefixes(&histories, Consistency::Causal).is_none());
+
+        let violation = check_prefixes(&histories, Consistency::SnapshotIsolation).unwrap();
+        assert_eq!(violation.cut, vec![2, 2]);
+        assert_eq!(
+            violation.last,
+            TransactionId {
+                session_id: 2,
+                session_height: 1,
+            }
+        );
+        assert!(matches!(
+            violation.error,
+            Error::Invalid(Consistency::SnapshotIsolation)
+        ));
+
+        // a read of a later write is not a violation of the prefix
+        let histories = vec![
+            vec![Transaction::committed(vec![Event::read("x", 1)])],
+            vec![Transaction::committed(vec![Event::write("x", 1)])],
+        ];
+        assert!(check_prefixes(&histories, Consistency::Serializable).is_none());
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_tracing() {
+        use std::string::{String, ToString};
+        use std::sync::Mutex;
+
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event as TracingEvent, Metadata};
+
+        /// Collects the names of the spans and the values of their fields.
+        #[derive(Default)]
+        struct Collector {
+            spans: Mutex<Vec<(&'static str, Fields)>>,
+        }
+
+        #[derive(Default)]
+        struct Fields(Vec<(String, String)>);
+
+        impl Visit for Fields {
+            fn record_debug(&mut self, field: &Field, value: &dyn core::fmt::Debug) {
+                self.0
+                    .push((field.name().to_string(), format!("{value:?}")));
+            }
+        }
+
+        impl tracing::Subscriber for &'static Collector {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                let mut fields = Fields::default();
+                span.record(&mut fields);
+                let mut spans = self.spans.lock().unwrap();
+                spans.push((span.metadata().name(), fields));
+                Id::from_u64(spans.len() as u64)
+            }
+
+            fn record(&self, span: &Id, values: &Record<'_>) {
+                let mut spans = self.spans.lock().unwrap();
+                let index = usize::try_from(span.into_u64()).unwrap() - 1;
+                values.record(&mut spans[index].1);
+            }
+
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+            fn event(&self, _: &TracingEvent<'_>) {}
+
+            fn enter(&self, _: &Id) {}
+
+            fn exit(&self, _: &Id) {}
+        }
+
+        // write skew: snapshot isolation, but not serializable
+        let histories = vec![
+            vec![Transaction::committed(vec![
+                Event::read_empty("x"),
+                Event::read_empty("y"),
+                Event::write("x", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read_empty("x"),
+                Event::read_empty("y"),
+                Event::write("y", 1),
+            ])],
+        ];
+        let collector: &'static Collector = Box::leak(Box::default());
+        tracing::subscriber::with_default(collector, || {
+            let mut session = CheckSession::new(&histories);
+            assert!(session.check(Consistency::Serializable).is_err());
+        });
+
+        let spans = core::mem::take(&mut *collector.spans.lock().unwrap());
+        let names: Vec<&str> = spans.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, vec!["check", "saturation", "linearization"]);
+        let field = |span: usize, name: &str| {
+            spans[span]
+                .1
+                 .0
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value.clone())
+        };
+        assert_eq!(field(0, "consistency").as_deref(), Some("serializability"));
+        assert_eq!(field(0, "valid").as_deref(), Some("false"));
+        assert_eq!(field(1, "iterations").as_deref(), Some("1"));
+        assert_eq!(field(2, "found").as_deref(), Some("false"));
+        assert!(field(2, "backtracks").is_some());
+    }
+}
@@ -0,0 +1,256 @@
+//! Checks a growing history incrementally, as new transactions are observed.
+//!
+//! A [`DeltaState`] keeps a history that satisfies a level, with its saturated causal partial order
+//! and its witness. [`check_delta`] appends new transactions to its sessions and checks the
+//! extended history, reusing both:
+//! - the new transactions come after the old ones in their sessions, and the old transactions do
+//!   not read from them, so the saturated visibility relation of the old history is part of the
+//!   one of the extended history. It seeds the saturation, which only derives the new edges.
+//! - the search first extends the old commit order, exploring only the placements of the new
+//!   transactions after it. If there is no such extension, a new transaction has to commit before
+//!   an old one, and the whole history is searched again.
+//!
+//! The levels weaker than causal consistency are saturated without a search, so they are checked
+//! on the extended history from scratch.
+
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+use crate::check::{CheckSession, Witness};
+use crate::history::atomic::types::AtomicTransactionHistory;
+use crate::history::atomic::AtomicTransactionPO;
+use crate::history::non_atomic::types::Session;
+use crate::solver::causal::saturate_causal;
+use crate::solver::constrained_linearization::{
+    ConstrainedLinearizationSolver, LinearizationStepper,
+};
+use crate::solver::error::Error;
+use crate::solver::prefix::PrefixConsistencySolver;
+use crate::solver::serializable::SerializabilitySolver;
+use crate::solver::snapshot_isolation::SnapshotIsolationSolver;
+use crate::Consistency;
+
+/// A history that satisfies a level, prepared to be extended by [`check_delta`].
+#[derive(Debug, Clone)]
+pub struct DeltaState<Variable, Version>
+where
+    Variable: Clone + Eq + Hash,
+{
+    histories: Vec<Session<Variable, Version>>,
+    level: Consistency,
+    /// The saturated causal partial order, from causal consistency up
+    causal: Option<AtomicTransactionPO<Variable>>,
+    witness: Witness,
+}
+
+impl<Variable, Version> DeltaState<Variable, Version>
+where
+    Variable: Clone + Eq + Ord + Hash,
+    Version: Clone + Eq + Hash,
+{
+    /// Checks `histories` against `level` from scratch.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the history does not satisfy `level`.
+    pub fn prepare(
+        histories: Vec<Session<Variable, Version>>,
+        level: Consistency,
+    ) -> Result<Self, Error<Variable, Version>> {
+        let mut session = CheckSession::new(&histories);
+        let witness = session.check(level)?;
+        let causal = match level {
+            Consistency::CommittedRead | Consistency::RepeatableRead | Consistency::AtomicRead => {
+                None
+            }
+            _ => Some(session.causal_po()?.clone()),
+        };
+        Ok(Self {
+            histories,
+            level,
+            causal,
+            witness,
+        })
+    }
+
+    #[must_use]
+    pub fn histories(&self) -> &[Session<Variable, Version>] {
+        &self.histories
+    }
+
+    #[must_use]
+    pub const fn level(&self) -> Consistency {
+        self.level
+    }
+
+    #[must_use]
+    pub const fn witness(&self) -> &Witness {
+        &self.witness
+    }
+}
+
+/// Checks the history of `state` extended by `new_transactions`, and returns the state of the
+/// extended history for the next call. See the module documentation.
+///
+/// The `i`-th session of `new_transactions` is appended to the `i`-th session of the history, or
+/// starts a new session past its last one.
+///
+/// # Errors
+///
+/// Returns an [`Error`] if the extended history does not satisfy the level of `state`.
+pub fn check_delta<Variable, Version>(
+    state: &DeltaState<Variable, Version>,
+    new_transactions: &[Session<Variable, Version>],
+) -> Result<DeltaState<Variable, Version>, Error<Variable, Version>>
+where
+    Variable: Clone + Eq + Ord + Hash,
+    Version: Clone + Eq + Hash,
+{
+    let mut histories = state.histories.clone();
+    for (i, session) in new_transactions.iter().enumerate() {
+        match histories.get_mut(i) {
+            Some(old) => old.extend_from_slice(session),
+            None => histories.push(session.clone()),
+        }
+    }
+
+    let Some(old) = &state.causal else {
+        return DeltaState::prepare(histories, state.level);
+    };
+
+    let mut po =
+        AtomicTransactionPO::from(AtomicTransactionHistory::try_from(histories.as_slice())?);
+    po.vis_includes_wr();
+    for (edge, kind) in &old.provenance {
+        po.provenance.entry(*edge).or_insert_with(|| kind.clone());
+    }
+    po.vis_includes(&old.visibility_relation);
+    let po = saturate_causal(po)?;
+
+    let extended = match (state.level, &state.witness) {
+        (Consistency::Prefix, Witness::SplitCommitOrder(order)) => {
+            extend(PrefixConsistencySolver::from(po.clone()), order).map(Witness::SplitCommitOrder)
+        }
+        (Consistency::SnapshotIsolation, Witness::SplitCommitOrder(order)) => {
+            extend(SnapshotIsolationSolver::from(po.clone()), order).map(Witness::SplitCommitOrder)
+        }
+        (Consistency::Serializable, Witness::CommitOrder(order)) => {
+            extend(SerializabilitySolver::from(po.clone()), order).map(Witness::CommitOrder)
+        }
+        _ => Some(Witness::Saturated),
+    };
+    let witness = match extended {
+        Some(witness) => witness,
+        None => CheckSession::new(&histories)
+            .with_causal_po(po.clone())
+            .check(state.level)?,
+    };
+
+    Ok(DeltaState {
+        histories,
+        level: state.level,
+        causal: Some(po),
+        witness,
+    })
+}
+
+/// Returns a linearization of `solver` that starts with `order`, if any.
+fn extend<S>(mut solver: S, order: &[S::Vertex]) -> Option<Vec<S::Vertex>>
+where
+    S: ConstrainedLinearizationSolver,
+{
+    LinearizationStepper::with_prefix(&mut solver, order)?.run()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::check::verify_witness;
+    use crate::history::atomic::types::TransactionId;
+    use crate::history::non_atomic::types::{Event, Transaction};
+
+    #[test]
+    fn test_check_delta() {
+        let id = |session_id, session_height| TransactionId {
+            session_id,
+            session_height,
+        };
+        let histories = vec![
+            vec![Transaction::committed(vec![Event::write("x", 1)])],
+            vec![Transaction::committed(vec![Event::read("x", 1)])],
+        ];
+
+        for level in [
+            Consistency::CommittedRead,
+            Consistency::Causal,
+            Consistency::Prefix,
+            Consistency::SnapshotIsolation,
+            Consistency::Serializable,
+        ] {
+            let state = DeltaState::prepare(histories.clone(), level).unwrap();
+
+            // the old commit order extends to the new transactions
+            let state = check_delta(
+                &state,
+                &[
+                    vec![Transaction::committed(vec![Event::write("y", 1)])],
+                    vec![],
+                    vec![Transaction::committed(vec![
+                        Event::read("y", 1),
+                        Event::read("x", 1),
+                    ])],
+                ],
+            )
+            .unwrap();
+            assert_eq!(state.histories().len(), 3);
+            assert!(verify_witness(state.histories(), level, state.witness()));
+            let state = check_delta(&state, &[]).unwrap();
+            assert!(verify_witness(state.histories(), level, state.witness()));
+
+            // s4 reads the initial version of x, so it commits before s1.t0, an old transaction
+            let extended = check_delta(
+                &state,
+                &[
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Transaction::committed(vec![Event::read_empty("x")])],
+                ],
+            )
+            .unwrap();
+            assert!(verify_witness(
+                extended.histories(),
+                level,
+                extended.witness()
+            ));
+            if let Witness::CommitOrder(order) = extended.witness() {
+                let position = |t| order.iter().position(|u| *u == t).unwrap();
+                assert!(position(id(4, 0)) < position(id(1, 0)));
+            }
+
+            // a lost update is a violation from snapshot isolation up
+            let lost_update = check_delta(
+                &state,
+                &[
+                    vec![Transaction::committed(vec![
+                        Event::read("x", 1),
+                        Event::write("x", 2),
+                    ])],
+                    vec![Transaction::committed(vec![
+                        Event::read("x", 1),
+                        Event::write("x", 3),
+                    ])],
+                ],
+            );
+            assert_eq!(
+                lost_update.is_err(),
+                matches!(
+                    level,
+                    Consistency::SnapshotIsolation | Consistency::Serializable
+                )
+            );
+        }
+    }
+}
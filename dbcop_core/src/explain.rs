@@ -0,0 +1,200 @@
+//! Narrates why a history violates a consistency level.
+//!
+//! A cycle of the visibility relation is told edge by edge, with the versions the transactions
+//! read in the history, and ends with the transaction that would have to happen before itself. The
+//! cycle is told from a transaction of the history rather than from the root transaction, which
+//! stands for the initial versions.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::fmt::Display;
+
+use crate::history::atomic::types::{Edge, EdgeKind, TransactionId};
+use crate::history::non_atomic::types::{Event, Session};
+use crate::solver::error::Error;
+
+/// Returns the sentences explaining `error`, one per line.
+#[must_use]
+pub fn explain<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    error: &Error<Variable, Version>,
+) -> Vec<String>
+where
+    Variable: Display + Eq,
+    Version: Display,
+{
+    match error {
+        Error::NonAtomic(error) => vec![format!("{error}")],
+        Error::UnknownTransaction(id) => vec![format!("{id} is not in the history")],
+        Error::Invalid(level) => vec![format!(
+            "no commit order of the transactions satisfies {level}"
+        )],
+        Error::Cycle { level, cycle } => {
+            let start = cycle
+                .iter()
+                .position(|edge| edge.source != TransactionId::root())
+                .unwrap_or(0);
+            let (before, after) = cycle.split_at(start);
+            let mut lines: Vec<String> = after
+                .iter()
+                .chain(before)
+                .map(|edge| step(histories, edge))
+                .collect();
+            if let Some(first) = after.first() {
+                lines.push(format!(
+                    "so {} happens before itself, which violates {level}",
+                    first.source
+                ));
+            }
+            lines
+        }
+    }
+}
+
+fn step<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    edge: &Edge<Variable>,
+) -> String
+where
+    Variable: Display + Eq,
+    Version: Display,
+{
+    let Edge {
+        source,
+        target,
+        kind,
+    } = edge;
+    match kind {
+        EdgeKind::SessionOrder if *source == TransactionId::root() => {
+            format!("the initial versions precede {target}")
+        }
+        EdgeKind::WriteWrite(variable) if *target == TransactionId::root() => format!(
+            "{source} writes {variable}, yet a transaction that sees {source} reads the initial \
+             version of {variable}"
+        ),
+        EdgeKind::SessionOrder => format!(
+            "{source} precedes {target} in session {}",
+            source.session_id
+        ),
+        EdgeKind::WriteRead(variable) => format!(
+            "{target} reads {}, written by {source}",
+            read(histories, target, variable)
+        ),
+        EdgeKind::WriteWrite(variable) => {
+            format!("{target} overwrites the version of {variable} written by {source}")
+        }
+        EdgeKind::External => format!("{source} precedes {target}, as known from outside"),
+        EdgeKind::ReadWrite(variable) => format!(
+            "{target} overwrites {}, which {source} reads",
+            read(histories, source, variable)
+        ),
+    }
+}
+
+/// Renders the first read of `variable` by `reader` as `x=1`, or the variable alone if the history
+/// has no such read.
+fn read<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    reader: &TransactionId,
+    variable: &Variable,
+) -> String
+where
+    Variable: Display + Eq,
+    Version: Display,
+{
+    let version = reader
+        .session_id
+        .checked_sub(1)
+        .and_then(|session| histories.get(usize::try_from(session).ok()?))
+        .and_then(|session| session.get(usize::try_from(reader.session_height).ok()?))
+        .and_then(|transaction| {
+            transaction.events.iter().find_map(|event| match event {
+                Event::Read {
+                    variable: read,
+                    version,
+                } if read == variable => Some(version),
+                _ => None,
+            })
+        });
+    match version {
+        Some(Some(version)) => format!("{variable}={version}"),
+        Some(None) => format!("the initial version of {variable}"),
+        None => format!("{variable}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::non_atomic::types::Transaction;
+    use crate::solver::causal::check_causal_read;
+    use crate::Consistency;
+
+    #[test]
+    fn test_explain_cycle() {
+        // s3.t0 sees the write of y by s2.t0, but not the write of x it depends on
+        let histories = vec![
+            vec![Transaction::committed(vec![Event::write("x", 1)])],
+            vec![Transaction::committed(vec![
+                Event::read("x", 1),
+                Event::write("y", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read("y", 1),
+                Event::read_empty("x"),
+            ])],
+        ];
+        let error = check_causal_read(&histories).unwrap_err();
+        let lines = explain(&histories, &error);
+
+        assert_eq!(
+            lines,
+            vec![
+                "s1.t0 writes x, yet a transaction that sees s1.t0 reads the initial version of x",
+                "the initial versions precede s1.t0",
+                "so s1.t0 happens before itself, which violates causal consistency",
+            ]
+        );
+
+        // s3.t0 depends on s1.t0, but s4.t0 sees s3.t0 and still reads `a` from s1.t0
+        let histories = vec![
+            vec![Transaction::committed(vec![
+                Event::write("x", 1),
+                Event::write("a", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read("x", 1),
+                Event::write("y", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read("y", 1),
+                Event::write("a", 2),
+                Event::write("z", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read("z", 1),
+                Event::read("a", 1),
+            ])],
+        ];
+        let error = check_causal_read(&histories).unwrap_err();
+        let lines = explain(&histories, &error);
+        for line in [
+            "s2.t0 reads x=1, written by s1.t0",
+            "s3.t0 reads y=1, written by s2.t0",
+            "s1.t0 overwrites the version of a written by s3.t0",
+        ] {
+            assert!(lines.iter().any(|l| l == line), "{lines:?}");
+        }
+        assert_eq!(lines.len(), 4);
+    }
+
+    #[test]
+    fn test_explain_invalid() {
+        let error: Error<&str, u64> = Error::Invalid(Consistency::Serializable);
+        assert_eq!(
+            explain(&[], &error),
+            vec!["no commit order of the transactions satisfies serializability"]
+        );
+    }
+}
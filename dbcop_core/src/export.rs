@@ -0,0 +1,360 @@
+//! Renders the relations of an atomic history as Graphviz DOT.
+//!
+//! The session order and the write-read relation are drawn edge by edge. The visibility relation is
+//! transitively closed during the checks, so only its edges that are neither implied by other
+//! visibility edges nor already drawn as session order or write-read edges are drawn. If the
+//! visibility relation is cyclic, a shortest cycle of it is drawn on top, labelled with the origin
+//! of each edge.
+//!
+//! A [`Witness`] of a check can be drawn along, as a chain of edges through its commit order,
+//! labelled with their rank.
+
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{Debug, Write};
+use core::hash::Hash;
+
+use crate::check::Witness;
+use crate::graph::digraph::DiGraph;
+use crate::history::atomic::types::{EdgeKind, TransactionId};
+use crate::history::atomic::AtomicTransactionPO;
+use crate::history::non_atomic::types::EventId;
+
+/// Colors of the edges, by kind. Any Graphviz color name or `#rrggbb` value.
+#[derive(Debug, Clone)]
+pub struct DotStyle {
+    pub session_order: String,
+    pub write_read: String,
+    pub visibility: String,
+    pub cycle: String,
+    pub commit_order: String,
+}
+
+impl Default for DotStyle {
+    fn default() -> Self {
+        Self {
+            session_order: String::from("black"),
+            write_read: String::from("blue"),
+            visibility: String::from("gray"),
+            cycle: String::from("red"),
+            commit_order: String::from("darkgreen"),
+        }
+    }
+}
+
+fn node(id: &TransactionId) -> String {
+    format!("t{}_{}", id.session_id, id.session_height)
+}
+
+/// Escapes a string for a double-quoted DOT string. Graphviz reads any other character as is.
+fn dot_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '"' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn kind_label<Variable: Debug>(kind: &EdgeKind<Variable>) -> String {
+    match kind {
+        EdgeKind::SessionOrder => String::from("so"),
+        EdgeKind::WriteRead(x) => format!("wr({x:?})"),
+        EdgeKind::WriteWrite(x) => format!("ww({x:?})"),
+        EdgeKind::ReadWrite(x) => format!("rw({x:?})"),
+        EdgeKind::External => String::from("ext"),
+    }
+}
+
+/// Returns the edges of a shortest cycle of the visibility relation, labelled with their origin.
+///
+/// If the relation was extended without provenance, the cycle is taken in the relation itself,
+/// without labels.
+fn cycle<Variable>(
+    po: &AtomicTransactionPO<Variable>,
+) -> Vec<(TransactionId, TransactionId, String)>
+where
+    Variable: Clone + Eq + Hash + Debug,
+{
+    if let Some(cycle) = po.vis_cycle() {
+        return cycle
+            .into_iter()
+            .map(|edge| (edge.source, edge.target, kind_label(&edge.kind)))
+            .collect();
+    }
+    // a cyclic closed relation has self-loops on the cycle, which are not informative
+    let without_self_loops = DiGraph {
+        adj_map: po
+            .visibility_relation
+            .adj_map
+            .iter()
+            .map(|(source, targets)| {
+                (
+                    *source,
+                    targets.iter().filter(|t| *t != source).copied().collect(),
+                )
+            })
+            .collect(),
+    };
+    let cycle = without_self_loops.find_cycle().unwrap_or_default();
+    cycle
+        .iter()
+        .zip(cycle.iter().cycle().skip(1))
+        .map(|(source, target)| (*source, *target, String::new()))
+        .collect()
+}
+
+/// Returns the transactions of the commit order of a witness, without consecutive repetitions.
+///
+/// The read and write sections of a split commit order are placed apart, so their transaction may
+/// appear twice. A saturation witness has no commit order.
+fn commit_order(witness: &Witness) -> Vec<TransactionId> {
+    let mut order: Vec<TransactionId> = match witness {
+        Witness::Saturated => Vec::new(),
+        Witness::CommitOrder(order) => order.clone(),
+        Witness::SplitCommitOrder(order) => order.iter().map(|(id, _)| *id).collect(),
+        Witness::OperationOrder(order) => order.iter().map(EventId::transaction_id).collect(),
+    };
+    order.dedup();
+    order
+}
+
+/// Renders the session order, the write-read relation, the visibility relation, the commit order
+/// of `witness`, if any, and, if there is one, a cycle of the visibility relation as a DOT digraph.
+///
+/// The output is deterministic, so it can be diffed across runs.
+#[must_use]
+pub fn to_dot<Variable>(
+    po: &AtomicTransactionPO<Variable>,
+    witness: Option<&Witness>,
+    style: &DotStyle,
+) -> String
+where
+    Variable: Clone + Eq + Hash + Debug,
+{
+    let mut transactions: Vec<TransactionId> = po.history.0.keys().copied().collect();
+    transactions.push(po.root);
+    transactions.sort_unstable();
+    transactions.dedup();
+
+    let mut dot = String::from("digraph history {\n    node [shape=box];\n");
+
+    for id in &transactions {
+        let label = if *id == po.root {
+            String::from("init")
+        } else {
+            format!("({}, {})", id.session_id, id.session_height)
+        };
+        let _ = writeln!(dot, "    {} [label=\"{label}\"];", node(id));
+    }
+
+    // the session order is closed, so it is drawn between immediate predecessors only
+    let mut drawn: BTreeSet<(TransactionId, TransactionId)> = BTreeSet::new();
+    for (target, info) in &po.history.0 {
+        for source in &info.predecessors {
+            drawn.insert((*source, *target));
+        }
+    }
+    for (source, target) in &drawn {
+        let _ = writeln!(
+            dot,
+            "    {} -> {} [color=\"{}\"];",
+            node(source),
+            node(target),
+            dot_escape(&style.session_order)
+        );
+    }
+
+    let mut write_read: Vec<(TransactionId, TransactionId, String)> = po
+        .write_read_relation
+        .iter()
+        .flat_map(|(variable, wr_x)| {
+            wr_x.adj_map.iter().flat_map(move |(source, targets)| {
+                targets
+                    .iter()
+                    .map(move |target| (*source, *target, format!("{variable:?}")))
+            })
+        })
+        .collect();
+    write_read.sort_unstable();
+    for (source, target, variable) in &write_read {
+        drawn.insert((*source, *target));
+        let _ = writeln!(
+            dot,
+            "    {} -> {} [color=\"{}\", label=\"{}\"];",
+            node(source),
+            node(target),
+            dot_escape(&style.write_read),
+            dot_escape(variable)
+        );
+    }
+
+    let visibility = &po.visibility_relation;
+    let mut reduced: BTreeSet<(TransactionId, TransactionId)> = BTreeSet::new();
+    for (source, targets) in &visibility.adj_map {
+        for target in targets {
+            let is_implied = targets.iter().any(|middle| {
+                middle != source && middle != target && visibility.has_edge(middle, target)
+            });
+            if source != target && !is_implied && !drawn.contains(&(*source, *target)) {
+                reduced.insert((*source, *target));
+            }
+        }
+    }
+    for (source, target) in &reduced {
+        let _ = writeln!(
+            dot,
+            "    {} -> {} [color=\"{}\", style=dashed];",
+            node(source),
+            node(target),
+            dot_escape(&style.visibility)
+        );
+    }
+
+    // the commit order does not constrain the layout, which follows the relations
+    let order = witness.map(commit_order).unwrap_or_default();
+    for (rank, (source, target)) in (1..).zip(order.iter().zip(order.iter().skip(1))) {
+        if transactions.binary_search(source).is_ok() && transactions.binary_search(target).is_ok()
+        {
+            let _ = writeln!(
+                dot,
+                "    {} -> {} [color=\"{}\", style=dotted, constraint=false, label=\"{rank}\"];",
+                node(source),
+                node(target),
+                dot_escape(&style.commit_order)
+            );
+        }
+    }
+
+    for (source, target, label) in cycle(po) {
+        let _ = writeln!(
+            dot,
+            "    {} -> {} [color=\"{}\", penwidth=2, label=\"{}\"];",
+            node(&source),
+            node(&target),
+            dot_escape(&style.cycle),
+            dot_escape(&label)
+        );
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::check::check;
+    use crate::history::atomic::types::AtomicTransactionHistory;
+    use crate::history::non_atomic::types::{Event, Transaction};
+    use crate::solver::causal::check_causal_read;
+    use crate::Consistency;
+
+    #[test]
+    fn test_to_dot() {
+        let histories = vec![
+            vec![
+                Transaction::committed(vec![Event::write("x", 1)]),
+                Transaction::committed(vec![Event::write("y", 1)]),
+            ],
+            vec![Transaction::committed(vec![Event::read("y", 1)])],
+        ];
+
+        let po = check_causal_read(&histories).unwrap();
+        let dot = to_dot(&po, None, &DotStyle::default());
+
+        assert!(dot.starts_with("digraph history {\n"));
+        assert!(dot.contains("    t0_0 [label=\"init\"];\n"));
+        assert!(dot.contains("    t1_0 -> t1_1 [color=\"black\"];\n"));
+        assert!(dot.contains("    t0_0 -> t2_0 [color=\"black\"];\n"));
+        assert!(dot.contains("    t1_1 -> t2_0 [color=\"blue\", label=\"\\\"y\\\"\"];\n"));
+        // implied by the session order and the write-read relation
+        assert!(!dot.contains("t1_0 -> t2_0"));
+        assert!(!dot.contains("red"));
+
+        assert_eq!(dot, to_dot(&po, None, &DotStyle::default()));
+    }
+
+    #[test]
+    fn test_to_dot_witness() {
+        let histories = vec![
+            vec![Transaction::committed(vec![Event::write("x", 1)])],
+            vec![Transaction::committed(vec![
+                Event::read("x", 1),
+                Event::write("x", 2),
+            ])],
+        ];
+
+        let po = check_causal_read(&histories).unwrap();
+        let witness = check(&histories, Consistency::Serializable).unwrap();
+        let style = DotStyle {
+            commit_order: String::from("#008000"),
+            ..DotStyle::default()
+        };
+        let dot = to_dot(&po, Some(&witness), &style);
+
+        assert!(dot.contains(
+            "    t1_0 -> t2_0 [color=\"#008000\", style=dotted, constraint=false, label=\""
+        ));
+        assert_eq!(
+            dot.matches("style=dotted").count(),
+            commit_order(&witness).len() - 1
+        );
+        assert!(!to_dot(&po, Some(&Witness::Saturated), &style).contains("dotted"));
+    }
+
+    #[test]
+    fn test_to_dot_escape() {
+        let histories = vec![
+            vec![Transaction::committed(vec![Event::write("café", 1)])],
+            vec![Transaction::committed(vec![Event::read("café", 1)])],
+        ];
+
+        let po = check_causal_read(&histories).unwrap();
+        let style = DotStyle {
+            write_read: String::from("blue\" penwidth=\"9"),
+            ..DotStyle::default()
+        };
+        let dot = to_dot(&po, None, &style);
+
+        // the non-ASCII name is kept as is, and the quotes of the color do not end the string
+        assert!(dot.contains(
+            "    t1_0 -> t2_0 [color=\"blue\\\" penwidth=\\\"9\", label=\"\\\"café\\\"\"];\n"
+        ));
+    }
+
+    #[test]
+    fn test_to_dot_cycle() {
+        let histories = vec![
+            vec![
+                Transaction::committed(vec![Event::write("x", 1)]),
+                Transaction::committed(vec![Event::write("x", 2)]),
+            ],
+            vec![
+                Transaction::committed(vec![Event::read("x", 2)]),
+                Transaction::committed(vec![Event::read("x", 1)]),
+            ],
+        ];
+
+        let mut po = AtomicTransactionPO::from(
+            AtomicTransactionHistory::try_from(histories.as_slice()).unwrap(),
+        );
+        po.vis_includes_wr();
+        po.vis_is_trans();
+        for (x, ww_x) in &po.causal_ww() {
+            po.vis_includes_from(ww_x, &EdgeKind::WriteWrite(x));
+        }
+        po.vis_is_trans();
+
+        let dot = to_dot(&po, None, &DotStyle::default());
+        assert!(dot.contains("    t1_0 -> t1_1 [color=\"red\", penwidth=2, label=\"so\"];\n"));
+        assert!(dot
+            .contains("    t1_1 -> t1_0 [color=\"red\", penwidth=2, label=\"ww(\\\"x\\\")\"];\n"));
+    }
+}
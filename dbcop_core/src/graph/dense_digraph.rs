@@ -0,0 +1,255 @@
+//! Directed graph over a fixed set of vertices, stored as a bit matrix.
+//!
+//! Closure-heavy operations on dense graphs are much faster on bit rows than on hash sets,
+//! so [`DiGraph`] switches to this representation for its closure on large graphs.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+use hashbrown::HashMap;
+
+use crate::graph::digraph::DiGraph;
+use crate::graph::small_set::SmallSet;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+#[derive(Debug, Clone)]
+pub struct DenseDiGraph<T>
+where
+    T: Hash + Eq + Clone + Debug,
+{
+    /// Vertices by their index.
+    vertices: Vec<T>,
+    /// Index of each vertex.
+    index: HashMap<T, usize>,
+    /// `rows[i]` is the bit set of the successors of `vertices[i]`.
+    rows: Vec<Vec<u64>>,
+}
+
+impl<T> DenseDiGraph<T>
+where
+    T: Hash + Eq + Clone + Debug,
+{
+    /// Creates a graph without edges over the given vertices.
+    pub fn new(vertices: impl IntoIterator<Item = T>) -> Self {
+        let mut index = HashMap::new();
+        let vertices: Vec<T> = vertices
+            .into_iter()
+            .filter(|vertex| {
+                let next = index.len();
+                index.try_insert(vertex.clone(), next).is_ok()
+            })
+            .collect();
+        let words = vertices.len().div_ceil(WORD_BITS);
+        Self {
+            rows: vec![vec![0; words]; vertices.len()],
+            vertices,
+            index,
+        }
+    }
+
+    /// Returns the vertices, by their index.
+    #[must_use]
+    pub fn vertices(&self) -> &[T] {
+        &self.vertices
+    }
+
+    /// Returns the index of `vertex`, that is its bit in the rows.
+    #[must_use]
+    pub fn index(&self, vertex: &T) -> Option<usize> {
+        self.index.get(vertex).copied()
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `source` or `target` is not a vertex of the graph.
+    pub fn add_edge(&mut self, source: &T, target: &T) {
+        let (i, j) = (self.index[source], self.index[target]);
+        self.rows[i][j / WORD_BITS] |= 1 << (j % WORD_BITS);
+    }
+
+    #[must_use]
+    pub fn has_edge(&self, source: &T, target: &T) -> bool {
+        match (self.index.get(source), self.index.get(target)) {
+            (Some(&i), Some(&j)) => self.rows[i][j / WORD_BITS] & (1 << (j % WORD_BITS)) != 0,
+            _ => false,
+        }
+    }
+
+    /// Returns the successors of `source`.
+    pub fn successors<'a>(&'a self, source: &T) -> impl Iterator<Item = &'a T> + 'a {
+        let row = self.index.get(source).map(|&i| &self.rows[i]);
+        row.into_iter()
+            .flat_map(move |row| ones(row).map(move |j| &self.vertices[j]))
+    }
+
+    /// Returns the indices of the vertices in the post-order of a depth-first search.
+    fn post_order(&self) -> Vec<usize> {
+        let mut visited = vec![false; self.vertices.len()];
+        let mut order = Vec::with_capacity(self.vertices.len());
+        let mut stack = Vec::new();
+        for root in 0..self.vertices.len() {
+            if visited[root] {
+                continue;
+            }
+            visited[root] = true;
+            stack.push((root, ones(&self.rows[root])));
+            while let Some((vertex, successors)) = stack.last_mut() {
+                if let Some(successor) = successors.find(|&j| !visited[j]) {
+                    visited[successor] = true;
+                    stack.push((successor, ones(&self.rows[successor])));
+                } else {
+                    order.push(*vertex);
+                    stack.pop();
+                }
+            }
+        }
+        order
+    }
+
+    /// Takes the transitive closure in place.
+    ///
+    /// The rows are closed in the post-order of a depth-first search, so on an acyclic graph the
+    /// successors of a vertex are closed before it, and its row is the union of theirs. On a
+    /// cycle, the vertices that are not closed yet are expanded one successor at a time.
+    pub fn close(&mut self) {
+        let successors = self.rows.clone();
+        let mut closed = vec![false; self.vertices.len()];
+        let mut pending = Vec::new();
+        for i in self.post_order() {
+            let mut row = core::mem::take(&mut self.rows[i]);
+            pending.extend(ones(&row));
+            while let Some(k) = pending.pop() {
+                if closed[k] {
+                    // a closed row already contains what its vertices reach
+                    for (word, closed_word) in row.iter_mut().zip(&self.rows[k]) {
+                        *word |= closed_word;
+                    }
+                } else {
+                    for (w, (word, successor_word)) in
+                        row.iter_mut().zip(&successors[k]).enumerate()
+                    {
+                        let new = successor_word & !*word;
+                        *word |= new;
+                        pending.extend(ones(&[new]).map(|j| w * WORD_BITS + j));
+                    }
+                }
+            }
+            self.rows[i] = row;
+            closed[i] = true;
+        }
+    }
+
+    #[must_use]
+    pub fn closure(&self) -> Self {
+        let mut closure = self.clone();
+        closure.close();
+        closure
+    }
+
+    /// Returns true if some vertex reaches itself.
+    #[must_use]
+    pub fn has_cycle(&self) -> bool {
+        let closure = self.closure();
+        (0..closure.vertices.len())
+            .any(|i| closure.rows[i][i / WORD_BITS] & (1 << (i % WORD_BITS)) != 0)
+    }
+}
+
+/// Returns the indices of the set bits of a row, in increasing order.
+fn ones(row: &[u64]) -> impl Iterator<Item = usize> + '_ {
+    row.iter().enumerate().flat_map(|(w, &word)| {
+        // pops the lowest set bit of the word at each step
+        core::iter::successors((word != 0).then_some(word), |&rest| {
+            Some(rest & (rest - 1)).filter(|&next| next != 0)
+        })
+        .map(move |rest| w * WORD_BITS + rest.trailing_zeros() as usize)
+    })
+}
+
+impl<T> From<&DiGraph<T>> for DenseDiGraph<T>
+where
+    T: Hash + Eq + Clone + Debug,
+{
+    fn from(graph: &DiGraph<T>) -> Self {
+        let mut dense = Self::new(
+            graph
+                .adj_map
+                .iter()
+                .flat_map(|(source, targets)| core::iter::once(source).chain(targets))
+                .cloned(),
+        );
+        for (source, targets) in &graph.adj_map {
+            for target in targets {
+                dense.add_edge(source, target);
+            }
+        }
+        dense
+    }
+}
+
+impl<T> From<&DenseDiGraph<T>> for DiGraph<T>
+where
+    T: Hash + Eq + Clone + Debug,
+{
+    fn from(graph: &DenseDiGraph<T>) -> Self {
+        Self {
+            adj_map: graph
+                .vertices
+                .iter()
+                .map(|source| {
+                    (
+                        source.clone(),
+                        graph.successors(source).cloned().collect::<SmallSet<_>>(),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dense_closure() {
+        let mut graph: DiGraph<u32> = DiGraph::default();
+        for i in 0..100 {
+            graph.add_edge(i, i + 1);
+        }
+
+        let dense = DenseDiGraph::from(&graph);
+        assert_eq!(dense.vertices().len(), 101);
+        assert_eq!(dense.vertices()[dense.index(&70).unwrap()], 70);
+        assert_eq!(dense.index(&101), None);
+        assert!(dense.has_edge(&0, &1));
+        assert!(!dense.has_edge(&0, &2));
+        assert!(!dense.has_cycle());
+
+        let closure = DiGraph::from(&dense.closure());
+        assert_eq!(closure.adj_map[&0], (1..=100).collect());
+        assert_eq!(closure.adj_map[&70], (71..=100).collect());
+        assert_eq!(closure.adj_map[&100], [].into());
+
+        graph.add_edge(100, 0);
+        assert!(DenseDiGraph::from(&graph).has_cycle());
+    }
+
+    #[test]
+    fn test_dense_closure_cycles() {
+        // cycles within and across the words of the rows
+        let mut graph: DiGraph<u32> = DiGraph::default();
+        for i in 0..150 {
+            graph.add_edge(i, (i * 7 + 3) % 150);
+            if i % 3 == 0 {
+                graph.add_edge(i, (i * 13 + 5) % 150);
+            }
+        }
+
+        let closure = DiGraph::from(&DenseDiGraph::from(&graph).closure());
+        assert_eq!(closure.adj_map, graph.sparse_closure().adj_map);
+    }
+}
@@ -1,3 +1,7 @@
 pub mod biconnected_component;
+pub mod dense_digraph;
 pub mod digraph;
+#[cfg(feature = "petgraph")]
+pub mod petgraph;
+pub mod small_set;
 pub mod ugraph;
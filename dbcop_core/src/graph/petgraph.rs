@@ -0,0 +1,105 @@
+//! Conversions of the graphs to [`petgraph`] graphs, for the analyses of other crates, such as
+//! centrality, condensation or drawing.
+//!
+//! The vertices become the node weights, and the edges have no weight. The node indices follow the
+//! iteration order of the adjacency maps, so a vertex is found back by its weight.
+
+use core::fmt::Debug;
+use core::hash::Hash;
+
+use ::petgraph::graph::{DiGraph as PetDiGraph, NodeIndex, UnGraph};
+use hashbrown::HashMap;
+
+use crate::graph::digraph::DiGraph;
+use crate::graph::ugraph::UGraph;
+
+/// Returns the index of `vertex`, adding it to `graph` if it is new.
+fn node<T, Ty>(
+    graph: &mut ::petgraph::Graph<T, (), Ty>,
+    indices: &mut HashMap<T, NodeIndex>,
+    vertex: &T,
+) -> NodeIndex
+where
+    T: Hash + Eq + Clone,
+    Ty: ::petgraph::EdgeType,
+{
+    *indices
+        .entry(vertex.clone())
+        .or_insert_with(|| graph.add_node(vertex.clone()))
+}
+
+impl<T> From<&DiGraph<T>> for PetDiGraph<T, ()>
+where
+    T: Hash + Eq + Clone + Debug,
+{
+    fn from(digraph: &DiGraph<T>) -> Self {
+        let mut graph = Self::new();
+        let mut indices = HashMap::new();
+        for (source, targets) in &digraph.adj_map {
+            let source = node(&mut graph, &mut indices, source);
+            for target in targets {
+                let target = node(&mut graph, &mut indices, target);
+                graph.add_edge(source, target, ());
+            }
+        }
+        graph
+    }
+}
+
+/// Each undirected edge, stored in both directions by [`UGraph`], is added once.
+impl<T> From<&UGraph<T>> for UnGraph<T, ()>
+where
+    T: Hash + Eq + Clone + Debug,
+{
+    fn from(ugraph: &UGraph<T>) -> Self {
+        let mut graph = Self::new_undirected();
+        let mut indices = HashMap::new();
+        for (source, targets) in &ugraph.adj_map {
+            let source = node(&mut graph, &mut indices, source);
+            for target in targets {
+                let target = node(&mut graph, &mut indices, target);
+                if source <= target {
+                    graph.add_edge(source, target, ());
+                }
+            }
+        }
+        graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use ::petgraph::algo::{condensation, kosaraju_scc};
+
+    use super::*;
+    use crate::history::non_atomic::types::{Event, Transaction};
+    use crate::history::stats::communication_graph;
+
+    #[test]
+    fn test_petgraph() {
+        let mut digraph = DiGraph::default();
+        digraph.add_edge(1, 2);
+        digraph.add_edge(2, 1);
+        digraph.add_edges(2, &[3]);
+        let graph = PetDiGraph::from(&digraph);
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(graph.edge_count(), 3);
+        let mut sizes: Vec<usize> = kosaraju_scc(&graph).iter().map(Vec::len).collect();
+        sizes.sort_unstable();
+        assert_eq!(sizes, vec![1, 2]);
+        assert_eq!(condensation(graph, true).node_count(), 2);
+
+        // sessions 1 and 2 conflict on x, and session 3 is alone
+        let histories = vec![
+            vec![Transaction::committed(vec![Event::write("x", 1)])],
+            vec![Transaction::committed(vec![Event::read("x", 1)])],
+            vec![Transaction::committed(vec![Event::write("y", 1)])],
+        ];
+        let graph = UnGraph::from(&communication_graph(&histories));
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(graph.edge_count(), 1);
+    }
+}
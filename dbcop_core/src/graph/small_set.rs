@@ -0,0 +1,293 @@
+//! Set of few elements, for the successors of a vertex of a
+//! [`DiGraph`](crate::graph::digraph::DiGraph).
+//!
+//! Most vertices of the partial orders have a handful of successors, for which a hash set allocates
+//! a table and hashes on every lookup. A [`SmallSet`] keeps up to [`SMALL_SET_CAPACITY`] elements in
+//! a vector, searched linearly, and moves them to a hash set once it grows past it.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{Debug, Formatter, Result as FmtResult};
+use core::hash::Hash;
+use core::slice;
+
+use hashbrown::{hash_set, HashSet};
+
+/// Number of elements a [`SmallSet`] keeps in a vector.
+pub const SMALL_SET_CAPACITY: usize = 8;
+
+#[derive(Clone)]
+pub struct SmallSet<T>(Repr<T>);
+
+#[derive(Clone)]
+enum Repr<T> {
+    Inline(Vec<T>),
+    Hashed(HashSet<T>),
+}
+
+impl<T> SmallSet<T> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self(Repr::Inline(Vec::new()))
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        match &self.0 {
+            Repr::Inline(elements) => elements.len(),
+            Repr::Hashed(elements) => elements.len(),
+        }
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[must_use]
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter(match &self.0 {
+            Repr::Inline(elements) => IterRepr::Inline(elements.iter()),
+            Repr::Hashed(elements) => IterRepr::Hashed(elements.iter()),
+        })
+    }
+}
+
+impl<T> SmallSet<T>
+where
+    T: Hash + Eq,
+{
+    #[must_use]
+    pub fn contains(&self, value: &T) -> bool {
+        match &self.0 {
+            Repr::Inline(elements) => elements.contains(value),
+            Repr::Hashed(elements) => elements.contains(value),
+        }
+    }
+
+    /// Adds `value`, and returns true if it was not in the set.
+    pub fn insert(&mut self, value: T) -> bool {
+        match &mut self.0 {
+            Repr::Inline(elements) => {
+                if elements.contains(&value) {
+                    return false;
+                }
+                if elements.len() < SMALL_SET_CAPACITY {
+                    // grow once to the full capacity, rather than doubling
+                    elements.reserve_exact(SMALL_SET_CAPACITY - elements.len());
+                    elements.push(value);
+                } else {
+                    let mut hashed: HashSet<T> = elements.drain(..).collect();
+                    hashed.insert(value);
+                    self.0 = Repr::Hashed(hashed);
+                }
+                true
+            }
+            Repr::Hashed(elements) => elements.insert(value),
+        }
+    }
+
+    /// Removes `value`, and returns true if it was in the set.
+    pub fn remove(&mut self, value: &T) -> bool {
+        match &mut self.0 {
+            Repr::Inline(elements) => elements
+                .iter()
+                .position(|element| element == value)
+                .map(|i| elements.swap_remove(i))
+                .is_some(),
+            Repr::Hashed(elements) => elements.remove(value),
+        }
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(&T) -> bool) {
+        match &mut self.0 {
+            Repr::Inline(elements) => elements.retain(|element| keep(element)),
+            Repr::Hashed(elements) => elements.retain(|element| keep(element)),
+        }
+    }
+}
+
+impl<T> Default for SmallSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Debug for SmallSet<T>
+where
+    T: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// Compares the elements, regardless of their order.
+impl<T> PartialEq for SmallSet<T>
+where
+    T: Hash + Eq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|element| other.contains(element))
+    }
+}
+
+impl<T> Eq for SmallSet<T> where T: Hash + Eq {}
+
+impl<T> Extend<T> for SmallSet<T>
+where
+    T: Hash + Eq,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let mut iter = iter.into_iter();
+        while let Repr::Inline(_) = self.0 {
+            let Some(value) = iter.next() else {
+                return;
+            };
+            self.insert(value);
+        }
+        if let Repr::Hashed(elements) = &mut self.0 {
+            elements.extend(iter);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for SmallSet<T>
+where
+    T: Hash + Eq,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+/// Keeps the hash set as is if it is past [`SMALL_SET_CAPACITY`].
+impl<T> From<HashSet<T>> for SmallSet<T> {
+    fn from(elements: HashSet<T>) -> Self {
+        if elements.len() > SMALL_SET_CAPACITY {
+            Self(Repr::Hashed(elements))
+        } else {
+            Self(Repr::Inline(elements.into_iter().collect()))
+        }
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for SmallSet<T>
+where
+    T: Hash + Eq,
+{
+    fn from(elements: [T; N]) -> Self {
+        elements.into_iter().collect()
+    }
+}
+
+/// Iterator over the elements of a [`SmallSet`], in no particular order.
+#[derive(Clone)]
+pub struct Iter<'a, T>(IterRepr<'a, T>);
+
+#[derive(Clone)]
+enum IterRepr<'a, T> {
+    Inline(slice::Iter<'a, T>),
+    Hashed(hash_set::Iter<'a, T>),
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.0 {
+            IterRepr::Inline(iter) => iter.next(),
+            IterRepr::Hashed(iter) => iter.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.0 {
+            IterRepr::Inline(iter) => iter.size_hint(),
+            IterRepr::Hashed(iter) => iter.size_hint(),
+        }
+    }
+}
+
+/// Owning iterator over the elements of a [`SmallSet`], in no particular order.
+pub struct IntoIter<T>(IntoIterRepr<T>);
+
+enum IntoIterRepr<T> {
+    Inline(vec::IntoIter<T>),
+    Hashed(hash_set::IntoIter<T>),
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.0 {
+            IntoIterRepr::Inline(iter) => iter.next(),
+            IntoIterRepr::Hashed(iter) => iter.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.0 {
+            IntoIterRepr::Inline(iter) => iter.size_hint(),
+            IntoIterRepr::Hashed(iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SmallSet<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T> IntoIterator for SmallSet<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(match self.0 {
+            Repr::Inline(elements) => IntoIterRepr::Inline(elements.into_iter()),
+            Repr::Hashed(elements) => IntoIterRepr::Hashed(elements.into_iter()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_set() {
+        let mut set: SmallSet<u32> = SmallSet::new();
+        assert!(set.is_empty());
+        for i in 0..20 {
+            assert!(set.insert(i));
+            assert!(!set.insert(i));
+            assert_eq!(set.len(), i as usize + 1);
+            assert_eq!(
+                matches!(set.0, Repr::Hashed(_)),
+                set.len() > SMALL_SET_CAPACITY
+            );
+        }
+        assert!((0..20).all(|i| set.contains(&i)));
+
+        assert!(set.remove(&3));
+        assert!(!set.remove(&3));
+        set.retain(|i| i % 2 == 0);
+        assert_eq!(set, (0..20).step_by(2).collect());
+
+        let mut small: SmallSet<u32> = [3, 1, 2].into();
+        assert!(small.remove(&1));
+        assert_eq!(small, [2, 3].into());
+        assert_ne!(small, [2].into());
+        let mut elements: Vec<u32> = small.into_iter().collect();
+        elements.sort_unstable();
+        assert_eq!(elements, vec![2, 3]);
+    }
+}
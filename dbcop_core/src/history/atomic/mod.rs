@@ -7,9 +7,12 @@ use core::hash::Hash;
 use hashbrown::HashMap;
 
 use crate::graph::digraph::DiGraph;
-use crate::history::atomic::types::{AtomicTransactionHistory, TransactionId};
+use crate::graph::small_set::SmallSet;
+use crate::history::atomic::types::{
+    AtomicTransactionHistory, Edge, EdgeKind, TransactionId, WriteOrderReason,
+};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AtomicTransactionPO<Variable>
 where
     Variable: Clone + Eq + Hash,
@@ -19,6 +22,8 @@ where
     pub session_order: DiGraph<TransactionId>,
     pub write_read_relation: HashMap<Variable, DiGraph<TransactionId>>,
     pub visibility_relation: DiGraph<TransactionId>,
+    /// Origin of the visibility edges that are not derived by transitivity
+    pub provenance: HashMap<(TransactionId, TransactionId), EdgeKind<Variable>>,
 }
 
 impl<Variable> From<AtomicTransactionHistory<Variable>> for AtomicTransactionPO<Variable>
@@ -28,30 +33,19 @@ where
     fn from(history: AtomicTransactionHistory<Variable>) -> Self {
         let mut session_order: DiGraph<TransactionId> = DiGraph::default();
 
-        {
-            let mut transactions: Vec<_> = history.0.keys().copied().collect();
-            transactions.sort_unstable();
-
-            // TODO(rano): this is bit hacky
-            // here we try to create the session order without knowing the number of sessions
-            //             ┌────────┐  ┌────────┐
-            //       ┌────>│ (1, 0) ├─>│ (1, 1) ├─>...
-            //       │     └────────┘  └────────┘
-            // ┌─────┴──┐  ┌────────┐  ┌────────┐
-            // │ (0, 0) ├─>│ (2, 0) ├─>│ (2, 1) ├─>...
-            // └─────┬──┘  └────────┘  └────────┘
-            //       │     ┌────────┐  ┌────────┐
-            //       └────>│ (3, 0) ├─>│ (3, 1) ├─>...
-            //             └────────┘  └────────┘
-            for pair in transactions.windows(2) {
-                let [t1, t2] = pair else {
-                    unreachable!("windows should have at least 2 elements")
-                };
-                if t1.session_id == t2.session_id {
-                    session_order.add_edge(*t1, *t2);
-                } else {
-                    session_order.add_edge(TransactionId::default(), *t2);
-                };
+        // the root transaction precedes every transaction without a predecessor in its session
+        //             ┌────────┐  ┌────────┐
+        //       ┌────>│ (1, 0) ├─>│ (1, 1) ├─>...
+        //       │     └────────┘  └────────┘
+        // ┌─────┴──┐  ┌────────┐  ┌────────┐
+        // │ (0, 0) ├─>│ (2, 0) ├─>│ (2, 1) ├─>...
+        // └─────┬──┘  └────────┘  └────────┘
+        //       │     ┌────────┐  ┌────────┐
+        //       └────>│ (3, 0) ├─>│ (3, 1) ├─>...
+        //             └────────┘  └────────┘
+        for (txn_id, txn_info) in &history.0 {
+            for predecessor in &txn_info.predecessors {
+                session_order.add_edge(*predecessor, *txn_id);
             }
         }
 
@@ -78,12 +72,23 @@ where
             }
         }
 
+        let provenance = session_order
+            .adj_map
+            .iter()
+            .flat_map(|(source, targets)| {
+                targets
+                    .iter()
+                    .map(|target| ((*source, *target), EdgeKind::SessionOrder))
+            })
+            .collect();
+
         Self {
             root: TransactionId::default(),
             history,
             write_read_relation,
             visibility_relation: session_order.clone(),
             session_order,
+            provenance,
         }
     }
 }
@@ -92,6 +97,32 @@ impl<Variable> AtomicTransactionPO<Variable>
 where
     Variable: Clone + Eq + Hash,
 {
+    /// Returns true if the transaction writes the variable.
+    /// The root transaction writes the initial version of every variable.
+    #[must_use]
+    pub fn writes(&self, transaction: &TransactionId, variable: &Variable) -> bool {
+        *transaction == self.root
+            || self
+                .history
+                .0
+                .get(transaction)
+                .is_some_and(|info| info.writes.contains(variable))
+    }
+
+    /// Returns the transactions reading the initial version of each variable
+    #[must_use]
+    pub fn initial_readers(&self) -> HashMap<Variable, SmallSet<TransactionId>> {
+        self.write_read_relation
+            .iter()
+            .filter_map(|(x, wr_x)| {
+                wr_x.adj_map
+                    .get(&self.root)
+                    .filter(|readers| !readers.is_empty())
+                    .map(|readers| (x.clone(), readers.clone()))
+            })
+            .collect()
+    }
+
     /// Returns the union of the write-read relation of all variables
     #[must_use]
     pub fn get_wr(&self) -> DiGraph<TransactionId> {
@@ -110,6 +141,74 @@ where
         self.visibility_relation.union(g)
     }
 
+    /// Takes the union of the visibility relation and the given graph, recording `kind` as the
+    /// origin of the new edges, and returns true if the relation has changed
+    pub fn vis_includes_from(
+        &mut self,
+        g: &DiGraph<TransactionId>,
+        kind: &EdgeKind<Variable>,
+    ) -> bool {
+        for (source, targets) in &g.adj_map {
+            for target in targets {
+                if !self.visibility_relation.has_edge(source, target) {
+                    self.provenance
+                        .entry((*source, *target))
+                        .or_insert_with(|| kind.clone());
+                }
+            }
+        }
+        self.vis_includes(g)
+    }
+
+    /// Includes edges known from outside the history, such as the order of commit timestamps, in
+    /// the visibility relation and returns true if the relation has changed
+    pub fn vis_includes_external(
+        &mut self,
+        edges: impl IntoIterator<Item = (TransactionId, TransactionId)>,
+    ) -> bool {
+        let mut g: DiGraph<TransactionId> = DiGraph::default();
+        for (source, target) in edges {
+            g.add_edge(source, target);
+        }
+        self.vis_includes_from(&g, &EdgeKind::External)
+    }
+
+    /// Includes the write-read relation of every variable in the visibility relation
+    /// and returns true if the relation has changed
+    pub fn vis_includes_wr(&mut self) -> bool {
+        let write_read_relation = core::mem::take(&mut self.write_read_relation);
+        let mut change = false;
+        for (x, wr_x) in &write_read_relation {
+            change |= self.vis_includes_from(wr_x, &EdgeKind::WriteRead(x.clone()));
+        }
+        self.write_read_relation = write_read_relation;
+        change
+    }
+
+    /// Returns a shortest cycle of the visibility relation, as edges with their origin.
+    ///
+    /// Edges derived by transitivity are expanded, so every edge of the cycle is a
+    /// session order, write-read, write-write or read-write edge.
+    #[must_use]
+    pub fn vis_cycle(&self) -> Option<Vec<Edge<Variable>>> {
+        let mut origins: DiGraph<TransactionId> = DiGraph::default();
+        for (source, target) in self.provenance.keys() {
+            origins.add_edge(*source, *target);
+        }
+        let cycle = origins.find_cycle()?;
+        Some(
+            cycle
+                .iter()
+                .zip(cycle.iter().cycle().skip(1))
+                .map(|(source, target)| Edge {
+                    source: *source,
+                    target: *target,
+                    kind: self.provenance[&(*source, *target)].clone(),
+                })
+                .collect(),
+        )
+    }
+
     /// Takes the transitive closure of the visibility relation
     /// and returns true if the relation has changed
     ///
@@ -123,39 +222,65 @@ where
                 .adj_map
                 .get(k)
                 .expect("closure map should have it")
-                .difference(v)
-                .count()
-                > 0
+                .iter()
+                .any(|t| !v.contains(t))
         });
         self.visibility_relation = closure;
         change
     }
 
     pub fn causal_ww(&mut self) -> HashMap<Variable, DiGraph<TransactionId>> {
-        let mut ww: HashMap<Variable, DiGraph<TransactionId>> = HashMap::default();
+        self.causal_ww_reasons()
+            .into_iter()
+            .map(|(x, reasons)| {
+                let mut ww_x: DiGraph<TransactionId> = DiGraph::default();
+                for (t2, t1) in reasons.keys() {
+                    ww_x.add_edge(*t2, *t1);
+                }
+                (x, ww_x)
+            })
+            .collect()
+    }
+
+    /// Returns the write-write edges of [`AtomicTransactionPO::causal_ww`], each with the reason
+    /// it is implied by the visibility relation.
+    #[must_use]
+    pub fn causal_ww_reasons(
+        &self,
+    ) -> HashMap<Variable, HashMap<(TransactionId, TransactionId), WriteOrderReason>> {
+        let mut ww: HashMap<Variable, HashMap<(TransactionId, TransactionId), WriteOrderReason>> =
+            HashMap::default();
 
         for (x, wr_x) in &self.write_read_relation {
-            let mut ww_x: DiGraph<TransactionId> = DiGraph::default();
-            for (t1, t3s) in &wr_x.adj_map {
+            let mut ww_x = HashMap::new();
+            for (t1, t3s) in wr_x.adj_map.iter().filter(|(t, _)| self.writes(t, x)) {
                 // t3s reads x from t1
                 // !t3s.contains(t1) - otherwise, it's a cycle in wr_x
-                for (t2, _) in &wr_x.adj_map {
+                for t2 in wr_x.adj_map.keys().filter(|t| self.writes(t, x)) {
                     // t1 and t2 both writes on x
-                    if t1 != t2
-                        && (self.visibility_relation.has_edge(t2, t1)
-                            || t3s
-                                .iter()
-                                .any(|t3| t3 != t2 && self.visibility_relation.has_edge(t2, t3)))
-                    {
-                        // it is obvious that vis(t2, t1) implies ww(t2, t1),
-                        // in other case, if vis(t2, t3), then t2 overwrites t1's write, read by t3
-                        // ┌──── t2 ─────┐
-                        // │ww_x      vis│
-                        // V             V
-                        // t1───────────>t3
-                        //      wr_x
-                        // t3 != t2 check is skipped, as acyclic vis(t3, t2) implies t3 != t2
-                        ww_x.add_edge(*t2, *t1);
+                    if t1 == t2 {
+                        continue;
+                    }
+                    // it is obvious that vis(t2, t1) implies ww(t2, t1),
+                    // in other case, if vis(t2, t3), then t2 overwrites t1's write, read by t3
+                    // ┌──── t2 ─────┐
+                    // │ww_x      vis│
+                    // V             V
+                    // t1───────────>t3
+                    //      wr_x
+                    // t3 != t2 check is skipped, as acyclic vis(t3, t2) implies t3 != t2
+                    // a reader explains the edge even after it is added to the visibility relation
+                    let reason = t3s
+                        .iter()
+                        .find(|t3| *t3 != t2 && self.visibility_relation.has_edge(t2, t3))
+                        .map(|t3| WriteOrderReason::VisibleToReader { reader: *t3 })
+                        .or_else(|| {
+                            self.visibility_relation
+                                .has_edge(t2, t1)
+                                .then_some(WriteOrderReason::Visible)
+                        });
+                    if let Some(reason) = reason {
+                        ww_x.insert((*t2, *t1), reason);
                     }
                 }
             }
@@ -170,10 +295,10 @@ where
 
         for (x, wr_x) in &self.write_read_relation {
             let mut rw_x: DiGraph<TransactionId> = DiGraph::default();
-            for (t1, t3s) in &wr_x.adj_map {
+            for (t1, t3s) in wr_x.adj_map.iter().filter(|(t, _)| self.writes(t, x)) {
                 // t3s reads x from t1
                 // !t3s.contains(t1) - otherwise, it's a cycle in wr_x
-                for (t2, _) in &wr_x.adj_map {
+                for t2 in wr_x.adj_map.keys().filter(|t| self.writes(t, x)) {
                     // t1 and t2 both writes on x
                     if t1 != t2 {
                         if self.visibility_relation.has_edge(t1, t2) {
@@ -206,7 +331,8 @@ where
         rw
     }
 
-    #[must_use] pub fn has_valid_visibility(&self) -> bool {
+    #[must_use]
+    pub fn has_valid_visibility(&self) -> bool {
         self.visibility_relation.is_acyclic()
     }
 }
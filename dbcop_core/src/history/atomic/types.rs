@@ -6,26 +6,35 @@
 //! Also, if a transaction has a `wr_x` child, then it commits a write on variable `x`.
 //!
 //! So it suffices to maintain the _write-read_ relation per variable across the transactions and the _write-set_ of each transaction.
+//!
+//! An aborted transaction, one that is neither committed nor [observed](observed_unknown), has
+//! no effect: it keeps its place in the session order, with empty read and write sets.
 
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter, Result as FmtResult};
 use core::hash::Hash;
 
-use crate::history::non_atomic::error::Error as NonAtomicError;
-use crate::history::non_atomic::types::{Event, EventId};
-use crate::{
-    history::non_atomic::{get_all_writes, types::Session},
-    solver::{error::Error, repeatable_read::check_repeatable_read},
-};
 use hashbrown::{HashMap, HashSet};
 
+use crate::history::non_atomic::error::Error as NonAtomicError;
+use crate::history::non_atomic::types::{Event, EventId, Session};
+use crate::history::non_atomic::{get_all_writes, observed_unknown, session_predecessors};
+use crate::solver::error::Error;
+use crate::solver::repeatable_read::check_repeatable_read;
+
 /// Information about a transaction.
+///
 /// `reads` is the read-set of the current transaction, mapping each variable to the transaction that it read from.
 /// `writes` is the write-set of the current transaction.
-#[derive(Debug)]
+/// `predecessors` are the transactions immediately before the current transaction in the session order.
+#[derive(Debug, Clone)]
 pub struct AtomicTransactionInfo<Variable> {
     pub reads: HashMap<Variable, TransactionId>,
     pub writes: HashSet<Variable>,
+    pub predecessors: Vec<TransactionId>,
 }
 
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct TransactionId {
     pub session_id: u64,
@@ -42,7 +51,82 @@ impl TransactionId {
     }
 }
 
-#[derive(Debug)]
+/// Renders as `s<session>.t<height>`, or `root` for the root transaction.
+impl Display for TransactionId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        if *self == Self::root() {
+            write!(f, "root")
+        } else {
+            write!(f, "s{}.t{}", self.session_id, self.session_height)
+        }
+    }
+}
+
+/// Origin of an edge of the visibility relation.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EdgeKind<Variable> {
+    /// The source precedes the target in its session
+    SessionOrder,
+    /// The target reads the variable from the source
+    WriteRead(Variable),
+    /// The target overwrites the version of the variable written by the source
+    WriteWrite(Variable),
+    /// The target overwrites the version of the variable read by the source
+    ReadWrite(Variable),
+    /// The source precedes the target by knowledge from outside the history, such as commit
+    /// timestamps
+    External,
+}
+
+/// Renders as `so`, `wr(x)`, `ww(x)`, `rw(x)` or `ext`.
+impl<Variable> Display for EdgeKind<Variable>
+where
+    Variable: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::SessionOrder => write!(f, "so"),
+            Self::WriteRead(variable) => write!(f, "wr({variable})"),
+            Self::WriteWrite(variable) => write!(f, "ww({variable})"),
+            Self::ReadWrite(variable) => write!(f, "rw({variable})"),
+            Self::External => write!(f, "ext"),
+        }
+    }
+}
+
+/// Why a write of a variable precedes another write of it under causal consistency.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WriteOrderReason {
+    /// The earlier write is visible to the later one
+    Visible,
+    /// The earlier write is visible to `reader`, which reads the later one
+    VisibleToReader { reader: TransactionId },
+}
+
+/// An edge of the visibility relation with its origin.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Edge<Variable> {
+    pub source: TransactionId,
+    pub target: TransactionId,
+    pub kind: EdgeKind<Variable>,
+}
+
+/// Renders as `s1.t0 -wr(x)-> s2.t0`.
+impl<Variable> Display for Edge<Variable>
+where
+    Variable: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{} -{}-> {}", self.source, self.kind, self.target)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct AtomicTransactionHistory<Variable>(
     pub HashMap<TransactionId, AtomicTransactionInfo<Variable>>,
 );
@@ -59,6 +143,7 @@ where
         check_repeatable_read(histories)?;
 
         let all_writes = get_all_writes(histories)?;
+        let observed = observed_unknown(histories);
 
         let mut atomic_history = HashMap::new();
 
@@ -72,8 +157,15 @@ where
                 let mut current_transaction_info = AtomicTransactionInfo {
                     reads: HashMap::new(),
                     writes: HashSet::new(),
+                    predecessors: session_predecessors(current_transaction_id, transaction)?,
                 };
 
+                // an aborted transaction keeps its place in the session order, without effects
+                if !transaction.committed && !observed.contains(&current_transaction_id) {
+                    atomic_history.insert(current_transaction_id, current_transaction_info);
+                    continue;
+                }
+
                 for (i_event, event) in (0..).zip(transaction.events.iter()) {
                     let event_id = EventId {
                         session_id: i_node,
@@ -0,0 +1,18 @@
+use crate::history::non_atomic::types::EventId;
+
+/// Error checking a counter history
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+#[derive(Debug)]
+pub enum Error<Variable> {
+    /// A read of a value that no set of the additions it may observe sums up to
+    UnreachableRead {
+        read: EventId,
+        variable: Variable,
+        value: i64,
+        /// The least and the greatest values the read may observe
+        range: [i64; 2],
+    },
+    /// No serial order of the committed transactions explains every read
+    NotSerializable,
+}
@@ -0,0 +1,377 @@
+//! Counter histories, where writes are increments and reads observe aggregate values.
+//!
+//! Transactions add deltas to counters, such as `x += 1`, and read their values, such as
+//! `x == 5`, as in bank-transfer workloads. A read does not name the write it observes, so the
+//! set of additions each read observes is inferred instead:
+//! - [`check_bounds`] bounds each read by the additions it must observe, the earlier ones of its
+//!   session, and those it may observe, the ones of the other sessions, and rejects a read outside
+//!   these bounds without searching,
+//! - [`CounterSerializabilitySolver`] searches for a serial order of the committed transactions in
+//!   which every read returns the sum of the additions before it,
+//! - [`observed_writes`] reads the additions each read observes off that order.
+//!
+//! The additions commute, so the value of a counter only depends on the set of transactions placed
+//! so far, which is what the linearization search remembers: its memoization stays exact.
+//! Uncommitted transactions are ignored, as their additions are never observed.
+
+pub mod error;
+pub mod types;
+
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+use hashbrown::HashMap;
+
+use crate::history::atomic::types::TransactionId;
+use crate::history::counter::error::Error;
+use crate::history::counter::types::{CounterEvent, CounterSession};
+use crate::history::non_atomic::types::EventId;
+use crate::solver::constrained_linearization::ConstrainedLinearizationSolver;
+
+/// Returns the committed transactions of the history with their ids, in session order.
+fn committed<Variable>(
+    histories: &[CounterSession<Variable>],
+) -> impl Iterator<Item = (TransactionId, &[CounterEvent<Variable>])> {
+    (1..).zip(histories).flat_map(|(session_id, session)| {
+        (0..)
+            .zip(session)
+            .filter(|(_, transaction)| transaction.committed)
+            .map(move |(session_height, transaction)| {
+                (
+                    TransactionId {
+                        session_id,
+                        session_height,
+                    },
+                    transaction.events.as_slice(),
+                )
+            })
+    })
+}
+
+/// Sums of the positive and of the negative deltas added to each variable.
+fn add_deltas<'a, Variable>(
+    sums: &mut HashMap<&'a Variable, [i64; 2]>,
+    events: &'a [CounterEvent<Variable>],
+) where
+    Variable: Eq + Hash,
+{
+    for event in events {
+        if let CounterEvent::Add { variable, delta } = event {
+            let sum = sums.entry(variable).or_default();
+            if *delta < 0 {
+                sum[0] += delta;
+            } else {
+                sum[1] += delta;
+            }
+        }
+    }
+}
+
+/// Checks that every committed read returns a value between the least and the greatest values it
+/// may observe. See the module documentation.
+///
+/// # Errors
+///
+/// Returns [`Error::UnreachableRead`] for the first read outside its bounds.
+pub fn check_bounds<Variable>(histories: &[CounterSession<Variable>]) -> Result<(), Error<Variable>>
+where
+    Variable: Eq + Hash + Clone,
+{
+    let mut total = HashMap::new();
+    for (_, events) in committed(histories) {
+        add_deltas(&mut total, events);
+    }
+
+    for (session_id, session) in (1..).zip(histories) {
+        // the additions of the session are observed in order, so not by the other reads
+        let mut own = HashMap::new();
+        for transaction in session.iter().filter(|transaction| transaction.committed) {
+            add_deltas(&mut own, &transaction.events);
+        }
+
+        let mut observed: HashMap<&Variable, i64> = HashMap::new();
+        for (session_height, transaction) in (0..).zip(session) {
+            if !transaction.committed {
+                continue;
+            }
+            for (transaction_height, event) in (0..).zip(&transaction.events) {
+                match event {
+                    CounterEvent::Add { variable, delta } => {
+                        *observed.entry(variable).or_default() += delta;
+                    }
+                    CounterEvent::Read { variable, value } => {
+                        let base = observed.get(variable).copied().unwrap_or_default();
+                        let [total_negative, total_positive] =
+                            total.get(variable).copied().unwrap_or_default();
+                        let [own_negative, own_positive] =
+                            own.get(variable).copied().unwrap_or_default();
+                        let range = [
+                            base + total_negative - own_negative,
+                            base + total_positive - own_positive,
+                        ];
+                        if !(range[0]..=range[1]).contains(value) {
+                            return Err(Error::UnreachableRead {
+                                read: EventId {
+                                    session_id,
+                                    session_height,
+                                    transaction_height,
+                                },
+                                variable: variable.clone(),
+                                value: *value,
+                                range,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Searches for a serial order of the committed transactions of a counter history, where every
+/// read returns the sum of the additions to its variable before it.
+#[derive(Debug, Clone)]
+pub struct CounterSerializabilitySolver<Variable> {
+    transactions: HashMap<TransactionId, Vec<CounterEvent<Variable>>>,
+    /// The next committed transaction of the session of each committed transaction
+    next: HashMap<TransactionId, TransactionId>,
+    /// The values of the counters after the transactions placed so far
+    values: HashMap<Variable, i64>,
+}
+
+impl<Variable> From<&[CounterSession<Variable>]> for CounterSerializabilitySolver<Variable>
+where
+    Variable: Clone,
+{
+    fn from(histories: &[CounterSession<Variable>]) -> Self {
+        let mut transactions = HashMap::new();
+        let mut next = HashMap::new();
+        let mut previous: Option<TransactionId> = None;
+        for (id, events) in committed(histories) {
+            if let Some(previous) = previous.filter(|p| p.session_id == id.session_id) {
+                next.insert(previous, id);
+            }
+            previous = Some(id);
+            transactions.insert(id, events.to_vec());
+        }
+        Self {
+            transactions,
+            next,
+            values: HashMap::new(),
+        }
+    }
+}
+
+impl<Variable> CounterSerializabilitySolver<Variable>
+where
+    Variable: Eq + Hash + Clone,
+{
+    /// Adds the deltas of `id` to the counters, negated if `sign` is `-1`.
+    fn apply(&mut self, id: &TransactionId, sign: i64) {
+        for event in &self.transactions[id] {
+            if let CounterEvent::Add { variable, delta } = event {
+                *self.values.entry(variable.clone()).or_default() += sign * delta;
+            }
+        }
+    }
+}
+
+impl<Variable> ConstrainedLinearizationSolver for CounterSerializabilitySolver<Variable>
+where
+    Variable: Eq + Hash + Clone,
+{
+    type Vertex = TransactionId;
+
+    fn get_root(&self) -> Self::Vertex {
+        TransactionId::root()
+    }
+
+    fn children_of(&self, source: &Self::Vertex) -> Option<Vec<Self::Vertex>> {
+        self.next.get(source).map(|next| alloc::vec![*next])
+    }
+
+    fn allow_next(&self, _linearization: &[Self::Vertex], v: &Self::Vertex) -> bool {
+        let mut local: HashMap<&Variable, i64> = HashMap::new();
+        self.transactions[v].iter().all(|event| match event {
+            CounterEvent::Add { variable, delta } => {
+                *local.entry(variable).or_default() += delta;
+                true
+            }
+            CounterEvent::Read { variable, value } => {
+                let current = self.values.get(variable).copied().unwrap_or_default()
+                    + local.get(variable).copied().unwrap_or_default();
+                current == *value
+            }
+        })
+    }
+
+    fn vertices(&self) -> Vec<Self::Vertex> {
+        self.transactions.keys().copied().collect()
+    }
+
+    fn forward_book_keeping(&mut self, linearization: &[Self::Vertex]) {
+        self.apply(linearization.last().unwrap(), 1);
+    }
+
+    fn backtrack_book_keeping(&mut self, linearization: &[Self::Vertex]) {
+        self.apply(linearization.last().unwrap(), -1);
+    }
+}
+
+/// Returns a serial order of the committed transactions that explains every read, after checking
+/// the bounds of the reads.
+///
+/// # Errors
+///
+/// Returns [`Error::UnreachableRead`] if a read is outside its bounds, and
+/// [`Error::NotSerializable`] if there is no such order.
+pub fn check_serializable<Variable>(
+    histories: &[CounterSession<Variable>],
+) -> Result<Vec<TransactionId>, Error<Variable>>
+where
+    Variable: Eq + Hash + Clone,
+{
+    check_bounds(histories)?;
+    let mut solver = CounterSerializabilitySolver::from(histories);
+    if solver.transactions.is_empty() {
+        return Ok(Vec::new());
+    }
+    solver.get_linearization().ok_or(Error::NotSerializable)
+}
+
+/// Returns the transactions whose additions each committed read observes in the serial order
+/// `linearization`, i.e. the earlier transactions adding to its variable, in order.
+#[must_use]
+pub fn observed_writes<Variable>(
+    histories: &[CounterSession<Variable>],
+    linearization: &[TransactionId],
+) -> HashMap<EventId, Vec<TransactionId>>
+where
+    Variable: Eq,
+{
+    let transactions: HashMap<TransactionId, &[CounterEvent<Variable>]> =
+        committed(histories).collect();
+    let adds = |id: &TransactionId, variable: &Variable| {
+        transactions.get(id).is_some_and(|events| {
+            events.iter().any(|event| {
+                matches!(event, CounterEvent::Add { variable: added, .. } if added == variable)
+            })
+        })
+    };
+
+    let mut observed = HashMap::new();
+    for (i, id) in linearization.iter().enumerate() {
+        let Some(events) = transactions.get(id) else {
+            continue;
+        };
+        for (transaction_height, event) in (0..).zip(events.iter()) {
+            if let CounterEvent::Read { variable, .. } = event {
+                observed.insert(
+                    EventId {
+                        session_id: id.session_id,
+                        session_height: id.session_height,
+                        transaction_height,
+                    },
+                    linearization[..i]
+                        .iter()
+                        .filter(|earlier| adds(earlier, variable))
+                        .copied()
+                        .collect(),
+                );
+            }
+        }
+    }
+    observed
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::history::counter::types::CounterTransaction;
+
+    #[test]
+    fn test_counter() {
+        let id = |session_id, session_height| TransactionId {
+            session_id,
+            session_height,
+        };
+        // two deposits, where s1 reads its own on top of the one of s2
+        let histories = vec![
+            vec![CounterTransaction::committed(vec![
+                CounterEvent::add("x", 10),
+                CounterEvent::read("x", 15),
+            ])],
+            vec![CounterTransaction::committed(vec![CounterEvent::add(
+                "x", 5,
+            )])],
+            vec![
+                CounterTransaction::committed(vec![CounterEvent::read("x", 5)]),
+                CounterTransaction::committed(vec![CounterEvent::read("x", 15)]),
+            ],
+            vec![CounterTransaction::uncommitted(vec![CounterEvent::add(
+                "x", 100,
+            )])],
+        ];
+
+        let linearization = check_serializable(&histories).unwrap();
+        let observed = observed_writes(&histories, &linearization);
+        let read = |session_id, session_height, transaction_height| EventId {
+            session_id,
+            session_height,
+            transaction_height,
+        };
+        assert_eq!(observed[&read(1, 0, 1)], vec![id(2, 0)]);
+        assert_eq!(observed[&read(3, 0, 0)], vec![id(2, 0)]);
+        assert_eq!(observed[&read(3, 1, 0)], vec![id(2, 0), id(1, 0)]);
+    }
+
+    #[test]
+    fn test_lost_update() {
+        // both transactions read 0 before adding 1, yet a later read sees both additions
+        let histories = vec![
+            vec![CounterTransaction::committed(vec![
+                CounterEvent::read("x", 0),
+                CounterEvent::add("x", 1),
+            ])],
+            vec![CounterTransaction::committed(vec![
+                CounterEvent::read("x", 0),
+                CounterEvent::add("x", 1),
+            ])],
+            vec![CounterTransaction::committed(vec![CounterEvent::read(
+                "x", 2,
+            )])],
+        ];
+        assert!(check_bounds(&histories).is_ok());
+        assert!(matches!(
+            check_serializable(&histories),
+            Err(Error::NotSerializable)
+        ));
+    }
+
+    #[test]
+    fn test_unreachable_read() {
+        let histories = vec![
+            vec![CounterTransaction::committed(vec![
+                CounterEvent::add("x", 1),
+                CounterEvent::add("x", -2),
+            ])],
+            vec![
+                CounterTransaction::committed(vec![CounterEvent::add("x", 3)]),
+                CounterTransaction::committed(vec![CounterEvent::read("x", 5)]),
+            ],
+        ];
+        // the read observes 3, and may observe 1 and -2
+        assert!(matches!(
+            check_serializable(&histories),
+            Err(Error::UnreachableRead {
+                variable: "x",
+                value: 5,
+                range: [1, 4],
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,47 @@
+use alloc::vec::Vec;
+
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CounterEvent<Variable> {
+    /// Adds `delta` to the counter of `variable`, such as `x += 1`
+    Add { variable: Variable, delta: i64 },
+    /// Reads the value of the counter of `variable`, such as `x == 5`
+    Read { variable: Variable, value: i64 },
+}
+
+impl<Variable> CounterEvent<Variable> {
+    pub const fn add(variable: Variable, delta: i64) -> Self {
+        Self::Add { variable, delta }
+    }
+
+    pub const fn read(variable: Variable, value: i64) -> Self {
+        Self::Read { variable, value }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Debug, Clone)]
+pub struct CounterTransaction<Variable> {
+    pub events: Vec<CounterEvent<Variable>>,
+    pub committed: bool,
+}
+
+impl<Variable> CounterTransaction<Variable> {
+    #[must_use]
+    pub const fn committed(events: Vec<CounterEvent<Variable>>) -> Self {
+        Self {
+            events,
+            committed: true,
+        }
+    }
+
+    #[must_use]
+    pub const fn uncommitted(events: Vec<CounterEvent<Variable>>) -> Self {
+        Self {
+            events,
+            committed: false,
+        }
+    }
+}
+
+pub type CounterSession<Variable> = Vec<CounterTransaction<Variable>>;
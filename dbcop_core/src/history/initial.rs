@@ -0,0 +1,129 @@
+//! Interpretations of the reads of uninitialized variables.
+//!
+//! The checkers take a read without a version, such as [`Event::read_empty`], as a read of the
+//! initial version, written by the root transaction before every other one. Not every database
+//! behaves so: some return a nondeterministic default for an absent key, and some histories write
+//! the initial values in transactions of their own, so that no read may return an unwritten value.
+//! [`InitialValuePolicy::apply`] rewrites a history so that the checkers interpret its reads of
+//! uninitialized variables under a policy.
+
+use alloc::vec::Vec;
+
+use crate::history::non_atomic::error::Error;
+use crate::history::non_atomic::types::{Event, EventId, Session};
+
+/// How the reads without a version are interpreted.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InitialValuePolicy {
+    /// Reads the initial version, written by the root transaction
+    #[default]
+    RootZero,
+    /// Reads an arbitrary value of an absent key, which constrains nothing
+    AbsentKeyOk,
+    /// Is invalid, as the history writes the initial values in transactions of its own
+    ExplicitInitTransaction,
+}
+
+impl InitialValuePolicy {
+    /// Returns the history that the checkers interpret under this policy.
+    ///
+    /// [`InitialValuePolicy::AbsentKeyOk`] drops the reads without a version, so the event ids of
+    /// the errors of the checkers refer to the returned history. The transaction ids are kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::IncompleteHistory`] on the first read without a version under
+    /// [`InitialValuePolicy::ExplicitInitTransaction`].
+    pub fn apply<Variable, Version>(
+        self,
+        histories: &[Session<Variable, Version>],
+    ) -> Result<Vec<Session<Variable, Version>>, Error<Variable, Version>>
+    where
+        Variable: Clone,
+        Version: Clone,
+    {
+        match self {
+            Self::RootZero => Ok(histories.to_vec()),
+            Self::AbsentKeyOk => Ok(histories
+                .iter()
+                .map(|session| {
+                    session
+                        .iter()
+                        .map(|transaction| {
+                            let mut transaction = transaction.clone();
+                            transaction.events.retain(|event| {
+                                !matches!(event, Event::Read { version: None, .. })
+                            });
+                            transaction
+                        })
+                        .collect()
+                })
+                .collect()),
+            Self::ExplicitInitTransaction => {
+                for (session_id, session) in (1..).zip(histories) {
+                    for (session_height, transaction) in (0..).zip(session) {
+                        for (transaction_height, event) in (0..).zip(&transaction.events) {
+                            if let Event::Read { version: None, .. } = event {
+                                return Err(Error::IncompleteHistory {
+                                    event: event.clone(),
+                                    id: EventId {
+                                        session_id,
+                                        session_height,
+                                        transaction_height,
+                                    },
+                                });
+                            }
+                        }
+                    }
+                }
+                Ok(histories.to_vec())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::check::check;
+    use crate::history::non_atomic::types::Transaction;
+    use crate::Consistency;
+
+    #[test]
+    fn test_initial_value_policy() {
+        // s2 reads x=1, then an uninitialized x: a nondeterministic default, not the initial x
+        let histories = vec![
+            vec![Transaction::committed(vec![Event::write("x", 1)])],
+            vec![
+                Transaction::committed(vec![Event::read("x", 1)]),
+                Transaction::committed(vec![Event::read_empty("x")]),
+            ],
+        ];
+
+        let root_zero = InitialValuePolicy::RootZero.apply(&histories).unwrap();
+        assert!(check(&root_zero, Consistency::Causal).is_err());
+
+        let absent_key_ok = InitialValuePolicy::AbsentKeyOk.apply(&histories).unwrap();
+        assert!(absent_key_ok[1][1].events.is_empty());
+        assert!(check(&absent_key_ok, Consistency::Serializable).is_ok());
+
+        assert!(matches!(
+            InitialValuePolicy::ExplicitInitTransaction.apply(&histories),
+            Err(Error::IncompleteHistory {
+                id: EventId {
+                    session_id: 2,
+                    session_height: 1,
+                    transaction_height: 0,
+                },
+                ..
+            })
+        ));
+        assert!(InitialValuePolicy::ExplicitInitTransaction
+            .apply(&histories[..1])
+            .is_ok());
+    }
+}
@@ -0,0 +1,353 @@
+//! Interning of variables and versions as dense integer ids.
+//!
+//! The checkers clone and hash variables throughout the partial orders they build, which is costly
+//! for large alphabets of string variables, such as parsed or imported histories. [`intern`]
+//! replaces every variable and version by a [`VariableId`] or a [`VersionId`], in order of first
+//! occurrence, and returns the [`SymbolTable`] that maps them back. A read refers to the write it
+//! reads by variable and version, so interning one-to-one keeps every write-read reference, and
+//! the interned history satisfies exactly the levels the original one does.
+
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter, Result as FmtResult};
+use core::hash::Hash;
+
+use hashbrown::HashMap;
+
+use crate::history::atomic::types::{Edge, EdgeKind};
+use crate::history::non_atomic::error::Error as NonAtomicError;
+use crate::history::non_atomic::types::{Event, Session, Transaction};
+use crate::solver::error::Error;
+
+/// An interned variable.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VariableId(pub u32);
+
+/// An interned version.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VersionId(pub u32);
+
+/// Renders as `v0`.
+impl Display for VariableId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "v{}", self.0)
+    }
+}
+
+impl Display for VersionId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The variables and versions of an interned history, indexed by their ids.
+#[derive(Debug, Clone)]
+pub struct SymbolTable<Variable, Version> {
+    variables: Vec<Variable>,
+    versions: Vec<Version>,
+}
+
+/// Assigns dense ids to values, in order of first occurrence.
+struct Interner<'a, T> {
+    ids: HashMap<&'a T, u32>,
+    values: Vec<T>,
+}
+
+impl<'a, T> Interner<'a, T>
+where
+    T: Eq + Hash + Clone,
+{
+    fn new() -> Self {
+        Self {
+            ids: HashMap::new(),
+            values: Vec::new(),
+        }
+    }
+
+    fn intern(&mut self, value: &'a T) -> u32 {
+        *self.ids.entry(value).or_insert_with(|| {
+            self.values.push(value.clone());
+            u32::try_from(self.values.len() - 1).expect("fewer than 2^32 distinct values")
+        })
+    }
+}
+
+/// Replaces the variables and versions of a history by ids, in order of first occurrence.
+///
+/// Returns the interned history and the table to resolve its ids.
+///
+/// # Panics
+///
+/// Panics if the history has 2^32 distinct variables or versions or more.
+#[must_use]
+pub fn intern<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+) -> (
+    Vec<Session<VariableId, VersionId>>,
+    SymbolTable<Variable, Version>,
+)
+where
+    Variable: Eq + Hash + Clone,
+    Version: Eq + Hash + Clone,
+{
+    let mut variables = Interner::new();
+    let mut versions = Interner::new();
+    let interned = histories
+        .iter()
+        .map(|session| {
+            session
+                .iter()
+                .map(|transaction| Transaction {
+                    events: transaction
+                        .events
+                        .iter()
+                        .map(|event| match event {
+                            Event::Read { variable, version } => Event::Read {
+                                variable: VariableId(variables.intern(variable)),
+                                version: version
+                                    .as_ref()
+                                    .map(|version| VersionId(versions.intern(version))),
+                            },
+                            Event::Write { variable, version } => Event::write(
+                                VariableId(variables.intern(variable)),
+                                VersionId(versions.intern(version)),
+                            ),
+                        })
+                        .collect(),
+                    committed: transaction.committed,
+                    unknown: transaction.unknown,
+                    predecessors: transaction.predecessors.clone(),
+                    meta: transaction.meta.clone(),
+                })
+                .collect()
+        })
+        .collect();
+    (
+        interned,
+        SymbolTable {
+            variables: variables.values,
+            versions: versions.values,
+        },
+    )
+}
+
+impl<Variable, Version> SymbolTable<Variable, Version>
+where
+    Variable: Clone,
+    Version: Clone,
+{
+    /// Returns the variable of `id`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not from this table.
+    #[must_use]
+    pub fn variable(&self, id: VariableId) -> &Variable {
+        &self.variables[id.0 as usize]
+    }
+
+    /// Returns the version of `id`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not from this table.
+    #[must_use]
+    pub fn version(&self, id: VersionId) -> &Version {
+        &self.versions[id.0 as usize]
+    }
+
+    #[must_use]
+    pub fn event(&self, event: &Event<VariableId, VersionId>) -> Event<Variable, Version> {
+        match event {
+            Event::Read { variable, version } => Event::Read {
+                variable: self.variable(*variable).clone(),
+                version: version.map(|version| self.version(version).clone()),
+            },
+            Event::Write { variable, version } => Event::write(
+                self.variable(*variable).clone(),
+                self.version(*version).clone(),
+            ),
+        }
+    }
+
+    #[must_use]
+    pub fn edge(&self, edge: &Edge<VariableId>) -> Edge<Variable> {
+        let variable = |id: &VariableId| self.variable(*id).clone();
+        Edge {
+            source: edge.source,
+            target: edge.target,
+            kind: match &edge.kind {
+                EdgeKind::SessionOrder => EdgeKind::SessionOrder,
+                EdgeKind::WriteRead(id) => EdgeKind::WriteRead(variable(id)),
+                EdgeKind::WriteWrite(id) => EdgeKind::WriteWrite(variable(id)),
+                EdgeKind::ReadWrite(id) => EdgeKind::ReadWrite(variable(id)),
+                EdgeKind::External => EdgeKind::External,
+            },
+        }
+    }
+
+    /// Returns the error of the original history for an error of the interned one.
+    #[must_use]
+    pub fn error(&self, error: Error<VariableId, VersionId>) -> Error<Variable, Version> {
+        match error {
+            Error::NonAtomic(error) => Error::NonAtomic(self.non_atomic_error(error)),
+            Error::Invalid(level) => Error::Invalid(level),
+            Error::Cycle { level, cycle } => Error::Cycle {
+                level,
+                cycle: cycle.iter().map(|edge| self.edge(edge)).collect(),
+            },
+            Error::UnknownTransaction(id) => Error::UnknownTransaction(id),
+        }
+    }
+
+    fn non_atomic_error(
+        &self,
+        error: NonAtomicError<VariableId, VersionId>,
+    ) -> NonAtomicError<Variable, Version> {
+        match error {
+            NonAtomicError::IncompleteHistory { event, id } => NonAtomicError::IncompleteHistory {
+                event: self.event(&event),
+                id,
+            },
+            NonAtomicError::SameVersionWrite { event, ids } => NonAtomicError::SameVersionWrite {
+                event: self.event(&event),
+                ids,
+            },
+            NonAtomicError::InconsistentLocalRead {
+                read_event_id,
+                write_event_id,
+                read_event,
+            } => NonAtomicError::InconsistentLocalRead {
+                read_event_id,
+                write_event_id,
+                read_event: self.event(&read_event),
+            },
+            NonAtomicError::UnsuccessfulEventRead {
+                read_event,
+                read_event_id,
+                write_event,
+                write_event_id,
+            } => NonAtomicError::UnsuccessfulEventRead {
+                read_event: self.event(&read_event),
+                read_event_id,
+                write_event: self.event(&write_event),
+                write_event_id,
+            },
+            NonAtomicError::UnsuccessfulTransactionRead {
+                read_event,
+                read_event_id,
+                write_event,
+                write_event_id,
+            } => NonAtomicError::UnsuccessfulTransactionRead {
+                read_event: self.event(&read_event),
+                read_event_id,
+                write_event: self.event(&write_event),
+                write_event_id,
+            },
+            NonAtomicError::NonRepeatableRead {
+                read_event,
+                read_event_id,
+                write_event_ids,
+            } => NonAtomicError::NonRepeatableRead {
+                read_event: self.event(&read_event),
+                read_event_id,
+                write_event_ids,
+            },
+            NonAtomicError::OverwrittenRead {
+                read_event,
+                read_event_id,
+                overwritten_write_event_id,
+                committed_write_event,
+                committed_write_event_id,
+            } => NonAtomicError::OverwrittenRead {
+                read_event: self.event(&read_event),
+                read_event_id,
+                overwritten_write_event_id,
+                committed_write_event: self.event(&committed_write_event),
+                committed_write_event_id,
+            },
+            NonAtomicError::UncommittedWrite {
+                read_event,
+                read_event_id,
+                write_event_id,
+            } => NonAtomicError::UncommittedWrite {
+                read_event: self.event(&read_event),
+                read_event_id,
+                write_event_id,
+            },
+            NonAtomicError::AbortedWritesRead {
+                transaction,
+                observed,
+                unobserved,
+            } => NonAtomicError::AbortedWritesRead {
+                transaction,
+                observed,
+                unobserved,
+            },
+            NonAtomicError::InvalidPredecessor { id, predecessor } => {
+                NonAtomicError::InvalidPredecessor { id, predecessor }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::{String, ToString};
+    use alloc::vec;
+
+    use super::*;
+    use crate::check::{check, check_interned};
+    use crate::Consistency;
+
+    #[test]
+    fn test_intern() {
+        let name = |s: &str| s.to_string();
+        // write skew: snapshot isolation, but not serializable
+        let histories: Vec<Session<String, String>> = vec![
+            vec![Transaction::committed(vec![
+                Event::read_empty(name("account:alice")),
+                Event::read_empty(name("account:bob")),
+                Event::write(name("account:alice"), name("-10")),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read_empty(name("account:alice")),
+                Event::read_empty(name("account:bob")),
+                Event::write(name("account:bob"), name("-10")),
+            ])],
+        ];
+
+        let (interned, table) = intern(&histories);
+        assert_eq!(
+            interned[0][0].events[2],
+            Event::write(VariableId(0), VersionId(0))
+        );
+        assert_eq!(
+            interned[1][0].events[2],
+            Event::write(VariableId(1), VersionId(0))
+        );
+        assert_eq!(table.variable(VariableId(1)), "account:bob");
+        assert_eq!(
+            table.event(&interned[1][0].events[2]),
+            histories[1][0].events[2]
+        );
+
+        for level in [Consistency::SnapshotIsolation, Consistency::Serializable] {
+            let witness = check(&histories, level).ok();
+            assert_eq!(check_interned(&histories, level).ok(), witness);
+        }
+
+        // reads a version nobody writes
+        let histories = vec![vec![Transaction::committed(vec![Event::read(
+            name("x"),
+            name("1"),
+        )])]];
+        let Err(Error::NonAtomic(NonAtomicError::IncompleteHistory { event, .. })) =
+            check_interned(&histories, Consistency::CommittedRead)
+        else {
+            panic!("expected an incomplete history");
+        };
+        assert_eq!(event, Event::read(name("x"), name("1")));
+    }
+}
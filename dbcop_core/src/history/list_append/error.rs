@@ -0,0 +1,15 @@
+use alloc::vec::Vec;
+
+/// Error reducing a list-append history to a register history
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+#[derive(Debug)]
+pub enum Error<Variable, Value> {
+    /// Two reads of a variable where neither list is a prefix of the other
+    IncompatibleReads {
+        variable: Variable,
+        lists: [Vec<Value>; 2],
+    },
+    /// The same value is appended twice to a variable
+    DuplicateAppend { variable: Variable, value: Value },
+}
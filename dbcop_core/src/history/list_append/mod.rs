@@ -0,0 +1,264 @@
+//! List-append histories, as checked by Elle.
+//!
+//! Transactions append unique values to lists and read whole lists. A read reveals the order of
+//! all the appends in its list, so the longest read of each variable gives its version order.
+//! [`to_register_history`] uses this order to reduce a list-append history to a register history
+//! checked at a level:
+//! - for snapshot isolation and serializability, an append of the `i`-th value of the version order
+//!   reads the `(i - 1)`-th value, or the initial version if `i = 0`, then writes the `i`-th value,
+//! - otherwise, and for an append that no read observed, an append is a blind write of its value,
+//! - a read of a list reads its last value, or the initial version if the list is empty.
+//!
+//! A transaction keeps its commit status, which may be unknown.
+//!
+//! The reads of the appends make the version order explicit in the write-read relation, so the
+//! register history can be checked by the existing solvers. These reads are only sound for the
+//! levels that forbid lost updates: two transactions that read an empty list and then append to
+//! it concurrently satisfy causal and prefix consistency, but the second one would read the first
+//! append. Below snapshot isolation, the version order only shows through the reads of the lists.
+
+pub mod error;
+pub mod types;
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+use hashbrown::{HashMap, HashSet};
+
+use crate::history::list_append::error::Error;
+use crate::history::list_append::types::{ListEvent, ListSession};
+use crate::history::non_atomic::types::{Event, Session, Transaction};
+use crate::Consistency;
+
+/// Returns the version order of each variable, i.e. its longest read list.
+///
+/// # Errors
+///
+/// Returns [`Error::IncompatibleReads`] if two reads of a variable are not prefixes of one another.
+pub fn version_order<Variable, Value>(
+    histories: &[ListSession<Variable, Value>],
+) -> Result<HashMap<Variable, Vec<Value>>, Error<Variable, Value>>
+where
+    Variable: Eq + Hash + Clone,
+    Value: Eq + Clone,
+{
+    let mut orders: HashMap<Variable, Vec<Value>> = HashMap::new();
+    for event in histories
+        .iter()
+        .flatten()
+        .filter(|transaction| transaction.committed)
+        .flat_map(|transaction| &transaction.events)
+    {
+        if let ListEvent::Read { variable, values } = event {
+            let order = orders.entry(variable.clone()).or_default();
+            let (shorter, longer) = if order.len() < values.len() {
+                (&*order, values)
+            } else {
+                (values, &*order)
+            };
+            if !longer.starts_with(shorter) {
+                return Err(Error::IncompatibleReads {
+                    variable: variable.clone(),
+                    lists: [order.clone(), values.clone()],
+                });
+            }
+            if values.len() > order.len() {
+                order.clone_from(values);
+            }
+        }
+    }
+    Ok(orders)
+}
+
+/// Reduces a list-append history to a register history to check at `level`. See the module
+/// documentation.
+///
+/// # Errors
+///
+/// Returns [`Error::IncompatibleReads`] if the reads do not agree on a version order,
+/// and [`Error::DuplicateAppend`] if a value is appended twice to a variable.
+pub fn to_register_history<Variable, Value>(
+    histories: &[ListSession<Variable, Value>],
+    level: Consistency,
+) -> Result<Vec<Session<Variable, Value>>, Error<Variable, Value>>
+where
+    Variable: Eq + Hash + Clone,
+    Value: Eq + Hash + Clone,
+{
+    let orders = version_order(histories)?;
+
+    // the appends read their previous value only from snapshot isolation up
+    let explicit = matches!(
+        level,
+        Consistency::SnapshotIsolation | Consistency::Serializable
+    );
+    // the previous value of each observed value, read by its append
+    let previous: HashMap<(&Variable, &Value), Option<&Value>> = orders
+        .iter()
+        .filter(|_| explicit)
+        .flat_map(|(variable, values)| {
+            values
+                .iter()
+                .enumerate()
+                .map(move |(i, value)| ((variable, value), i.checked_sub(1).map(|j| &values[j])))
+        })
+        .collect();
+
+    let mut appended: HashSet<(&Variable, &Value)> = HashSet::new();
+
+    histories
+        .iter()
+        .map(|session| {
+            session
+                .iter()
+                .map(|transaction| {
+                    let mut events = Vec::new();
+                    for event in &transaction.events {
+                        match event {
+                            ListEvent::Append { variable, value } => {
+                                if !appended.insert((variable, value)) {
+                                    return Err(Error::DuplicateAppend {
+                                        variable: variable.clone(),
+                                        value: value.clone(),
+                                    });
+                                }
+                                if let Some(previous) = previous.get(&(variable, value)) {
+                                    events.push(Event::Read {
+                                        variable: variable.clone(),
+                                        version: previous.cloned(),
+                                    });
+                                }
+                                events.push(Event::write(variable.clone(), value.clone()));
+                            }
+                            ListEvent::Read { variable, values } => events.push(Event::Read {
+                                variable: variable.clone(),
+                                version: values.last().cloned(),
+                            }),
+                        }
+                    }
+                    Ok(Transaction {
+                        events,
+                        committed: transaction.committed,
+                        unknown: transaction.unknown,
+                        predecessors: None,
+                        meta: BTreeMap::new(),
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::check::check;
+    use crate::history::list_append::types::ListTransaction;
+    use crate::solver::causal::check_causal_read;
+    use crate::solver::constrained_linearization::ConstrainedLinearizationSolver;
+    use crate::solver::serializable::SerializabilitySolver;
+
+    #[test]
+    fn test_reduction() {
+        let histories = vec![
+            vec![ListTransaction::committed(vec![
+                ListEvent::append("x", 1),
+                ListEvent::append("x", 2),
+            ])],
+            vec![
+                ListTransaction::committed(vec![ListEvent::read("x", vec![1, 2])]),
+                ListTransaction::committed(vec![ListEvent::append("x", 3)]),
+            ],
+            vec![ListTransaction::committed(vec![ListEvent::read(
+                "x",
+                vec![1, 2, 3],
+            )])],
+        ];
+
+        let register = to_register_history(&histories, Consistency::Serializable).unwrap();
+
+        assert_eq!(
+            register[0][0].events,
+            vec![
+                Event::read_empty("x"),
+                Event::write("x", 1),
+                Event::read("x", 1),
+                Event::write("x", 2),
+            ]
+        );
+        assert_eq!(
+            register[1][1].events,
+            vec![Event::read("x", 2), Event::write("x", 3)]
+        );
+        assert_eq!(register[2][0].events, vec![Event::read("x", 3)]);
+
+        let po = check_causal_read(&register).unwrap();
+        assert!(SerializabilitySolver::from(po)
+            .get_linearization()
+            .is_some());
+
+        // below snapshot isolation, the appends are blind writes
+        let register = to_register_history(&histories, Consistency::Causal).unwrap();
+        assert_eq!(
+            register[0][0].events,
+            vec![Event::write("x", 1), Event::write("x", 2)]
+        );
+        assert_eq!(register[1][1].events, vec![Event::write("x", 3)]);
+    }
+
+    #[test]
+    fn test_lost_append() {
+        // both transactions observe the empty list before appending, but only one append survives
+        let histories = vec![
+            vec![ListTransaction::committed(vec![
+                ListEvent::read("x", vec![]),
+                ListEvent::append("x", 1),
+            ])],
+            vec![ListTransaction::committed(vec![
+                ListEvent::read("x", vec![]),
+                ListEvent::append("x", 2),
+            ])],
+            vec![ListTransaction::committed(vec![ListEvent::read(
+                "x",
+                vec![1, 2],
+            )])],
+        ];
+
+        // concurrent appends are allowed below snapshot isolation
+        for level in [Consistency::Causal, Consistency::Prefix] {
+            let register = to_register_history(&histories, level).unwrap();
+            assert!(check(&register, level).is_ok(), "{level:?}");
+        }
+        // the second appender reads x twice: empty, then 1 to append 2 after it
+        for level in [Consistency::SnapshotIsolation, Consistency::Serializable] {
+            let register = to_register_history(&histories, level).unwrap();
+            assert!(check(&register, level).is_err(), "{level:?}");
+        }
+    }
+
+    #[test]
+    fn test_incompatible_reads() {
+        let histories = vec![
+            vec![ListTransaction::committed(vec![
+                ListEvent::append("x", 1),
+                ListEvent::append("x", 2),
+            ])],
+            vec![ListTransaction::committed(vec![ListEvent::read(
+                "x",
+                vec![1, 2],
+            )])],
+            vec![ListTransaction::committed(vec![ListEvent::read(
+                "x",
+                vec![2],
+            )])],
+        ];
+
+        assert!(matches!(
+            to_register_history(&histories, Consistency::Serializable),
+            Err(Error::IncompatibleReads { variable: "x", .. })
+        ));
+    }
+}
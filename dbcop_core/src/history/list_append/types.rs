@@ -0,0 +1,66 @@
+use alloc::vec::Vec;
+
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ListEvent<Variable, Value> {
+    /// Appends `value` to the list of `variable`
+    Append { variable: Variable, value: Value },
+    /// Reads the whole list of `variable`
+    Read {
+        variable: Variable,
+        values: Vec<Value>,
+    },
+}
+
+impl<Variable, Value> ListEvent<Variable, Value> {
+    pub const fn append(variable: Variable, value: Value) -> Self {
+        Self::Append { variable, value }
+    }
+
+    pub const fn read(variable: Variable, values: Vec<Value>) -> Self {
+        Self::Read { variable, values }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Debug, Clone)]
+pub struct ListTransaction<Variable, Value> {
+    pub events: Vec<ListEvent<Variable, Value>>,
+    pub committed: bool,
+    /// The commit status was not observed. See
+    /// [`Transaction::unknown`](crate::history::non_atomic::types::Transaction::unknown).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub unknown: bool,
+}
+
+impl<Variable, Value> ListTransaction<Variable, Value> {
+    #[must_use]
+    pub const fn committed(events: Vec<ListEvent<Variable, Value>>) -> Self {
+        Self {
+            events,
+            committed: true,
+            unknown: false,
+        }
+    }
+
+    #[must_use]
+    pub const fn uncommitted(events: Vec<ListEvent<Variable, Value>>) -> Self {
+        Self {
+            events,
+            committed: false,
+            unknown: false,
+        }
+    }
+
+    /// A transaction whose commit status is unknown.
+    #[must_use]
+    pub const fn unknown(events: Vec<ListEvent<Variable, Value>>) -> Self {
+        Self {
+            events,
+            committed: false,
+            unknown: true,
+        }
+    }
+}
+
+pub type ListSession<Variable, Value> = Vec<ListTransaction<Variable, Value>>;
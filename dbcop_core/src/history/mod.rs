@@ -1,2 +1,11 @@
 pub mod atomic;
+pub mod counter;
+pub mod initial;
+pub mod intern;
+pub mod list_append;
 pub mod non_atomic;
+#[cfg(feature = "predicate-reads")]
+pub mod predicate;
+pub mod project;
+pub mod remap;
+pub mod stats;
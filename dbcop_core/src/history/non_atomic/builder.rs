@@ -0,0 +1,191 @@
+//! Fluent construction of histories.
+//!
+//! ```
+//! use dbcop_core::history::non_atomic::builder::HistoryBuilder;
+//!
+//! let histories = HistoryBuilder::new()
+//!     .session()
+//!     .txn()
+//!     .write("x", 1)
+//!     .commit()
+//!     .session()
+//!     .txn()
+//!     .read("x", 1)
+//!     .write("y", 1)
+//!     .commit()
+//!     .build();
+//! assert_eq!(histories.len(), 2);
+//! ```
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::history::non_atomic::types::{Event, Session, Transaction};
+
+#[derive(Debug, Clone)]
+pub struct HistoryBuilder<Variable, Version> {
+    sessions: Vec<Session<Variable, Version>>,
+}
+
+impl<Variable, Version> Default for HistoryBuilder<Variable, Version> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Variable, Version> HistoryBuilder<Variable, Version> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            sessions: Vec::new(),
+        }
+    }
+
+    /// Starts a new session.
+    #[must_use]
+    pub fn session(mut self) -> SessionBuilder<Variable, Version> {
+        self.sessions.push(Vec::new());
+        SessionBuilder { history: self }
+    }
+
+    #[must_use]
+    pub fn build(self) -> Vec<Session<Variable, Version>> {
+        self.sessions
+    }
+}
+
+/// Adds transactions to the last session of a history.
+#[derive(Debug, Clone)]
+pub struct SessionBuilder<Variable, Version> {
+    history: HistoryBuilder<Variable, Version>,
+}
+
+impl<Variable, Version> SessionBuilder<Variable, Version> {
+    /// Starts a new transaction at the end of the session.
+    #[must_use]
+    pub const fn txn(self) -> TransactionBuilder<Variable, Version> {
+        TransactionBuilder {
+            session: self,
+            events: Vec::new(),
+            predecessors: None,
+            meta: BTreeMap::new(),
+        }
+    }
+
+    /// Ends the session and starts a new one.
+    #[must_use]
+    pub fn session(self) -> Self {
+        self.history.session()
+    }
+
+    #[must_use]
+    pub fn build(self) -> Vec<Session<Variable, Version>> {
+        self.history.build()
+    }
+}
+
+/// Adds events to a transaction, until it commits or aborts.
+#[derive(Debug, Clone)]
+pub struct TransactionBuilder<Variable, Version> {
+    session: SessionBuilder<Variable, Version>,
+    events: Vec<Event<Variable, Version>>,
+    predecessors: Option<Vec<u64>>,
+    meta: BTreeMap<String, String>,
+}
+
+impl<Variable, Version> TransactionBuilder<Variable, Version> {
+    #[must_use]
+    pub fn read(mut self, variable: Variable, version: Version) -> Self {
+        self.events.push(Event::read(variable, version));
+        self
+    }
+
+    /// Reads the initial version of `variable`.
+    #[must_use]
+    pub fn read_empty(mut self, variable: Variable) -> Self {
+        self.events.push(Event::read_empty(variable));
+        self
+    }
+
+    #[must_use]
+    pub fn write(mut self, variable: Variable, version: Version) -> Self {
+        self.events.push(Event::write(variable, version));
+        self
+    }
+
+    /// Orders the transaction after the given transactions of its session only. See
+    /// [`Transaction::with_predecessors`].
+    #[must_use]
+    pub fn after(mut self, predecessors: Vec<u64>) -> Self {
+        self.predecessors = Some(predecessors);
+        self
+    }
+
+    /// Annotates the transaction. See [`Transaction::with_meta`].
+    #[must_use]
+    pub fn meta(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.meta.insert(key.into(), value.into());
+        self
+    }
+
+    #[must_use]
+    pub fn commit(self) -> SessionBuilder<Variable, Version> {
+        self.end(true)
+    }
+
+    #[must_use]
+    pub fn abort(self) -> SessionBuilder<Variable, Version> {
+        self.end(false)
+    }
+
+    fn end(self, committed: bool) -> SessionBuilder<Variable, Version> {
+        let mut session = self.session;
+        if let Some(transactions) = session.history.sessions.last_mut() {
+            transactions.push(Transaction {
+                events: self.events,
+                committed,
+                unknown: false,
+                predecessors: self.predecessors,
+                meta: self.meta,
+            });
+        }
+        session
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn test_history_builder() {
+        let histories = HistoryBuilder::new()
+            .session()
+            .txn()
+            .write("x", 1)
+            .commit()
+            .txn()
+            .read_empty("y")
+            .abort()
+            .session()
+            .txn()
+            .read("x", 1)
+            .after(vec![])
+            .meta("node", "n1")
+            .commit()
+            .build();
+
+        assert_eq!(histories.len(), 2);
+        assert_eq!(histories[0].len(), 2);
+        assert_eq!(histories[0][0].events, vec![Event::write("x", 1)]);
+        assert!(histories[0][0].committed);
+        assert!(!histories[0][1].committed);
+        assert_eq!(histories[1][0].events, vec![Event::read("x", 1)]);
+        assert_eq!(histories[1][0].predecessors, Some(vec![]));
+        assert_eq!(histories[1][0].meta["node"], "n1");
+        assert!(histories[0][0].meta.is_empty());
+    }
+}
@@ -1,8 +1,16 @@
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter, Result};
+
 use super::types::Event;
+use crate::history::atomic::types::TransactionId;
 use crate::history::non_atomic::types::EventId;
 
 /// Error converting a raw history to an atomic transactional history
-#[derive(Debug)]
+///
+/// With the `serde` feature, it serializes as an object tagged by `kind`, the snake case name of the variant.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+#[derive(Debug, Clone)]
 pub enum Error<Variable, Version> {
     /// Reads an absent value
     IncompleteHistory {
@@ -53,4 +61,116 @@ pub enum Error<Variable, Version> {
         read_event_id: EventId,
         write_event_id: EventId,
     },
+    /// Reads writes of an aborted transaction. The transaction is partially visible if some of
+    /// its writes are not read.
+    AbortedWritesRead {
+        transaction: TransactionId,
+        /// The reads of its writes, as pairs of the read and the write it reads
+        observed: Vec<(EventId, EventId)>,
+        /// Its writes that are not read
+        unobserved: Vec<EventId>,
+    },
+    /// Follows a transaction that is not an earlier transaction of its session
+    InvalidPredecessor { id: TransactionId, predecessor: u64 },
+}
+
+impl<Variable, Version> Display for Error<Variable, Version>
+where
+    Variable: Display,
+    Version: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::IncompleteHistory { event, id } => {
+                write!(f, "{event} at {id} reads a version that is never written")
+            }
+            Self::SameVersionWrite { event, ids } => {
+                write!(f, "{event} is written at both {} and {}", ids[0], ids[1])
+            }
+            Self::InconsistentLocalRead {
+                read_event_id,
+                write_event_id,
+                read_event,
+            } => write!(
+                f,
+                "{read_event} at {read_event_id} does not read the last local write, at \
+                 {write_event_id}"
+            ),
+            Self::UnsuccessfulEventRead {
+                read_event,
+                read_event_id,
+                write_event,
+                write_event_id,
+            } => write!(
+                f,
+                "{read_event} at {read_event_id} reads the failed {write_event} at \
+                 {write_event_id}"
+            ),
+            Self::UnsuccessfulTransactionRead {
+                read_event,
+                read_event_id,
+                write_event,
+                write_event_id,
+            } => write!(
+                f,
+                "{read_event} at {read_event_id} reads {write_event} at {write_event_id} of an \
+                 aborted transaction"
+            ),
+            Self::NonRepeatableRead {
+                read_event,
+                read_event_id,
+                write_event_ids,
+            } => write!(
+                f,
+                "{read_event} at {read_event_id} reads from both {} and {}",
+                write_event_ids[0].transaction_id(),
+                write_event_ids[1].transaction_id()
+            ),
+            Self::OverwrittenRead {
+                read_event,
+                read_event_id,
+                overwritten_write_event_id,
+                committed_write_event,
+                committed_write_event_id,
+            } => write!(
+                f,
+                "{read_event} at {read_event_id} reads the write at {overwritten_write_event_id}, \
+                 overwritten by {committed_write_event} at {committed_write_event_id}"
+            ),
+            Self::UncommittedWrite {
+                read_event,
+                read_event_id,
+                write_event_id,
+            } => write!(
+                f,
+                "{read_event} at {read_event_id} reads the uncommitted write at {write_event_id}"
+            ),
+            Self::AbortedWritesRead {
+                transaction,
+                observed,
+                unobserved,
+            } => {
+                write!(
+                    f,
+                    "writes of the aborted transaction {transaction} are read:"
+                )?;
+                for (read_event_id, write_event_id) in observed {
+                    write!(f, " {read_event_id} reads {write_event_id},")?;
+                }
+                if unobserved.is_empty() {
+                    write!(f, " all of them")
+                } else {
+                    write!(f, " but not")?;
+                    for write_event_id in unobserved {
+                        write!(f, " {write_event_id}")?;
+                    }
+                    Ok(())
+                }
+            }
+            Self::InvalidPredecessor { id, predecessor } => write!(
+                f,
+                "{id} follows t{predecessor}, which is not an earlier transaction of its session"
+            ),
+        }
+    }
 }
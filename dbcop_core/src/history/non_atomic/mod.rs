@@ -1,12 +1,14 @@
+pub mod builder;
 pub mod error;
 pub mod types;
 
+use ::alloc::vec::Vec;
 use ::core::hash::Hash;
-use ::hashbrown::HashMap;
+use ::hashbrown::{HashMap, HashSet};
 
 use super::atomic::types::TransactionId;
 use crate::history::non_atomic::error::Error;
-use crate::history::non_atomic::types::{Event, EventId, Session};
+use crate::history::non_atomic::types::{Event, EventId, Session, Transaction};
 
 // Raw history
 // sanity checks --
@@ -84,7 +86,65 @@ where
     Ok(write_map)
 }
 
-/// Get committed writes
+/// Returns the transactions of unknown commit status that another transaction reads from.
+///
+/// A read of a write needs its transaction committed, while a write nobody reads only adds
+/// constraints, so the checkers take these transactions as committed and the other ones of unknown
+/// status as aborted.
+#[must_use]
+pub fn observed_unknown<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+) -> HashSet<TransactionId>
+where
+    Variable: Eq + Hash,
+    Version: Eq + Hash,
+{
+    let mut unknown_writes: HashMap<(&Variable, &Version), TransactionId> = HashMap::new();
+    for (session_id, session) in (1..).zip(histories.iter()) {
+        for (session_height, transaction) in (0..).zip(session.iter()) {
+            if transaction.unknown {
+                for event in &transaction.events {
+                    if let Event::Write { variable, version } = event {
+                        let id = TransactionId {
+                            session_id,
+                            session_height,
+                        };
+                        unknown_writes.insert((variable, version), id);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut observed = HashSet::new();
+    if unknown_writes.is_empty() {
+        return observed;
+    }
+    for (session_id, session) in (1..).zip(histories.iter()) {
+        for (session_height, transaction) in (0..).zip(session.iter()) {
+            for event in &transaction.events {
+                if let Event::Read {
+                    variable,
+                    version: Some(version),
+                } = event
+                {
+                    let reader = TransactionId {
+                        session_id,
+                        session_height,
+                    };
+                    if let Some(writer) = unknown_writes.get(&(variable, version)) {
+                        if *writer != reader {
+                            observed.insert(*writer);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    observed
+}
+
+/// Get committed writes, including those of the transactions of [`observed_unknown`].
 #[must_use]
 pub fn get_committed_writes<Variable, Version>(
     histories: &[Session<Variable, Version>],
@@ -94,12 +154,17 @@ where
     Version: Eq + Hash + Clone,
 {
     let mut write_map = HashMap::new();
+    let observed = observed_unknown(histories);
 
     // 0 session_id is reserved for variable initization
     for (session_id, session) in (1..).zip(histories.iter()) {
         // (0..).zip() is used for u64 index
         for (session_height, transaction) in (0..).zip(session.iter()) {
-            if transaction.committed {
+            let id = TransactionId {
+                session_id,
+                session_height,
+            };
+            if transaction.committed || observed.contains(&id) {
                 for (transaction_height, event) in (0..).zip(transaction.events.iter()) {
                     if let Event::Write { variable, version } = event {
                         let current_event_id = EventId {
@@ -136,13 +201,13 @@ where
 
     for (i_node, session) in (1..).zip(histories.iter()) {
         for (i_transaction, transaction) in (0..).zip(session.iter()) {
+            let mut local_writes = HashMap::new();
             for (i_event, event) in (0..).zip(transaction.events.iter()) {
                 let current_event_id = EventId {
                     session_id: i_node,
                     session_height: i_transaction,
                     transaction_height: i_event,
                 };
-                let mut local_writes = HashMap::new();
                 match event {
                     Event::Write { variable, version } => {
                         local_writes.insert(variable.clone(), version.clone());
@@ -208,6 +273,10 @@ where
                                 event: event.clone(),
                                 id: current_event_id,
                             })?;
+                    if write_event_id.transaction_id() == current_event_id.transaction_id() {
+                        // local reads are checked by `consistent_local_reads`
+                        continue;
+                    }
                     if let Some(&(ref committed_version, committed_event_id)) =
                         committed_writes.get(&(write_event_id.transaction_id(), variable.clone()))
                     {
@@ -223,7 +292,8 @@ where
                                 committed_write_event_id: committed_event_id,
                             });
                         }
-                    } else {
+                    } else if write_event_id.transaction_id() != TransactionId::root() {
+                        // initial versions are committed by the root transaction
                         return Err(Error::UncommittedWrite {
                             read_event: event.clone(),
                             read_event_id: current_event_id,
@@ -238,6 +308,66 @@ where
     Ok(())
 }
 
+/// Returns the immediate session-order predecessors of a transaction.
+///
+/// These are its explicit predecessors or, without them, the previous transaction of its session.
+/// The first transactions of the sessions, and transactions without predecessors, follow the root
+/// transaction.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidPredecessor`] if a predecessor is not an earlier transaction of the
+/// session.
+pub fn session_predecessors<Variable, Version>(
+    id: TransactionId,
+    transaction: &Transaction<Variable, Version>,
+) -> Result<Vec<TransactionId>, Error<Variable, Version>> {
+    let heights: Vec<u64> = transaction.predecessors.as_ref().map_or_else(
+        || id.session_height.checked_sub(1).into_iter().collect(),
+        Clone::clone,
+    );
+    if heights.is_empty() {
+        return Ok(::alloc::vec![TransactionId::root()]);
+    }
+    heights
+        .into_iter()
+        .map(|predecessor| {
+            if predecessor < id.session_height {
+                Ok(TransactionId {
+                    session_id: id.session_id,
+                    session_height: predecessor,
+                })
+            } else {
+                Err(Error::InvalidPredecessor { id, predecessor })
+            }
+        })
+        .collect()
+}
+
+/// Returns the session order between immediate predecessors, as `(predecessor, successor)` pairs.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidPredecessor`] if a predecessor is not an earlier transaction of its
+/// session.
+pub fn session_order_edges<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+) -> Result<Vec<(TransactionId, TransactionId)>, Error<Variable, Version>> {
+    let mut edges = Vec::new();
+    for (session_id, session) in (1..).zip(histories.iter()) {
+        for (session_height, transaction) in (0..).zip(session.iter()) {
+            let id = TransactionId {
+                session_id,
+                session_height,
+            };
+            for predecessor in session_predecessors(id, transaction)? {
+                edges.push((predecessor, id));
+            }
+        }
+    }
+    Ok(edges)
+}
+
 /// Reads are from a write and no writes have the same version
 /// # Errors
 ///
@@ -253,9 +383,206 @@ where
     consistent_local_reads(histories)?;
     // checks external reads are from committed writes
     committed_external_reads(histories)?;
+    // checks the session order is between earlier transactions
+    session_order_edges(histories)?;
     Ok(())
 }
 
+/// Runs the checks of [`is_valid_history`], and returns every violation instead of the first one.
+///
+/// Of two writes of the same version, the later one is reported, and reads of the version are
+/// checked against the earlier one.
+#[must_use]
+pub fn validate_history<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+) -> Vec<Error<Variable, Version>>
+where
+    Variable: Eq + Hash + Clone,
+    Version: Eq + Hash + Clone,
+{
+    let mut errors = Vec::new();
+
+    let mut all_writes: HashMap<(&Variable, &Version), EventId> = HashMap::new();
+    for (session_id, session) in (1..).zip(histories.iter()) {
+        for (session_height, transaction) in (0..).zip(session.iter()) {
+            for (transaction_height, event) in (0..).zip(transaction.events.iter()) {
+                if let Event::Write { variable, version } = event {
+                    let id = EventId {
+                        session_id,
+                        session_height,
+                        transaction_height,
+                    };
+                    if let Some(other_id) = all_writes.get(&(variable, version)) {
+                        errors.push(Error::SameVersionWrite {
+                            event: event.clone(),
+                            ids: [id, *other_id],
+                        });
+                    } else {
+                        all_writes.insert((variable, version), id);
+                    }
+                }
+            }
+        }
+    }
+    let committed_writes = get_committed_writes(histories);
+
+    for (session_id, session) in (1..).zip(histories.iter()) {
+        for (session_height, transaction) in (0..).zip(session.iter()) {
+            let transaction_id = TransactionId {
+                session_id,
+                session_height,
+            };
+            if let Err(error) = session_predecessors(transaction_id, transaction) {
+                errors.push(error);
+            }
+
+            let mut local_writes = HashMap::new();
+            for (transaction_height, event) in (0..).zip(transaction.events.iter()) {
+                let (variable, version) = match event {
+                    Event::Write { variable, version } => {
+                        local_writes.insert(variable, version);
+                        continue;
+                    }
+                    Event::Read {
+                        variable,
+                        version: Some(version),
+                    } => (variable, version),
+                    // initial versions are written by the root transaction
+                    Event::Read { version: None, .. } => continue,
+                };
+                let read_event_id = EventId {
+                    session_id,
+                    session_height,
+                    transaction_height,
+                };
+                let Some(&write_event_id) = all_writes.get(&(variable, version)) else {
+                    errors.push(Error::IncompleteHistory {
+                        event: event.clone(),
+                        id: read_event_id,
+                    });
+                    continue;
+                };
+
+                if write_event_id.transaction_id() == transaction_id {
+                    if local_writes.get(variable) != Some(&version) {
+                        errors.push(Error::InconsistentLocalRead {
+                            read_event_id,
+                            write_event_id,
+                            read_event: event.clone(),
+                        });
+                    }
+                } else if let Some((committed_version, committed_event_id)) =
+                    committed_writes.get(&(write_event_id.transaction_id(), variable.clone()))
+                {
+                    if write_event_id != *committed_event_id {
+                        errors.push(Error::OverwrittenRead {
+                            read_event: event.clone(),
+                            read_event_id,
+                            overwritten_write_event_id: write_event_id,
+                            committed_write_event: Event::write(
+                                variable.clone(),
+                                committed_version.clone(),
+                            ),
+                            committed_write_event_id: *committed_event_id,
+                        });
+                    }
+                } else {
+                    errors.push(Error::UncommittedWrite {
+                        read_event: event.clone(),
+                        read_event_id,
+                        write_event_id,
+                    });
+                }
+            }
+        }
+    }
+
+    errors
+}
+
+/// Returns an [`Error::AbortedWritesRead`] for each aborted transaction that has writes read by
+/// other transactions, with the exact reads and the writes nobody reads.
+///
+/// [`validate_history`] reports each such read on its own, as an [`Error::UncommittedWrite`].
+/// Grouping them by transaction tells a partially visible abort, which exposes a non-atomic
+/// rollback, from a fully visible one. Transactions of unknown commit status are not aborted.
+#[must_use]
+pub fn aborted_writes_read<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+) -> Vec<Error<Variable, Version>>
+where
+    Variable: Eq + Hash,
+    Version: Eq + Hash,
+{
+    // the reads of each version, by event
+    let mut readers: HashMap<(&Variable, &Version), Vec<EventId>> = HashMap::new();
+    for (session_id, session) in (1..).zip(histories.iter()) {
+        for (session_height, transaction) in (0..).zip(session.iter()) {
+            for (transaction_height, event) in (0..).zip(transaction.events.iter()) {
+                if let Event::Read {
+                    variable,
+                    version: Some(version),
+                } = event
+                {
+                    readers
+                        .entry((variable, version))
+                        .or_default()
+                        .push(EventId {
+                            session_id,
+                            session_height,
+                            transaction_height,
+                        });
+                }
+            }
+        }
+    }
+
+    let mut errors = Vec::new();
+    for (session_id, session) in (1..).zip(histories.iter()) {
+        for (session_height, transaction) in (0..).zip(session.iter()) {
+            if transaction.committed || transaction.unknown {
+                continue;
+            }
+            let id = TransactionId {
+                session_id,
+                session_height,
+            };
+            let mut observed = Vec::new();
+            let mut unobserved = Vec::new();
+            for (transaction_height, event) in (0..).zip(transaction.events.iter()) {
+                let Event::Write { variable, version } = event else {
+                    continue;
+                };
+                let write_event_id = EventId {
+                    session_id,
+                    session_height,
+                    transaction_height,
+                };
+                let external_reads: Vec<(EventId, EventId)> = readers
+                    .get(&(variable, version))
+                    .into_iter()
+                    .flatten()
+                    .filter(|read_event_id| read_event_id.transaction_id() != id)
+                    .map(|read_event_id| (*read_event_id, write_event_id))
+                    .collect();
+                if external_reads.is_empty() {
+                    unobserved.push(write_event_id);
+                } else {
+                    observed.extend(external_reads);
+                }
+            }
+            if !observed.is_empty() {
+                errors.push(Error::AbortedWritesRead {
+                    transaction: id,
+                    observed,
+                    unobserved,
+                });
+            }
+        }
+    }
+    errors
+}
+
 #[cfg(test)]
 mod tests {
     use tests::types::Transaction;
@@ -291,6 +618,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_initial_and_local_reads() {
+        let histories = vec![
+            vec![Transaction::committed(vec![
+                Event::read_empty("a"),
+                Event::write("a", 0),
+                Event::read("a", 0),
+                Event::write("a", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read_empty("b"),
+                Event::read("a", 1),
+            ])],
+        ];
+
+        let result = is_valid_history(&histories);
+
+        assert!(result.is_ok(), "valid history check failed: {result:?}");
+    }
+
     #[test]
     fn test_uncommitted_reads() {
         let histories = vec![
@@ -324,6 +671,64 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_aborted_writes_read() {
+        let histories = vec![
+            vec![Transaction::uncommitted(vec![
+                Event::write("a", 0),
+                Event::write("b", 0),
+            ])],
+            vec![Transaction::committed(vec![Event::read("a", 0)])],
+            vec![Transaction::uncommitted(vec![Event::write("c", 0)])],
+        ];
+
+        let errors = aborted_writes_read(&histories);
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            &errors[0],
+            Error::AbortedWritesRead {
+                transaction: TransactionId {
+                    session_id: 1,
+                    session_height: 0,
+                },
+                observed,
+                unobserved,
+            } if observed.len() == 1 && observed[0].0.session_id == 2 && unobserved.len() == 1
+        ));
+        assert_eq!(
+            errors[0].to_string(),
+            "writes of the aborted transaction s1.t0 are read: s2.t0.e0 reads s1.t0.e0, but not \
+             s1.t0.e1"
+        );
+    }
+
+    #[test]
+    fn test_unknown_commit() {
+        // a truncated capture: the last transactions of the sessions have no commit status
+        let histories = vec![
+            vec![
+                Transaction::committed(vec![Event::write("a", 0)]),
+                Transaction::unknown(vec![Event::write("a", 1)]),
+            ],
+            vec![
+                Transaction::committed(vec![Event::read("a", 1)]),
+                Transaction::unknown(vec![Event::write("a", 2)]),
+            ],
+        ];
+
+        let observed = observed_unknown(&histories);
+        assert_eq!(observed.len(), 1);
+        assert!(observed.contains(&TransactionId {
+            session_id: 1,
+            session_height: 1,
+        }));
+        assert!(is_valid_history(&histories).is_ok());
+        assert!(crate::solver::causal::check_causal_read(&histories).is_ok());
+        assert!(crate::solver::phenomena::g1_anomalies(&histories)
+            .unwrap()
+            .is_empty());
+    }
+
     #[test]
     fn test_overwritten_reads() {
         let histories = vec![
@@ -402,4 +807,86 @@ mod tests {
             "consistent local reads check failed: {result:?}"
         );
     }
+
+    #[test]
+    fn test_invalid_predecessor() {
+        let histories = vec![vec![
+            Transaction::committed(vec![Event::write("a", 1)]).with_predecessors(vec![1]),
+            Transaction::committed(vec![Event::read("a", 1)]),
+        ]];
+
+        assert!(matches!(
+            is_valid_history(&histories),
+            Err(Error::InvalidPredecessor {
+                id: TransactionId {
+                    session_id: 1,
+                    session_height: 0,
+                },
+                predecessor: 1,
+            })
+        ));
+    }
+
+    #[test]
+    fn test_validate_history() {
+        let histories = vec![
+            vec![
+                Transaction::uncommitted(vec![Event::write("a", 0)]),
+                Transaction::committed(vec![
+                    Event::write("b", 0),
+                    Event::read("b", 1),
+                    Event::write("b", 1),
+                ])
+                .with_predecessors(vec![2]),
+            ],
+            vec![Transaction::committed(vec![
+                Event::read("a", 0),
+                Event::read("c", 1),
+                Event::write("b", 0),
+            ])],
+        ];
+
+        let errors = validate_history(&histories);
+        assert_eq!(errors.len(), 5, "{errors:?}");
+        assert!(matches!(
+            errors[0],
+            Error::SameVersionWrite {
+                ids: [EventId { session_id: 2, .. }, EventId { session_id: 1, .. }],
+                ..
+            }
+        ));
+        assert!(matches!(
+            errors[1],
+            Error::InvalidPredecessor { predecessor: 2, .. }
+        ));
+        assert!(matches!(errors[2], Error::InconsistentLocalRead { .. }));
+        assert!(matches!(errors[3], Error::UncommittedWrite { .. }));
+        assert!(matches!(errors[4], Error::IncompleteHistory { .. }));
+
+        for histories in [
+            vec![vec![Transaction::committed(vec![
+                Event::write("a", 0),
+                Event::read("a", 1),
+                Event::write("a", 1),
+            ])]],
+            vec![
+                vec![Transaction::committed(vec![
+                    Event::write("a", 0),
+                    Event::write("a", 1),
+                ])],
+                vec![Transaction::committed(vec![Event::read("a", 0)])],
+            ],
+        ] {
+            let errors = validate_history(&histories);
+            assert_eq!(errors.len(), 1);
+            assert_eq!(
+                format!("{:?}", is_valid_history(&histories).unwrap_err()),
+                format!("{:?}", errors[0])
+            );
+        }
+        assert!(validate_history(&[vec![Transaction::committed(vec![
+            Event::<_, u64>::read_empty("a")
+        ])]])
+        .is_empty());
+    }
 }
@@ -1,5 +1,7 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
 use alloc::vec::Vec;
-use core::fmt::{Debug, Formatter, Result};
+use core::fmt::{Debug, Display, Formatter, Result};
 
 use crate::history::atomic::types::TransactionId;
 
@@ -61,6 +63,27 @@ where
     }
 }
 
+/// Renders as `r(x)=1`, `r(x)=?` for the initial version, or `w(x)=1`.
+impl<Variable, Version> Display for Event<Variable, Version>
+where
+    Variable: Display,
+    Version: Display,
+{
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self {
+            Self::Read {
+                variable,
+                version: Some(version),
+            } => write!(f, "r({variable})={version}"),
+            Self::Read {
+                variable,
+                version: None,
+            } => write!(f, "r({variable})=?"),
+            Self::Write { variable, version } => write!(f, "w({variable})={version}"),
+        }
+    }
+}
+
 impl<Variable, Version> Event<Variable, Version> {
     pub const fn read_empty(variable: Variable) -> Self {
         Self::Read {
@@ -88,7 +111,9 @@ where
 {
     fn fmt(&self, f: &mut Formatter) -> Result {
         write!(f, "{:?}", self.events)?;
-        if !self.committed {
+        if self.unknown {
+            write!(f, "?")?;
+        } else if !self.committed {
             write!(f, "!")?;
         }
         Ok(())
@@ -96,9 +121,25 @@ where
 }
 
 #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Clone, PartialEq, Eq)]
 pub struct Transaction<Variable, Version> {
     pub events: Vec<Event<Variable, Version>>,
     pub committed: bool,
+    /// The commit status was not observed, such as for the last transaction of a session in a
+    /// truncated capture. Such a transaction is not `committed`, but the checkers take it as
+    /// committed if another transaction reads one of its writes, and as aborted otherwise. See
+    /// [`observed_unknown`](crate::history::non_atomic::observed_unknown).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub unknown: bool,
+    /// Heights of the transactions of the same session that precede this one, for sessions that
+    /// are only partially ordered, such as pipelined clients. `None` means the previous
+    /// transaction of the session, and an empty list none.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub predecessors: Option<Vec<u64>>,
+    /// Annotations of the transaction, such as the client, the node that served it or its
+    /// wall-clock time. The checkers ignore them.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub meta: BTreeMap<String, String>,
 }
 
 impl<Variable, Version> Transaction<Variable, Version> {
@@ -107,6 +148,9 @@ impl<Variable, Version> Transaction<Variable, Version> {
         Self {
             events,
             committed: true,
+            unknown: false,
+            predecessors: None,
+            meta: BTreeMap::new(),
         }
     }
 
@@ -115,12 +159,42 @@ impl<Variable, Version> Transaction<Variable, Version> {
         Self {
             events,
             committed: false,
+            unknown: false,
+            predecessors: None,
+            meta: BTreeMap::new(),
+        }
+    }
+
+    /// A transaction whose commit status is unknown.
+    #[must_use]
+    pub const fn unknown(events: Vec<Event<Variable, Version>>) -> Self {
+        Self {
+            events,
+            committed: false,
+            unknown: true,
+            predecessors: None,
+            meta: BTreeMap::new(),
         }
     }
+
+    /// Orders the transaction after the given transactions of its session only.
+    #[must_use]
+    pub fn with_predecessors(mut self, predecessors: Vec<u64>) -> Self {
+        self.predecessors = Some(predecessors);
+        self
+    }
+
+    /// Annotates the transaction with `key`, replacing its previous value.
+    #[must_use]
+    pub fn with_meta(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.meta.insert(key.into(), value.into());
+        self
+    }
 }
 
 pub type Session<Variable, Version> = Vec<Transaction<Variable, Version>>;
 
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct EventId {
     pub session_id: u64,
@@ -138,6 +212,13 @@ impl EventId {
     }
 }
 
+/// Renders as `s<session>.t<height>.e<height>`.
+impl Display for EventId {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(f, "{}.e{}", self.transaction_id(), self.transaction_height)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -191,14 +272,46 @@ mod tests {
         assert_eq!(format!("{event:?}"), "1<=2");
     }
 
+    #[test]
+    fn test_event_display() {
+        assert_eq!(Event::read("x", 1).to_string(), "r(x)=1");
+        assert_eq!(Event::<_, u64>::read_empty("x").to_string(), "r(x)=?");
+        assert_eq!(Event::write("x", 2).to_string(), "w(x)=2");
+        let event_id = EventId {
+            session_id: 2,
+            session_height: 3,
+            transaction_height: 1,
+        };
+        assert_eq!(event_id.to_string(), "s2.t3.e1");
+    }
+
     #[test]
     fn test_transaction_debug() {
         let mut transaction = Transaction {
             events: vec![Event::read_empty(1), Event::write(1, 2)],
             committed: true,
+            unknown: false,
+            predecessors: None,
+            meta: BTreeMap::new(),
         };
         assert_eq!(format!("{transaction:?}"), "[1=>?, 1<=2]");
         transaction.committed = false;
         assert_eq!(format!("{transaction:?}"), "[1=>?, 1<=2]!");
+        transaction.unknown = true;
+        assert_eq!(format!("{transaction:?}"), "[1=>?, 1<=2]?");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize_meta() {
+        // histories written before the annotations read back without them
+        let json = r#"{"events":[],"committed":true}"#;
+        let transaction: Transaction<String, u64> = serde_json::from_str(json).unwrap();
+        assert!(transaction.meta.is_empty());
+
+        let transaction = transaction.with_meta("node", "n1");
+        let json = serde_json::to_string(&transaction).unwrap();
+        let transaction: Transaction<String, u64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(transaction.meta["node"], "n1");
     }
 }
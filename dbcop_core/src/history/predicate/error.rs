@@ -0,0 +1,8 @@
+/// Error reducing a history with predicate reads to a register history
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+#[derive(Debug)]
+pub enum Error<Variable> {
+    /// A range read observes a variable that does not match its predicate
+    UnmatchedObservation { variable: Variable },
+}
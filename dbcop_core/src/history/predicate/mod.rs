@@ -0,0 +1,164 @@
+//! Histories with predicate reads, such as the range scans of SQL queries.
+//!
+//! A range read returns the versions of the variables matching its predicate that it observes.
+//! The variables it does not observe are read too: it sees them absent, that is, at their initial
+//! version. A write of such a variable that the range read misses is a phantom.
+//!
+//! [`to_register_history`] reduces a history with predicate reads to a register history:
+//! - an observed variable is a read of its observed version,
+//! - a variable written anywhere in the history, matching the predicate but not observed, is a
+//!   read of its initial version.
+//!
+//! The reads of the initial versions make the phantoms explicit: under serializability, the range
+//! read precedes every write of these variables, as for any read of an initial version. So the
+//! register history is checked by the existing solvers, at every level.
+
+pub mod error;
+pub mod types;
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec::Vec;
+
+use crate::history::non_atomic::types::{Event, Session, Transaction};
+use crate::history::predicate::error::Error;
+use crate::history::predicate::types::{PredicateEvent, PredicateSession};
+
+/// Reduces a history with predicate reads to a register history. See the module documentation.
+///
+/// # Errors
+///
+/// Returns [`Error::UnmatchedObservation`] if a range read observes a variable outside its
+/// predicate.
+pub fn to_register_history<Variable, Version>(
+    histories: &[PredicateSession<Variable, Version>],
+) -> Result<Vec<Session<Variable, Version>>, Error<Variable>>
+where
+    Variable: Ord + Clone,
+    Version: Clone,
+{
+    let written: BTreeSet<&Variable> = histories
+        .iter()
+        .flatten()
+        .flat_map(|transaction| &transaction.events)
+        .filter_map(|event| match event {
+            PredicateEvent::Point(Event::Write { variable, .. }) => Some(variable),
+            _ => None,
+        })
+        .collect();
+
+    histories
+        .iter()
+        .map(|session| {
+            session
+                .iter()
+                .map(|transaction| {
+                    let mut events = Vec::new();
+                    for event in &transaction.events {
+                        match event {
+                            PredicateEvent::Point(event) => events.push(event.clone()),
+                            PredicateEvent::RangeRead {
+                                predicate,
+                                observed,
+                            } => {
+                                if let Some((variable, _)) = observed
+                                    .iter()
+                                    .find(|(variable, _)| !predicate.matches(variable))
+                                {
+                                    return Err(Error::UnmatchedObservation {
+                                        variable: variable.clone(),
+                                    });
+                                }
+                                events.extend(observed.iter().map(|(variable, version)| {
+                                    Event::read(variable.clone(), version.clone())
+                                }));
+                                events.extend(
+                                    written
+                                        .iter()
+                                        .filter(|variable| {
+                                            predicate.matches(variable)
+                                                && observed
+                                                    .iter()
+                                                    .all(|(observed, _)| observed != **variable)
+                                        })
+                                        .map(|variable| Event::read_empty((*variable).clone())),
+                                );
+                            }
+                        }
+                    }
+                    Ok(Transaction {
+                        events,
+                        committed: transaction.committed,
+                        unknown: false,
+                        predecessors: None,
+                        meta: BTreeMap::new(),
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::history::predicate::types::PredicateTransaction;
+    use crate::solver::causal::check_causal_read;
+    use crate::solver::constrained_linearization::ConstrainedLinearizationSolver;
+    use crate::solver::serializable::SerializabilitySolver;
+
+    #[test]
+    fn test_phantom() {
+        // each transaction misses the insert of the other one in its range
+        let histories = vec![
+            vec![PredicateTransaction::committed(vec![
+                PredicateEvent::range_read("a", "c", vec![]),
+                Event::write("y", 1).into(),
+            ])],
+            vec![PredicateTransaction::committed(vec![
+                PredicateEvent::range_read("x", "z", vec![]),
+                Event::write("b", 1).into(),
+            ])],
+        ];
+
+        let register = to_register_history(&histories).unwrap();
+        assert_eq!(
+            register[0][0].events,
+            vec![Event::read_empty("b"), Event::write("y", 1)]
+        );
+
+        let po = check_causal_read(&register).unwrap();
+        assert!(SerializabilitySolver::from(po)
+            .get_linearization()
+            .is_none());
+
+        // the second range read sees the insert of the first transaction
+        let histories = vec![
+            vec![PredicateTransaction::committed(vec![
+                PredicateEvent::range_read("a", "c", vec![]),
+                Event::write("y", 1).into(),
+            ])],
+            vec![PredicateTransaction::committed(vec![
+                PredicateEvent::range_read("x", "z", vec![("y", 1)]),
+                Event::write("b", 1).into(),
+            ])],
+        ];
+        let po = check_causal_read(&to_register_history(&histories).unwrap()).unwrap();
+        assert!(SerializabilitySolver::from(po)
+            .get_linearization()
+            .is_some());
+    }
+
+    #[test]
+    fn test_unmatched_observation() {
+        let histories = vec![vec![PredicateTransaction::committed(vec![
+            PredicateEvent::range_read("a", "c", vec![("d", 1)]),
+        ])]];
+
+        assert!(matches!(
+            to_register_history(&histories),
+            Err(Error::UnmatchedObservation { variable: "d" })
+        ));
+    }
+}
@@ -0,0 +1,79 @@
+use alloc::vec::Vec;
+
+use crate::history::non_atomic::types::Event;
+
+/// The variables from `start` to `end`, both included.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Predicate<Variable> {
+    pub start: Variable,
+    pub end: Variable,
+}
+
+impl<Variable> Predicate<Variable>
+where
+    Variable: Ord,
+{
+    #[must_use]
+    pub fn matches(&self, variable: &Variable) -> bool {
+        self.start <= *variable && *variable <= self.end
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PredicateEvent<Variable, Version> {
+    /// A read or a write of a single variable
+    Point(Event<Variable, Version>),
+    /// Reads every variable matching `predicate`, observing the versions of `observed` only
+    RangeRead {
+        predicate: Predicate<Variable>,
+        observed: Vec<(Variable, Version)>,
+    },
+}
+
+impl<Variable, Version> PredicateEvent<Variable, Version> {
+    pub const fn range_read(
+        start: Variable,
+        end: Variable,
+        observed: Vec<(Variable, Version)>,
+    ) -> Self {
+        Self::RangeRead {
+            predicate: Predicate { start, end },
+            observed,
+        }
+    }
+}
+
+impl<Variable, Version> From<Event<Variable, Version>> for PredicateEvent<Variable, Version> {
+    fn from(event: Event<Variable, Version>) -> Self {
+        Self::Point(event)
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Debug, Clone)]
+pub struct PredicateTransaction<Variable, Version> {
+    pub events: Vec<PredicateEvent<Variable, Version>>,
+    pub committed: bool,
+}
+
+impl<Variable, Version> PredicateTransaction<Variable, Version> {
+    #[must_use]
+    pub const fn committed(events: Vec<PredicateEvent<Variable, Version>>) -> Self {
+        Self {
+            events,
+            committed: true,
+        }
+    }
+
+    #[must_use]
+    pub const fn uncommitted(events: Vec<PredicateEvent<Variable, Version>>) -> Self {
+        Self {
+            events,
+            committed: false,
+        }
+    }
+}
+
+pub type PredicateSession<Variable, Version> = Vec<PredicateTransaction<Variable, Version>>;
@@ -0,0 +1,144 @@
+//! Projections of a history to a subset of its variables or sessions.
+//!
+//! Projections keep every transaction of the kept sessions, even when it becomes empty, so the
+//! transaction heights of the projected history match the original one. Session ids are 1-based,
+//! as in [`EventId`](crate::history::non_atomic::types::EventId).
+
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+use hashbrown::{HashMap, HashSet};
+
+use crate::history::non_atomic::types::{Event, Session, Transaction};
+
+/// Keeps only the events on `variables`.
+///
+/// A read still reads from the same write, so no write-read reference is lost.
+#[must_use]
+pub fn project_variables<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    variables: &HashSet<Variable>,
+) -> Vec<Session<Variable, Version>>
+where
+    Variable: Eq + Hash + Clone,
+    Version: Clone,
+{
+    histories
+        .iter()
+        .map(|session| {
+            session
+                .iter()
+                .map(|transaction| Transaction {
+                    events: transaction
+                        .events
+                        .iter()
+                        .filter(|event| variables.contains(&event.variable()))
+                        .cloned()
+                        .collect(),
+                    committed: transaction.committed,
+                    unknown: transaction.unknown,
+                    predecessors: transaction.predecessors.clone(),
+                    meta: transaction.meta.clone(),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Keeps only the sessions in `sessions`, renumbered in their original order.
+///
+/// The reads of versions written by a dropped session would be dangling, so they are dropped too.
+#[must_use]
+pub fn project_sessions<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    sessions: &HashSet<u64>,
+) -> Vec<Session<Variable, Version>>
+where
+    Variable: Eq + Hash + Clone,
+    Version: Eq + Hash + Clone,
+{
+    let writers: HashMap<(&Variable, &Version), u64> = (1..)
+        .zip(histories)
+        .flat_map(|(session_id, session)| {
+            session
+                .iter()
+                .flat_map(|transaction| &transaction.events)
+                .filter_map(move |event| match event {
+                    Event::Write { variable, version } => Some(((variable, version), session_id)),
+                    Event::Read { .. } => None,
+                })
+        })
+        .collect();
+
+    (1..)
+        .zip(histories)
+        .filter(|(session_id, _)| sessions.contains(session_id))
+        .map(|(_, session)| {
+            session
+                .iter()
+                .map(|transaction| Transaction {
+                    events: transaction
+                        .events
+                        .iter()
+                        .filter(|event| match event {
+                            Event::Read {
+                                variable,
+                                version: Some(version),
+                            } => writers
+                                .get(&(variable, version))
+                                .map_or(true, |writer| sessions.contains(writer)),
+                            _ => true,
+                        })
+                        .cloned()
+                        .collect(),
+                    committed: transaction.committed,
+                    unknown: transaction.unknown,
+                    predecessors: transaction.predecessors.clone(),
+                    meta: transaction.meta.clone(),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::solver::causal::check_causal_read;
+
+    #[test]
+    fn test_projection() {
+        let histories = vec![
+            vec![Transaction::committed(vec![
+                Event::write("x", 1),
+                Event::write("y", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read("x", 1),
+                Event::write("z", 1),
+            ])],
+            vec![
+                Transaction::committed(vec![Event::read("z", 1), Event::read_empty("y")]),
+                Transaction::committed(vec![Event::read("y", 1)]),
+            ],
+        ];
+
+        let projected = project_variables(&histories, &["y"].into());
+        assert_eq!(projected.len(), 3);
+        assert!(projected[1][0].events.is_empty());
+        assert_eq!(projected[2][0].events, vec![Event::read_empty("y")]);
+        assert!(check_causal_read(&projected).is_ok());
+
+        let projected = project_sessions(&histories, &[2, 3].into());
+        assert_eq!(projected.len(), 2);
+        assert_eq!(projected[0][0].events, vec![Event::write("z", 1)]);
+        assert_eq!(
+            projected[1][0].events,
+            vec![Event::read("z", 1), Event::read_empty("y")]
+        );
+        assert!(projected[1][1].events.is_empty());
+        assert!(check_causal_read(&projected).is_ok());
+    }
+}
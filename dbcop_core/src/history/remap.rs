@@ -0,0 +1,164 @@
+//! Merging histories, renumbering their sessions and compacting their variables.
+//!
+//! A read refers to the write it reads by variable and version, so every write-read reference stays
+//! valid as long as variables are renamed one-to-one. Session ids are 1-based, as in
+//! [`EventId`](crate::history::non_atomic::types::EventId).
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+use hashbrown::HashMap;
+
+use crate::history::non_atomic::types::{Event, Session, Transaction};
+
+/// Renames every variable of a history with `rename`, keeping everything else.
+fn map_variables<Variable, Renamed, Version>(
+    histories: &[Session<Variable, Version>],
+    mut rename: impl FnMut(&Variable) -> Renamed,
+) -> Vec<Session<Renamed, Version>>
+where
+    Version: Clone,
+{
+    histories
+        .iter()
+        .map(|session| {
+            session
+                .iter()
+                .map(|transaction| Transaction {
+                    events: transaction
+                        .events
+                        .iter()
+                        .map(|event| match event {
+                            Event::Read { variable, version } => Event::Read {
+                                variable: rename(variable),
+                                version: version.clone(),
+                            },
+                            Event::Write { variable, version } => {
+                                Event::write(rename(variable), version.clone())
+                            }
+                        })
+                        .collect(),
+                    committed: transaction.committed,
+                    unknown: transaction.unknown,
+                    predecessors: transaction.predecessors.clone(),
+                    meta: transaction.meta.clone(),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Puts the sessions of several histories side by side, in order.
+///
+/// Each variable is tagged with the index of its history, so the histories do not read from each
+/// other. The merged history satisfies a level if and only if every history does.
+#[must_use]
+pub fn merge<Variable, Version>(
+    histories: &[Vec<Session<Variable, Version>>],
+) -> Vec<Session<(usize, Variable), Version>>
+where
+    Variable: Clone,
+    Version: Clone,
+{
+    histories
+        .iter()
+        .enumerate()
+        .flat_map(|(i, history)| map_variables(history, |variable| (i, variable.clone())))
+        .collect()
+}
+
+/// Reorders the sessions: session `i + 1` of the result is session `sessions[i]` of `histories`.
+///
+/// Returns `None` if `sessions` is not a permutation of the session ids.
+#[must_use]
+pub fn renumber_sessions<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    sessions: &[u64],
+) -> Option<Vec<Session<Variable, Version>>>
+where
+    Variable: Clone,
+    Version: Clone,
+{
+    if sessions.len() != histories.len() {
+        return None;
+    }
+    let mut used = vec![false; histories.len()];
+    sessions
+        .iter()
+        .map(|session_id| {
+            let index = usize::try_from(session_id.checked_sub(1)?).ok()?;
+            if core::mem::replace(used.get_mut(index)?, true) {
+                return None;
+            }
+            Some(histories[index].clone())
+        })
+        .collect()
+}
+
+/// Renames the variables to `0..n`, in order of first occurrence.
+///
+/// Returns the renamed history and the original variables, indexed by their new names.
+#[must_use]
+pub fn compact_variables<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+) -> (Vec<Session<u64, Version>>, Vec<Variable>)
+where
+    Variable: Eq + Hash + Clone,
+    Version: Clone,
+{
+    let mut ids: HashMap<Variable, u64> = HashMap::new();
+    let mut variables = Vec::new();
+    let compacted = map_variables(histories, |variable| {
+        *ids.entry(variable.clone()).or_insert_with(|| {
+            variables.push(variable.clone());
+            variables.len() as u64 - 1
+        })
+    });
+    (compacted, variables)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check::check;
+    use crate::Consistency;
+
+    #[test]
+    fn test_remap() {
+        // lost update, which is prefix consistent but not snapshot isolated
+        let lost_update = vec![
+            vec![Transaction::committed(vec![
+                Event::read_empty("x"),
+                Event::write("x", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read_empty("x"),
+                Event::write("x", 2),
+            ])],
+        ];
+        let serial = vec![vec![
+            Transaction::committed(vec![Event::write("x", 1)]),
+            Transaction::committed(vec![Event::read("x", 1), Event::write("y", 1)]),
+        ]];
+
+        let merged = merge(&[serial.clone(), lost_update.clone()]);
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[1][0].events[0], Event::read_empty((1, "x")));
+        assert!(check(&merged, Consistency::Prefix).is_ok());
+        assert!(check(&merged, Consistency::SnapshotIsolation).is_err());
+        assert!(check(&merge(&[serial.clone(), serial]), Consistency::Serializable).is_ok());
+
+        let renumbered = renumber_sessions(&merged, &[2, 3, 1]).unwrap();
+        assert_eq!(renumbered[2][1].events, merged[0][1].events);
+        assert!(check(&renumbered, Consistency::SnapshotIsolation).is_err());
+        assert!(renumber_sessions(&merged, &[1, 1, 2]).is_none());
+        assert!(renumber_sessions(&merged, &[1, 2]).is_none());
+        assert!(renumber_sessions(&merged, &[0, 1, 2]).is_none());
+
+        let (compacted, variables) = compact_variables(&lost_update);
+        assert_eq!(variables, vec!["x"]);
+        assert_eq!(compacted[1][0].events[1], Event::write(0, 2));
+        assert!(check(&compacted, Consistency::SnapshotIsolation).is_err());
+    }
+}
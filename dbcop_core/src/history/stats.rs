@@ -0,0 +1,454 @@
+//! Size and shape statistics of a history.
+//!
+//! Two transactions conflict if they access a common variable and one of them writes it.
+//! The communication graph connects two sessions with conflicting transactions; its components
+//! can be checked independently. [`decompose`] also reports its biconnected components and the
+//! sessions that join them, to tell whether sharding the sessions would speed up a check.
+//! [`variable_stats`] tells which variables the conflicts are on.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter, Result};
+use core::hash::Hash;
+
+use hashbrown::{HashMap, HashSet};
+
+use crate::graph::biconnected_component::BiconnectedComponentWalker;
+use crate::graph::ugraph::UGraph;
+use crate::history::atomic::types::TransactionId;
+use crate::history::non_atomic::types::{Event, Session};
+
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HistoryStats {
+    pub n_session: usize,
+    pub n_transaction: usize,
+    pub n_event: usize,
+    pub n_variable: usize,
+    pub n_read: usize,
+    pub n_write: usize,
+    /// Fraction of the pairs of transactions that conflict
+    pub conflict_density: f64,
+    /// Number of connected components of the communication graph
+    pub n_component: usize,
+    /// Number of transactions of the longest session
+    pub longest_session: usize,
+}
+
+impl HistoryStats {
+    /// Fraction of the events that are reads
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn read_ratio(&self) -> f64 {
+        if self.n_event == 0 {
+            0.0
+        } else {
+            self.n_read as f64 / self.n_event as f64
+        }
+    }
+}
+
+impl<Variable, Version> From<&[Session<Variable, Version>]> for HistoryStats
+where
+    Variable: Eq + Hash + Clone,
+{
+    #[allow(clippy::cast_precision_loss)]
+    fn from(histories: &[Session<Variable, Version>]) -> Self {
+        let mut stats = Self {
+            n_session: histories.len(),
+            ..Self::default()
+        };
+
+        // accessing transactions and writing transactions of each variable
+        let mut accesses: HashMap<&Variable, (HashSet<TransactionId>, HashSet<TransactionId>)> =
+            HashMap::new();
+
+        for (session_id, session) in (1..).zip(histories) {
+            stats.n_transaction += session.len();
+            stats.longest_session = stats.longest_session.max(session.len());
+            for (session_height, transaction) in (0..).zip(session) {
+                let id = TransactionId {
+                    session_id,
+                    session_height,
+                };
+                for event in &transaction.events {
+                    stats.n_event += 1;
+                    let (variable, is_write) = match event {
+                        Event::Read { variable, .. } => (variable, false),
+                        Event::Write { variable, .. } => (variable, true),
+                    };
+                    if is_write {
+                        stats.n_write += 1;
+                    } else {
+                        stats.n_read += 1;
+                    }
+                    let (accessing, writing) = accesses.entry(variable).or_default();
+                    accessing.insert(id);
+                    if is_write {
+                        writing.insert(id);
+                    }
+                }
+            }
+        }
+        stats.n_variable = accesses.len();
+
+        let mut conflicts: HashSet<(TransactionId, TransactionId)> = HashSet::new();
+        for (accessing, writing) in accesses.values() {
+            for writer in writing {
+                for other in accessing.iter().filter(|other| *other != writer) {
+                    conflicts.insert((*writer.min(other), *writer.max(other)));
+                }
+            }
+        }
+
+        let n_pair = stats.n_transaction * stats.n_transaction.saturating_sub(1) / 2;
+        if n_pair > 0 {
+            stats.conflict_density = conflicts.len() as f64 / n_pair as f64;
+        }
+        stats.n_component = session_components(histories).len();
+
+        stats
+    }
+}
+
+/// Returns the connected components of an undirected graph, each sorted, in order of their
+/// smallest vertex.
+#[must_use]
+pub fn connected_components(graph: &UGraph<u64>) -> Vec<Vec<u64>> {
+    let mut seen: HashSet<u64> = HashSet::new();
+    let mut components = Vec::new();
+    for vertex in graph.adj_map.keys() {
+        if seen.insert(*vertex) {
+            let mut component = vec![*vertex];
+            let mut stack = vec![*vertex];
+            while let Some(current) = stack.pop() {
+                for neighbor in &graph.adj_map[&current] {
+                    if seen.insert(*neighbor) {
+                        component.push(*neighbor);
+                        stack.push(*neighbor);
+                    }
+                }
+            }
+            component.sort_unstable();
+            components.push(component);
+        }
+    }
+    components.sort_unstable();
+    components
+}
+
+/// Returns the biconnected components of an undirected graph with at least two vertices, each
+/// sorted, in order, and the articulation points, sorted.
+#[must_use]
+pub fn biconnected_components(graph: &UGraph<u64>) -> (Vec<Vec<u64>>, Vec<u64>) {
+    let (articulation_points, components, non_group) =
+        BiconnectedComponentWalker::get_vertex_components(graph);
+    let mut components: Vec<Vec<u64>> = components
+        .into_iter()
+        .chain(non_group)
+        .filter(|component| component.len() > 1)
+        .map(|component| component.into_iter().collect())
+        .collect();
+    components.sort_unstable();
+    let mut articulation_points: Vec<u64> = articulation_points.into_iter().collect();
+    articulation_points.sort_unstable();
+    (components, articulation_points)
+}
+
+/// Returns the communication graph of a history, over its session ids.
+#[must_use]
+pub fn communication_graph<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+) -> UGraph<u64>
+where
+    Variable: Eq + Hash,
+{
+    // accessing sessions and writing sessions of each variable
+    let mut accesses: HashMap<&Variable, (HashSet<u64>, HashSet<u64>)> = HashMap::new();
+    for (session_id, session) in (1..).zip(histories) {
+        for event in session.iter().flat_map(|transaction| &transaction.events) {
+            let (Event::Read { variable, .. } | Event::Write { variable, .. }) = event;
+            let (accessing, writing) = accesses.entry(variable).or_default();
+            accessing.insert(session_id);
+            if matches!(event, Event::Write { .. }) {
+                writing.insert(session_id);
+            }
+        }
+    }
+
+    let mut communication: UGraph<u64> = UGraph::default();
+    for session_id in (1..).take(histories.len()) {
+        communication.add_vertex(session_id);
+    }
+    for (accessing, writing) in accesses.values() {
+        for writer in writing {
+            communication.add_edges(
+                writer,
+                accessing.iter().copied().filter(|other| other != writer),
+            );
+        }
+    }
+    communication
+}
+
+/// Returns the session ids of each connected component of the communication graph.
+#[must_use]
+pub fn session_components<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+) -> Vec<Vec<u64>>
+where
+    Variable: Eq + Hash,
+{
+    connected_components(&communication_graph(histories))
+}
+
+/// A connected component of the communication graph.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Component {
+    pub sessions: Vec<u64>,
+    pub n_transaction: usize,
+}
+
+/// The structure of the communication graph of a history.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decomposition {
+    /// The connected components, which are checked independently
+    pub components: Vec<Component>,
+    /// The biconnected components with at least two sessions
+    pub biconnected_components: Vec<Vec<u64>>,
+    /// The sessions that disconnect their component if removed
+    pub articulation_sessions: Vec<u64>,
+}
+
+/// Renders one line per component, then the biconnected components and the articulation
+/// sessions, such as `component 1: sessions 1, 2 (3 transactions)`.
+impl Display for Decomposition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        writeln!(f, "{} components", self.components.len())?;
+        for (i, component) in (1..).zip(&self.components) {
+            write!(f, "component {i}: sessions ")?;
+            write_sessions(f, &component.sessions)?;
+            writeln!(f, " ({} transactions)", component.n_transaction)?;
+        }
+        write!(f, "biconnected components: ")?;
+        for (i, component) in self.biconnected_components.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{{")?;
+            write_sessions(f, component)?;
+            write!(f, "}}")?;
+        }
+        write!(f, "\narticulation sessions: ")?;
+        write_sessions(f, &self.articulation_sessions)
+    }
+}
+
+/// Writes session ids as `1, 2`.
+fn write_sessions(f: &mut Formatter<'_>, sessions: &[u64]) -> Result {
+    for (i, session_id) in sessions.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{session_id}")?;
+    }
+    Ok(())
+}
+
+/// Returns the decomposition of the communication graph of a history.
+#[must_use]
+pub fn decompose<Variable, Version>(histories: &[Session<Variable, Version>]) -> Decomposition
+where
+    Variable: Eq + Hash,
+{
+    let communication = communication_graph(histories);
+    let (biconnected_components, articulation_sessions) = biconnected_components(&communication);
+    let components = connected_components(&communication)
+        .into_iter()
+        .map(|sessions| Component {
+            n_transaction: sessions
+                .iter()
+                .filter_map(|session_id| histories.get(usize::try_from(*session_id - 1).ok()?))
+                .map(Vec::len)
+                .sum(),
+            sessions,
+        })
+        .collect();
+    Decomposition {
+        components,
+        biconnected_components,
+        articulation_sessions,
+    }
+}
+
+/// Accesses to a single variable, to tell which variables are contended.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariableStats<Variable> {
+    pub variable: Variable,
+    pub n_read: usize,
+    pub n_write: usize,
+    /// Number of pairs of transactions that conflict on the variable
+    pub n_conflict: usize,
+}
+
+/// Returns the statistics of each variable, the most contended first, and in order of first
+/// access among equally contended ones.
+#[must_use]
+pub fn variable_stats<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+) -> Vec<VariableStats<Variable>>
+where
+    Variable: Eq + Hash + Clone,
+{
+    let mut index: HashMap<&Variable, usize> = HashMap::new();
+    let mut stats: Vec<VariableStats<Variable>> = Vec::new();
+    // accessing transactions and writing transactions of each variable, by index
+    let mut accesses: Vec<(HashSet<TransactionId>, HashSet<TransactionId>)> = Vec::new();
+
+    for (session_id, session) in (1..).zip(histories) {
+        for (session_height, transaction) in (0..).zip(session) {
+            let id = TransactionId {
+                session_id,
+                session_height,
+            };
+            for event in &transaction.events {
+                let (Event::Read { variable, .. } | Event::Write { variable, .. }) = event;
+                let i = *index.entry(variable).or_insert_with(|| {
+                    stats.push(VariableStats {
+                        variable: variable.clone(),
+                        n_read: 0,
+                        n_write: 0,
+                        n_conflict: 0,
+                    });
+                    accesses.push(Default::default());
+                    stats.len() - 1
+                });
+                let (accessing, writing) = &mut accesses[i];
+                accessing.insert(id);
+                if matches!(event, Event::Write { .. }) {
+                    stats[i].n_write += 1;
+                    writing.insert(id);
+                } else {
+                    stats[i].n_read += 1;
+                }
+            }
+        }
+    }
+
+    for (variable, (accessing, writing)) in stats.iter_mut().zip(&accesses) {
+        // pairs of writers, counted once, and pairs of a writer with a reader only
+        let n_writer = writing.len();
+        variable.n_conflict =
+            n_writer * n_writer.saturating_sub(1) / 2 + n_writer * (accessing.len() - n_writer);
+    }
+    stats.sort_by_key(|stats| core::cmp::Reverse(stats.n_conflict));
+    stats
+}
+
+impl Display for HistoryStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        writeln!(f, "sessions          {}", self.n_session)?;
+        writeln!(f, "transactions      {}", self.n_transaction)?;
+        writeln!(f, "events            {}", self.n_event)?;
+        writeln!(f, "variables         {}", self.n_variable)?;
+        writeln!(f, "reads             {}", self.n_read)?;
+        writeln!(f, "writes            {}", self.n_write)?;
+        writeln!(f, "read ratio        {:.3}", self.read_ratio())?;
+        writeln!(f, "conflict density  {:.3}", self.conflict_density)?;
+        writeln!(f, "components        {}", self.n_component)?;
+        write!(f, "longest session   {}", self.longest_session)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::non_atomic::types::Transaction;
+
+    #[test]
+    fn test_stats() {
+        let histories = vec![
+            vec![
+                Transaction::committed(vec![Event::write("x", 1)]),
+                Transaction::committed(vec![Event::read("x", 1), Event::write("y", 1)]),
+            ],
+            vec![Transaction::committed(vec![Event::read("y", 1)])],
+            vec![Transaction::committed(vec![Event::read("z", 1)])],
+        ];
+
+        let stats = HistoryStats::from(histories.as_slice());
+
+        assert_eq!(stats.n_session, 3);
+        assert_eq!(stats.n_transaction, 4);
+        assert_eq!(stats.n_event, 5);
+        assert_eq!(stats.n_variable, 3);
+        assert_eq!(stats.n_read, 3);
+        assert_eq!(stats.n_write, 2);
+        assert!((stats.read_ratio() - 0.6).abs() < 1e-9);
+        // (1, 0) - (1, 1) on x and (1, 1) - (2, 0) on y, out of 6 pairs
+        assert!((stats.conflict_density - 2.0 / 6.0).abs() < 1e-9);
+        // sessions 1 and 2 communicate, session 3 is alone
+        assert_eq!(stats.n_component, 2);
+        assert_eq!(session_components(&histories), vec![vec![1, 2], vec![3]]);
+        assert_eq!(stats.longest_session, 2);
+
+        let variables = variable_stats(&histories);
+        assert_eq!(
+            variables
+                .iter()
+                .map(|stats| (stats.variable, stats.n_conflict))
+                .collect::<Vec<_>>(),
+            vec![("x", 1), ("y", 1), ("z", 0)]
+        );
+        assert_eq!((variables[0].n_read, variables[0].n_write), (1, 1));
+    }
+
+    #[test]
+    fn test_decompose() {
+        // sessions 1, 2 and 3 share x, session 4 reads y from session 3, and session 5 is alone
+        let histories = vec![
+            vec![Transaction::committed(vec![Event::write("x", 1)])],
+            vec![
+                Transaction::committed(vec![Event::read("x", 1)]),
+                Transaction::committed(vec![Event::write("x", 2)]),
+            ],
+            vec![Transaction::committed(vec![
+                Event::read("x", 2),
+                Event::write("y", 1),
+            ])],
+            vec![Transaction::committed(vec![Event::read("y", 1)])],
+            vec![Transaction::committed(vec![Event::write("z", 1)])],
+        ];
+
+        let decomposition = decompose(&histories);
+        assert_eq!(
+            decomposition.components,
+            vec![
+                Component {
+                    sessions: vec![1, 2, 3, 4],
+                    n_transaction: 5,
+                },
+                Component {
+                    sessions: vec![5],
+                    n_transaction: 1,
+                },
+            ]
+        );
+        assert_eq!(
+            decomposition.biconnected_components,
+            vec![vec![1, 2, 3], vec![3, 4]]
+        );
+        assert_eq!(decomposition.articulation_sessions, vec![3]);
+        assert_eq!(
+            decomposition.to_string(),
+            "2 components\n\
+             component 1: sessions 1, 2, 3, 4 (5 transactions)\n\
+             component 2: sessions 5 (1 transactions)\n\
+             biconnected components: {1, 2, 3}, {3, 4}\n\
+             articulation sessions: 3"
+        );
+    }
+}
@@ -3,17 +3,58 @@
 #![cfg_attr(not(test), no_std)]
 #![cfg_attr(not(test), no_main)]
 extern crate alloc;
+#[cfg(feature = "parallel")]
+extern crate std;
 
+pub mod check;
+pub mod delta;
+pub mod explain;
+pub mod export;
 pub mod graph;
 pub mod history;
+pub mod query;
+pub mod repair;
+pub mod replay;
+pub mod shard;
 pub mod solver;
 
-#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Consistency {
     CommittedRead,
+    RepeatableRead,
     AtomicRead,
     Causal,
     Prefix,
     SnapshotIsolation,
     Serializable,
 }
+
+impl Consistency {
+    /// The levels from the weakest to the strongest, each implying the ones before it.
+    pub const LEVELS: [Self; 7] = [
+        Self::CommittedRead,
+        Self::RepeatableRead,
+        Self::AtomicRead,
+        Self::Causal,
+        Self::Prefix,
+        Self::SnapshotIsolation,
+        Self::Serializable,
+    ];
+}
+
+/// Renders the name of the level, such as `causal consistency`.
+impl core::fmt::Display for Consistency {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            Self::CommittedRead => "read committed",
+            Self::RepeatableRead => "repeatable read",
+            Self::AtomicRead => "read atomic",
+            Self::Causal => "causal consistency",
+            Self::Prefix => "prefix consistency",
+            Self::SnapshotIsolation => "snapshot isolation",
+            Self::Serializable => "serializability",
+        })
+    }
+}
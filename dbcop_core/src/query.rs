@@ -0,0 +1,287 @@
+//! Answers why a transaction reads the version it reads.
+//!
+//! [`explain_read`] finds the transaction that writes the version a read returns, and relates each
+//! other committed writer of the variable to the reader and to that writer. A writer is known to
+//! precede a transaction by session order, or causally, through session order and write-read
+//! edges. A writer that causally precedes the reader and follows the writer is one the reader
+//! should have read instead under causal consistency.
+
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter, Result};
+use core::hash::Hash;
+
+use crate::graph::digraph::DiGraph;
+use crate::history::atomic::types::{AtomicTransactionHistory, TransactionId};
+use crate::history::atomic::AtomicTransactionPO;
+use crate::history::non_atomic::observed_unknown;
+use crate::history::non_atomic::types::{Event, EventId, Session};
+
+/// What orders a transaction before another.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    SessionOrder,
+    /// A chain of session order and write-read edges
+    Causal,
+}
+
+/// Renders as `by session order` or `causally`.
+impl Display for Order {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::SessionOrder => write!(f, "by session order"),
+            Self::Causal => write!(f, "causally"),
+        }
+    }
+}
+
+/// Another committed writer of the variable a read returns, with its known order to the reader
+/// and to the writer of the version read.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub transaction: TransactionId,
+    pub before_reader: Option<Order>,
+    pub after_reader: Option<Order>,
+    pub before_writer: Option<Order>,
+    pub after_writer: Option<Order>,
+}
+
+/// Why a read returns its version.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadExplanation<Variable, Version> {
+    pub read: EventId,
+    pub variable: Variable,
+    /// `None` for the initial version
+    pub version: Option<Version>,
+    /// The transaction that writes the version, the root transaction for the initial version, or
+    /// `None` if no committed transaction writes it
+    pub writer: Option<TransactionId>,
+    /// The other committed writers of the variable, in order
+    pub candidates: Vec<Candidate>,
+}
+
+/// Returns why the event `read` returns its version, or `None` if it is not a read of the history.
+///
+/// The orders of the candidates are known only if the history passes the checks of the atomic
+/// history, as the write-read edges are not defined otherwise.
+#[must_use]
+pub fn explain_read<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    read: EventId,
+) -> Option<ReadExplanation<Variable, Version>>
+where
+    Variable: Eq + Hash + Clone,
+    Version: Eq + Hash + Clone,
+{
+    let reader = read.transaction_id();
+    let (variable, version) = match histories
+        .get(usize::try_from(read.session_id.checked_sub(1)?).ok()?)?
+        .get(usize::try_from(read.session_height).ok()?)?
+        .events
+        .get(usize::try_from(read.transaction_height).ok()?)?
+    {
+        Event::Read { variable, version } => (variable, version),
+        Event::Write { .. } => return None,
+    };
+
+    // the committed writers of the variable, and the one of the version read
+    let observed = observed_unknown(histories);
+    let mut writer = version.is_none().then(TransactionId::root);
+    let mut writers = Vec::new();
+    for (session_id, session) in (1..).zip(histories) {
+        for (session_height, transaction) in (0..).zip(session) {
+            let id = TransactionId {
+                session_id,
+                session_height,
+            };
+            if !transaction.committed && !observed.contains(&id) {
+                continue;
+            }
+            for event in &transaction.events {
+                if let Event::Write {
+                    variable: written,
+                    version: written_version,
+                } = event
+                {
+                    if written == variable {
+                        if version.as_ref() == Some(written_version) {
+                            writer.get_or_insert(id);
+                        }
+                        if writers.last() != Some(&id) {
+                            writers.push(id);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // session order and its union with the write-read edges, both closed
+    let orders = AtomicTransactionHistory::try_from(histories)
+        .ok()
+        .map(|history| {
+            let mut po = AtomicTransactionPO::from(history);
+            po.vis_includes_wr();
+            let causal = po.visibility_relation.closure();
+            (po.session_order, causal)
+        });
+    let order = |source: &TransactionId, target: &TransactionId| {
+        let (session_order, causal): &(DiGraph<TransactionId>, DiGraph<TransactionId>) =
+            orders.as_ref()?;
+        if session_order.has_edge(source, target) {
+            Some(Order::SessionOrder)
+        } else {
+            causal.has_edge(source, target).then_some(Order::Causal)
+        }
+    };
+
+    let candidates = writers
+        .into_iter()
+        .filter(|id| Some(*id) != writer && *id != reader)
+        .map(|id| Candidate {
+            transaction: id,
+            before_reader: order(&id, &reader),
+            after_reader: order(&reader, &id),
+            before_writer: writer.and_then(|writer| order(&id, &writer)),
+            after_writer: writer.and_then(|writer| order(&writer, &id)),
+        })
+        .collect();
+
+    Some(ReadExplanation {
+        read,
+        variable: variable.clone(),
+        version: version.clone(),
+        writer,
+        candidates,
+    })
+}
+
+/// Renders the read and its writer on the first line, then one line per candidate, such as
+/// `s3.t0 also writes x: before the reader by session order, after the writer causally`.
+impl<Variable, Version> Display for ReadExplanation<Variable, Version>
+where
+    Variable: Display,
+    Version: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match (&self.version, &self.writer) {
+            (None, _) => write!(
+                f,
+                "{} reads the initial version of {}",
+                self.read, self.variable
+            )?,
+            (Some(version), Some(writer)) => write!(
+                f,
+                "{} reads {}={version}, written by {writer}",
+                self.read, self.variable
+            )?,
+            (Some(version), None) => write!(
+                f,
+                "{} reads {}={version}, which no committed transaction writes",
+                self.read, self.variable
+            )?,
+        }
+        for candidate in &self.candidates {
+            write!(
+                f,
+                "\n{} also writes {}",
+                candidate.transaction, self.variable
+            )?;
+            let relations = [
+                ("before the reader", candidate.before_reader),
+                ("after the reader", candidate.after_reader),
+                ("before the writer", candidate.before_writer),
+                ("after the writer", candidate.after_writer),
+            ];
+            let mut first = true;
+            for (relation, order) in relations {
+                if let Some(order) = order {
+                    write!(f, "{} {relation} {order}", if first { ":" } else { "," })?;
+                    first = false;
+                }
+            }
+            if first {
+                write!(f, ", unordered")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    use super::*;
+    use crate::history::non_atomic::types::Transaction;
+
+    #[test]
+    fn test_explain_read() {
+        // s3.t1 reads x=1 although it sees x=2 through y
+        let histories = vec![
+            vec![Transaction::committed(vec![Event::write("x", 1)])],
+            vec![Transaction::committed(vec![
+                Event::read("x", 1),
+                Event::write("x", 2),
+                Event::write("y", 1),
+            ])],
+            vec![
+                Transaction::committed(vec![Event::read("y", 1)]),
+                Transaction::committed(vec![Event::read("x", 1)]),
+            ],
+            vec![Transaction::committed(vec![Event::write("x", 3)])],
+        ];
+        let id = |session_id, session_height| TransactionId {
+            session_id,
+            session_height,
+        };
+        let read = EventId {
+            session_id: 3,
+            session_height: 1,
+            transaction_height: 0,
+        };
+
+        let explanation = explain_read(&histories, read).unwrap();
+        assert_eq!(explanation.writer, Some(id(1, 0)));
+        assert_eq!(
+            explanation.candidates,
+            vec![
+                Candidate {
+                    transaction: id(2, 0),
+                    before_reader: Some(Order::Causal),
+                    after_reader: None,
+                    before_writer: None,
+                    after_writer: Some(Order::Causal),
+                },
+                Candidate {
+                    transaction: id(4, 0),
+                    before_reader: None,
+                    after_reader: None,
+                    before_writer: None,
+                    after_writer: None,
+                },
+            ]
+        );
+        assert_eq!(
+            explanation.to_string(),
+            "s3.t1.e0 reads x=1, written by s1.t0\n\
+             s2.t0 also writes x: before the reader causally, after the writer causally\n\
+             s4.t0 also writes x, unordered"
+        );
+
+        // a write is not a read
+        assert!(explain_read(
+            &histories,
+            EventId {
+                session_id: 1,
+                session_height: 0,
+                transaction_height: 0,
+            }
+        )
+        .is_none());
+    }
+}
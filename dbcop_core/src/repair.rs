@@ -0,0 +1,558 @@
+//! Measures how far a history is from satisfying a consistency level.
+//!
+//! [`minimal_repair`] finds a smallest set of reads whose versions would have to change for the
+//! history to satisfy a level. Each read of the set is dropped rather than assigned a new version:
+//! dropping a read only removes constraints, so a history that satisfies the level after changing
+//! some versions also satisfies it after dropping their reads.
+//!
+//! The sets are searched by increasing size, each checked from scratch, so the search is
+//! exponential in the size of the repair and is bounded by the caller.
+//!
+//! [`find_max_consistent_subset`] isolates a few culprit transactions of a large history instead:
+//! it excludes transactions of the violation the checker reports until the rest satisfies the
+//! level, then includes back every excluded transaction that does not break it again.
+//!
+//! [`near_violations`] looks the other way, at a history that satisfies a level: it adds one read
+//! at a time to a transaction, of a version written by another transaction, and reports the reads
+//! that would violate the level. A generated workload whose histories have few such reads exercises
+//! the level poorly, as no plausible single anomaly would have been caught.
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+use hashbrown::{HashMap, HashSet};
+
+use crate::check::check;
+use crate::history::atomic::types::TransactionId;
+use crate::history::non_atomic::observed_unknown;
+use crate::history::non_atomic::types::{Event, EventId, Session, Transaction};
+use crate::solver::error::Error;
+use crate::Consistency;
+
+/// A subset of the committed transactions of a history that satisfies a level.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsistentSubset {
+    /// In the order of the history
+    pub included: Vec<TransactionId>,
+    /// In the order of the history
+    pub excluded: Vec<TransactionId>,
+}
+
+/// The reads that would make a history violate a level, out of the probed ones.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Fragility<Variable, Version> {
+    /// Number of reads probed
+    pub probes: usize,
+    /// In the order of the history of their readers
+    pub near_violations: Vec<NearViolation<Variable, Version>>,
+}
+
+/// A read that, added to a history, violates a level.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Debug, Clone)]
+pub struct NearViolation<Variable, Version> {
+    /// The transaction the read is added to
+    pub reader: TransactionId,
+    /// The transaction that wrote the version read
+    pub writer: TransactionId,
+    pub variable: Variable,
+    pub version: Version,
+    /// The violation of the history with the read
+    pub error: Error<Variable, Version>,
+}
+
+/// Returns a smallest set of reads whose versions would have to change for `histories` to satisfy
+/// `level`, in the order of the history, or `None` if every such set has more than `max_size`
+/// reads.
+///
+/// The set is empty if the history already satisfies `level`. Only the reads of the committed
+/// transactions are candidates, as the checkers ignore the aborted ones.
+#[must_use]
+pub fn minimal_repair<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    level: Consistency,
+    max_size: usize,
+) -> Option<Vec<EventId>>
+where
+    Variable: Clone + Eq + Ord + Hash,
+    Version: Clone + Eq + Hash,
+{
+    let observed = observed_unknown(histories);
+    let mut reads = Vec::new();
+    for (session_id, session) in (1..).zip(histories) {
+        for (session_height, transaction) in (0..).zip(session) {
+            let id = TransactionId {
+                session_id,
+                session_height,
+            };
+            if !transaction.committed && !observed.contains(&id) {
+                continue;
+            }
+            for (transaction_height, event) in (0..).zip(&transaction.events) {
+                if matches!(event, Event::Read { .. }) {
+                    reads.push(EventId {
+                        session_id,
+                        session_height,
+                        transaction_height,
+                    });
+                }
+            }
+        }
+    }
+
+    for size in 0..=max_size.min(reads.len()) {
+        // the indices of the current subset of `reads`, in increasing order
+        let mut subset: Vec<usize> = (0..size).collect();
+        loop {
+            let dropped: Vec<EventId> = subset.iter().map(|&i| reads[i]).collect();
+            if check(&drop_reads(histories, &dropped), level).is_ok() {
+                return Some(dropped);
+            }
+            // advances to the next subset in lexicographic order
+            let Some(i) = (0..size)
+                .rev()
+                .find(|&i| subset[i] < reads.len() - size + i)
+            else {
+                break;
+            };
+            subset[i] += 1;
+            for j in i + 1..size {
+                subset[j] = subset[j - 1] + 1;
+            }
+        }
+    }
+    None
+}
+
+/// Returns a large subset of the committed transactions of `histories` that satisfies `level`, and
+/// the excluded ones. See the module documentation.
+///
+/// The subset is maximal, as including back any excluded transaction violates `level`, but not
+/// necessarily the largest one. Excluding a transaction excludes the transactions that read its
+/// writes too, and keeps the session order between the others.
+#[must_use]
+pub fn find_max_consistent_subset<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    level: Consistency,
+) -> ConsistentSubset
+where
+    Variable: Clone + Eq + Ord + Hash,
+    Version: Clone + Eq + Hash,
+{
+    let observed = observed_unknown(histories);
+    let mut committed = Vec::new();
+    let mut writers = HashMap::new();
+    for (session_id, session) in (1..).zip(histories) {
+        for (session_height, transaction) in (0..).zip(session) {
+            let id = TransactionId {
+                session_id,
+                session_height,
+            };
+            if !transaction.committed && !observed.contains(&id) {
+                continue;
+            }
+            committed.push(id);
+            for event in &transaction.events {
+                if let Event::Write { variable, version } = event {
+                    writers.insert((variable, version), id);
+                }
+            }
+        }
+    }
+    let mut readers: HashMap<TransactionId, Vec<TransactionId>> = HashMap::new();
+    for &id in &committed {
+        for event in &transaction(histories, id).events {
+            if let Event::Read {
+                variable,
+                version: Some(version),
+            } = event
+            {
+                if let Some(&writer) = writers.get(&(variable, version)) {
+                    if writer != id {
+                        readers.entry(writer).or_default().push(id);
+                    }
+                }
+            }
+        }
+    }
+    // the transaction and the ones that read from it, transitively
+    let cascade = |id: TransactionId, excluded: &HashSet<TransactionId>| {
+        let mut cascade: HashSet<TransactionId> = core::iter::once(id).collect();
+        let mut queue = VecDeque::from([id]);
+        while let Some(writer) = queue.pop_front() {
+            for &reader in readers.get(&writer).into_iter().flatten() {
+                if !excluded.contains(&reader) && cascade.insert(reader) {
+                    queue.push_back(reader);
+                }
+            }
+        }
+        cascade
+    };
+
+    let mut excluded = HashSet::new();
+    loop {
+        let candidates: Vec<TransactionId> = match check(&exclude(histories, &excluded), level) {
+            Ok(_) => break,
+            Err(Error::Cycle { cycle, .. }) => cycle
+                .iter()
+                .flat_map(|edge| [edge.source, edge.target])
+                .filter(|id| *id != TransactionId::root() && !excluded.contains(id))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        let candidates = if candidates.is_empty() {
+            committed
+                .iter()
+                .copied()
+                .filter(|id| !excluded.contains(id))
+                .collect()
+        } else {
+            candidates
+        };
+        // excludes the fewest transactions
+        let Some(smallest) = candidates
+            .into_iter()
+            .map(|id| cascade(id, &excluded))
+            .min_by_key(HashSet::len)
+        else {
+            break;
+        };
+        excluded.extend(smallest);
+    }
+
+    // includes back the transactions that are not part of the violations
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for id in &committed {
+            if excluded.remove(id) {
+                if check(&exclude(histories, &excluded), level).is_ok() {
+                    changed = true;
+                } else {
+                    excluded.insert(*id);
+                }
+            }
+        }
+    }
+
+    let (excluded, included) = committed.into_iter().partition(|id| excluded.contains(id));
+    ConsistentSubset { included, excluded }
+}
+
+/// Probes `histories`, which satisfies `level`, with one additional read at a time, and returns the
+/// reads that would violate it. See the module documentation.
+///
+/// A committed transaction is probed with a read of the last version written to a variable by
+/// every other committed transaction, if it does not access the variable itself: another read of
+/// an accessed variable would only break its repeatable reads. Each probe is checked from scratch,
+/// so the cost grows with the number of transactions times the number of writes.
+///
+/// # Errors
+///
+/// Returns the violation of `histories` if it does not satisfy `level` in the first place.
+pub fn near_violations<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    level: Consistency,
+) -> Result<Fragility<Variable, Version>, Error<Variable, Version>>
+where
+    Variable: Clone + Eq + Ord + Hash,
+    Version: Clone + Eq + Hash,
+{
+    check(histories, level)?;
+
+    let observed = observed_unknown(histories);
+    let mut committed = Vec::new();
+    // the last version each transaction writes to each variable, in the order of the history
+    let mut writes: Vec<(TransactionId, &Variable, &Version)> = Vec::new();
+    for (session_id, session) in (1..).zip(histories) {
+        for (session_height, transaction) in (0..).zip(session) {
+            let id = TransactionId {
+                session_id,
+                session_height,
+            };
+            if !transaction.committed && !observed.contains(&id) {
+                continue;
+            }
+            committed.push(id);
+            let mut last: HashMap<&Variable, &Version> = HashMap::new();
+            for event in &transaction.events {
+                if let Event::Write { variable, version } = event {
+                    last.insert(variable, version);
+                }
+            }
+            let mut last: Vec<_> = last.into_iter().collect();
+            last.sort_unstable_by_key(|&(variable, _)| variable);
+            writes.extend(
+                last.into_iter()
+                    .map(|(variable, version)| (id, variable, version)),
+            );
+        }
+    }
+
+    let mut fragility = Fragility {
+        probes: 0,
+        near_violations: Vec::new(),
+    };
+    let mut probed = histories.to_vec();
+    for reader in committed {
+        let (session, height) = indices(reader);
+        let accessed: HashSet<&Variable> = transaction(histories, reader)
+            .events
+            .iter()
+            .map(|event| match event {
+                Event::Read { variable, .. } | Event::Write { variable, .. } => variable,
+            })
+            .collect();
+        for &(writer, variable, version) in &writes {
+            if writer == reader || accessed.contains(variable) {
+                continue;
+            }
+            fragility.probes += 1;
+            probed[session][height]
+                .events
+                .push(Event::read(variable.clone(), version.clone()));
+            if let Err(error) = check(&probed, level) {
+                fragility.near_violations.push(NearViolation {
+                    reader,
+                    writer,
+                    variable: variable.clone(),
+                    version: version.clone(),
+                    error,
+                });
+            }
+            probed[session][height].events.pop();
+        }
+    }
+    Ok(fragility)
+}
+
+/// Returns the indices of the session and of the transaction `id` in a history.
+fn indices(id: TransactionId) -> (usize, usize) {
+    (
+        usize::try_from(id.session_id - 1).expect("session id fits in usize"),
+        usize::try_from(id.session_height).expect("session height fits in usize"),
+    )
+}
+
+fn transaction<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    id: TransactionId,
+) -> &Transaction<Variable, Version> {
+    let (session, height) = indices(id);
+    &histories[session][height]
+}
+
+/// Returns `histories` with the transactions of `excluded` emptied, which keeps the transaction ids
+/// and the session order between the others.
+fn exclude<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    excluded: &HashSet<TransactionId>,
+) -> Vec<Session<Variable, Version>>
+where
+    Variable: Clone,
+    Version: Clone,
+{
+    (1..)
+        .zip(histories)
+        .map(|(session_id, session)| {
+            (0..)
+                .zip(session)
+                .map(|(session_height, transaction)| {
+                    let mut transaction = transaction.clone();
+                    if excluded.contains(&TransactionId {
+                        session_id,
+                        session_height,
+                    }) {
+                        transaction.events.clear();
+                    }
+                    transaction
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Returns `histories` without the events of `reads`, which is sorted.
+fn drop_reads<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    reads: &[EventId],
+) -> Vec<Session<Variable, Version>>
+where
+    Variable: Clone,
+    Version: Clone,
+{
+    (1..)
+        .zip(histories)
+        .map(|(session_id, session)| {
+            (0..)
+                .zip(session)
+                .map(|(session_height, transaction)| {
+                    let mut transaction = transaction.clone();
+                    transaction.events = (0..)
+                        .zip(&transaction.events)
+                        .filter(|(transaction_height, _)| {
+                            reads
+                                .binary_search(&EventId {
+                                    session_id,
+                                    session_height,
+                                    transaction_height: *transaction_height,
+                                })
+                                .is_err()
+                        })
+                        .map(|(_, event)| event.clone())
+                        .collect();
+                    transaction
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn test_minimal_repair() {
+        let event = |session_id, session_height, transaction_height| EventId {
+            session_id,
+            session_height,
+            transaction_height,
+        };
+        // a lost update, and a write skew
+        let histories = vec![
+            vec![Transaction::committed(vec![
+                Event::write("x", 1),
+                Event::write("y", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read("x", 1),
+                Event::read("y", 1),
+                Event::write("x", 2),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read("x", 1),
+                Event::read("y", 1),
+                Event::write("y", 2),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read("x", 1),
+                Event::write("x", 3),
+            ])],
+        ];
+
+        assert_eq!(
+            minimal_repair(&histories, Consistency::Causal, 2),
+            Some(vec![])
+        );
+        // s2 reading x=3 instead ends the lost update
+        assert_eq!(
+            minimal_repair(&histories, Consistency::SnapshotIsolation, 2),
+            Some(vec![event(2, 0, 0)])
+        );
+        // the write skew needs another read to change
+        let repair = minimal_repair(&histories, Consistency::Serializable, 2).unwrap();
+        assert_eq!(repair.len(), 2);
+        assert_eq!(
+            minimal_repair(&histories, Consistency::Serializable, 1),
+            None
+        );
+    }
+
+    #[test]
+    fn test_find_max_consistent_subset() {
+        let id = |session_id, session_height| TransactionId {
+            session_id,
+            session_height,
+        };
+        // s2.t0 and s3.t0 lose an update of x, and s3.t1 reads from s3.t0
+        let histories = vec![
+            vec![Transaction::committed(vec![Event::write("x", 1)])],
+            vec![Transaction::committed(vec![
+                Event::read("x", 1),
+                Event::write("x", 2),
+            ])],
+            vec![
+                Transaction::committed(vec![Event::read("x", 1), Event::write("x", 3)]),
+                Transaction::committed(vec![Event::read("x", 3), Event::write("y", 1)]),
+            ],
+            vec![Transaction::committed(vec![Event::read("y", 1)])],
+        ];
+
+        let subset = find_max_consistent_subset(&histories, Consistency::Causal);
+        assert!(subset.excluded.is_empty());
+        assert_eq!(subset.included.len(), 5);
+
+        for level in [Consistency::SnapshotIsolation, Consistency::Serializable] {
+            let subset = find_max_consistent_subset(&histories, level);
+            assert_eq!(subset.excluded, vec![id(2, 0)]);
+            assert_eq!(
+                subset.included,
+                vec![id(1, 0), id(3, 0), id(3, 1), id(4, 0)]
+            );
+        }
+
+        // s1.t0 reads a version nobody writes, and excluding it excludes its readers
+        let histories = vec![
+            vec![Transaction::committed(vec![
+                Event::read("y", 1),
+                Event::write("x", 1),
+            ])],
+            vec![Transaction::committed(vec![Event::read("x", 1)])],
+            vec![Transaction::committed(vec![Event::read("x", 1)])],
+            vec![Transaction::committed(vec![Event::read_empty("x")])],
+        ];
+        let subset = find_max_consistent_subset(&histories, Consistency::CommittedRead);
+        assert_eq!(subset.excluded, vec![id(1, 0), id(2, 0), id(3, 0)]);
+        assert_eq!(subset.included, vec![id(4, 0)]);
+    }
+
+    #[test]
+    fn test_near_violations() {
+        let id = |session_id, session_height| TransactionId {
+            session_id,
+            session_height,
+        };
+        // s1 reading y from s2 would break even read committed, as s2 reads x from s1
+        let histories = vec![
+            vec![Transaction::committed(vec![Event::write("x", 1)])],
+            vec![Transaction::committed(vec![
+                Event::read("x", 1),
+                Event::write("y", 1),
+            ])],
+        ];
+        for level in [Consistency::Causal, Consistency::Serializable] {
+            let fragility = near_violations(&histories, level).unwrap();
+            // s2 accesses both variables, so only s1 is probed
+            assert_eq!(fragility.probes, 1);
+            assert_eq!(fragility.near_violations.len(), 1);
+            let near = &fragility.near_violations[0];
+            assert_eq!((near.reader, near.writer), (id(1, 0), id(2, 0)));
+            assert_eq!((near.variable, near.version), ("y", 1));
+        }
+
+        // independent writers tolerate any single read, and only the last version is read
+        let histories = vec![
+            vec![Transaction::committed(vec![
+                Event::write("x", 1),
+                Event::write("x", 2),
+            ])],
+            vec![Transaction::committed(vec![Event::write("y", 1)])],
+        ];
+        let fragility = near_violations(&histories, Consistency::Serializable).unwrap();
+        assert_eq!(fragility.probes, 2);
+        assert!(fragility.near_violations.is_empty());
+
+        // a violating history is not probed
+        let histories = vec![
+            vec![Transaction::committed(vec![Event::read("x", 1)])],
+            vec![Transaction::committed(vec![Event::read("y", 1)])],
+        ];
+        assert!(near_violations(&histories, Consistency::CommittedRead).is_err());
+    }
+}
@@ -0,0 +1,215 @@
+//! Replays the commit order of a witness step by step, for animating it.
+//!
+//! Each step places a transaction, or one section of it for a split commit order, and records the
+//! transactions committed before it along with the events it performs. A transaction is committed
+//! at its position in a commit order, or at its write section in a split commit order.
+//!
+//! An operation order is replayed by runs of consecutive events of a transaction: a transaction
+//! with a single run is placed whole, and one with two runs, from a split commit order, is placed
+//! by its read and write sections.
+
+use alloc::vec::Vec;
+
+use hashbrown::HashSet;
+
+use crate::check::Witness;
+use crate::history::atomic::types::TransactionId;
+use crate::history::non_atomic::types::{Event, Session};
+
+/// What a step of a replay places.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    /// The whole transaction, which reads and commits at once
+    Transaction,
+    /// The reads of the transaction
+    Read,
+    /// The writes of the transaction, which commit it
+    Write,
+}
+
+/// A step of a replay.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayStep<Variable, Version> {
+    pub transaction: TransactionId,
+    pub section: Section,
+    /// The transactions committed before the step, in commit order
+    pub visible: Vec<TransactionId>,
+    /// The reads of the step, with the versions they return
+    pub reads: Vec<(Variable, Option<Version>)>,
+    /// The writes of the step
+    pub writes: Vec<(Variable, Version)>,
+}
+
+/// Returns the steps of the commit order of `witness`. A saturation witness has no commit order,
+/// and no steps.
+///
+/// Transactions of the order that are not in `histories` are placed without events.
+#[must_use]
+pub fn replay<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    witness: &Witness,
+) -> Vec<ReplayStep<Variable, Version>>
+where
+    Variable: Clone,
+    Version: Clone,
+{
+    let sections: Vec<(TransactionId, Section)> = match witness {
+        Witness::Saturated => Vec::new(),
+        Witness::CommitOrder(order) => order.iter().map(|id| (*id, Section::Transaction)).collect(),
+        Witness::SplitCommitOrder(order) => order
+            .iter()
+            .map(|(id, write)| {
+                (
+                    *id,
+                    if *write {
+                        Section::Write
+                    } else {
+                        Section::Read
+                    },
+                )
+            })
+            .collect(),
+        Witness::OperationOrder(order) => {
+            let mut runs: Vec<TransactionId> = Vec::new();
+            for event in order {
+                let id = event.transaction_id();
+                if runs.last() != Some(&id) {
+                    runs.push(id);
+                }
+            }
+            let mut placed = HashSet::new();
+            runs.iter()
+                .map(|id| {
+                    let section = if runs.iter().filter(|run| *run == id).count() == 1 {
+                        Section::Transaction
+                    } else if placed.insert(*id) {
+                        Section::Read
+                    } else {
+                        Section::Write
+                    };
+                    (*id, section)
+                })
+                .collect()
+        }
+    };
+
+    let mut visible = Vec::new();
+    sections
+        .into_iter()
+        .map(|(transaction, section)| {
+            let events = transaction
+                .session_id
+                .checked_sub(1)
+                .and_then(|session| histories.get(usize::try_from(session).ok()?))
+                .and_then(|session| session.get(usize::try_from(transaction.session_height).ok()?))
+                .map_or(&[][..], |transaction| &transaction.events[..]);
+
+            let mut reads = Vec::new();
+            let mut writes = Vec::new();
+            for event in events {
+                match event {
+                    Event::Read { variable, version } if section != Section::Write => {
+                        reads.push((variable.clone(), version.clone()));
+                    }
+                    Event::Write { variable, version } if section != Section::Read => {
+                        writes.push((variable.clone(), version.clone()));
+                    }
+                    _ => {}
+                }
+            }
+
+            let step = ReplayStep {
+                transaction,
+                section,
+                visible: visible.clone(),
+                reads,
+                writes,
+            };
+            if section != Section::Read {
+                visible.push(transaction);
+            }
+            step
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::check::check;
+    use crate::history::non_atomic::types::Transaction;
+    use crate::Consistency;
+
+    #[test]
+    fn test_replay() {
+        let histories = vec![
+            vec![Transaction::committed(vec![Event::write("x", 1)])],
+            vec![Transaction::committed(vec![
+                Event::read("x", 1),
+                Event::write("y", 1),
+            ])],
+        ];
+        let id = |session_id| TransactionId {
+            session_id,
+            session_height: 0,
+        };
+
+        let witness = check(&histories, Consistency::Serializable).unwrap();
+        let steps = replay(&histories, &witness);
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0].transaction, id(1));
+        assert_eq!(steps[0].writes, vec![("x", 1)]);
+        assert_eq!(steps[1].visible, vec![id(1)]);
+        assert_eq!(steps[1].reads, vec![("x", Some(1))]);
+        assert_eq!(steps[1].writes, vec![("y", 1)]);
+
+        let witness = check(&histories, Consistency::SnapshotIsolation).unwrap();
+        let steps = replay(&histories, &witness);
+        assert_eq!(steps.len(), 4);
+        let read = steps
+            .iter()
+            .find(|step| step.transaction == id(2) && step.section == Section::Read)
+            .unwrap();
+        assert_eq!(read.visible, vec![id(1)]);
+        assert_eq!(read.reads, vec![("x", Some(1))]);
+        assert!(read.writes.is_empty());
+
+        // adjacent sections of a transaction make a single run of its operations
+        let operations = witness.into_operation_order(&histories);
+        let sections: Vec<_> = replay(&histories, &operations)
+            .iter()
+            .map(|step| (step.transaction, step.section))
+            .collect();
+        assert_eq!(
+            sections,
+            vec![(id(1), Section::Transaction), (id(2), Section::Transaction)]
+        );
+
+        // a write skew interleaves the sections, and its operation order replays the same steps
+        let histories = vec![
+            vec![Transaction::committed(vec![
+                Event::read_empty("x"),
+                Event::read_empty("y"),
+                Event::write("x", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read_empty("x"),
+                Event::read_empty("y"),
+                Event::write("y", 1),
+            ])],
+        ];
+        let witness = check(&histories, Consistency::SnapshotIsolation).unwrap();
+        let operations = witness.clone().into_operation_order(&histories);
+        assert_eq!(
+            replay(&histories, &operations),
+            replay(&histories, &witness)
+        );
+
+        assert!(replay(&histories, &Witness::Saturated).is_empty());
+    }
+}
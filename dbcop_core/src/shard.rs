@@ -0,0 +1,315 @@
+//! Checks a long history in overlapping shards, for captures too long to check at once.
+//!
+//! A shard is a window of consecutive transactions in an order of the whole history, such as
+//! [`round_order`] or the order of their timestamps with [`order_by_meta`]. Each window repeats the
+//! last transactions of the previous one, so that a violation between neighbouring windows is
+//! still found in one of them.
+//!
+//! A shard keeps the transaction ids of the history: the transactions of its sessions before the
+//! window are emptied, and the ones after it are dropped. The versions its transactions read from
+//! writers outside the window are carried forward: each such writer is replaced by a transaction
+//! of a new session past the sessions of the history, with only the carried writes. A shard is
+//! then a sub-history of the history with fewer constraints, so a violation of a shard is a
+//! violation of the history. The converse does not hold, as a violation may span transactions
+//! further apart than a window.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::hash::Hash;
+use core::ops::Range;
+
+use hashbrown::{HashMap, HashSet};
+#[cfg(feature = "parallel")]
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::check::check;
+use crate::history::atomic::types::TransactionId;
+use crate::history::non_atomic::types::{Event, Session, Transaction};
+use crate::solver::error::Error;
+use crate::Consistency;
+
+/// A violation found in a shard.
+#[derive(Debug, Clone)]
+pub struct ShardViolation<Variable, Version> {
+    /// The positions of the window in the order
+    pub window: Range<usize>,
+    /// Transactions of sessions past the ones of the history stand for carried writes
+    pub error: Error<Variable, Version>,
+}
+
+/// Returns the transactions of a history in round order: the first transaction of every session,
+/// then the second ones, and so on.
+#[must_use]
+pub fn round_order<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+) -> Vec<TransactionId> {
+    let mut order: Vec<TransactionId> = (1..)
+        .zip(histories)
+        .flat_map(|(session_id, session)| {
+            (0..session.len() as u64).map(move |session_height| TransactionId {
+                session_id,
+                session_height,
+            })
+        })
+        .collect();
+    order.sort_unstable_by_key(|id| (id.session_height, id.session_id));
+    order
+}
+
+/// Returns the transactions of a history ordered by their metadata value under `key`, such as a
+/// commit timestamp, and in [`round_order`] between equal values.
+///
+/// The values are compared as strings, which orders RFC 3339 timestamps in the same time zone and
+/// zero-padded numbers. The transactions without the key come first.
+#[must_use]
+pub fn order_by_meta<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    key: &str,
+) -> Vec<TransactionId> {
+    let mut order = round_order(histories);
+    order.sort_by_key(|id| transaction(histories, *id).meta.get(key));
+    order
+}
+
+/// Concatenates a later capture of the same sessions to a history: the `i`-th session of `later`
+/// continues the `i`-th session of `histories`, or starts a new session past its last one.
+#[must_use]
+pub fn concat<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    later: &[Session<Variable, Version>],
+) -> Vec<Session<Variable, Version>>
+where
+    Variable: Clone,
+    Version: Clone,
+{
+    let mut concatenated = histories.to_vec();
+    for (i, session) in later.iter().enumerate() {
+        match concatenated.get_mut(i) {
+            Some(earlier) => earlier.extend_from_slice(session),
+            None => concatenated.push(session.clone()),
+        }
+    }
+    concatenated
+}
+
+/// Returns the windows of `size` transactions of an order of `length` transactions, each
+/// starting `overlap` transactions before the end of the previous one.
+///
+/// # Panics
+///
+/// Panics if `overlap` is not smaller than `size`.
+#[must_use]
+pub fn windows(length: usize, size: usize, overlap: usize) -> Vec<Range<usize>> {
+    assert!(overlap < size, "the overlap must be smaller than the shard");
+    let mut windows = Vec::new();
+    let mut start = 0;
+    loop {
+        let end = (start + size).min(length);
+        windows.push(start..end);
+        if end == length {
+            return windows;
+        }
+        start = end - overlap;
+    }
+}
+
+/// Returns the shard of the transactions of `window`, with the versions they read from other
+/// transactions carried forward. See the module documentation.
+#[must_use]
+pub fn shard<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    window: &[TransactionId],
+) -> Vec<Session<Variable, Version>>
+where
+    Variable: Clone + Eq + Hash,
+    Version: Clone + Eq + Hash,
+{
+    let inside: HashSet<TransactionId> = window.iter().copied().collect();
+    let mut writers = HashMap::new();
+    for (session_id, session) in (1..).zip(histories) {
+        for (session_height, transaction) in (0..).zip(session) {
+            for event in &transaction.events {
+                if let Event::Write { variable, version } = event {
+                    let id = TransactionId {
+                        session_id,
+                        session_height,
+                    };
+                    writers.insert((variable, version), id);
+                }
+            }
+        }
+    }
+
+    // the writes carried forward, by writer
+    let mut carried: BTreeMap<TransactionId, Vec<Event<Variable, Version>>> = BTreeMap::new();
+    for id in window {
+        for event in &transaction(histories, *id).events {
+            if let Event::Read {
+                variable,
+                version: Some(version),
+            } = event
+            {
+                if let Some(writer) = writers.get(&(variable, version)) {
+                    if !inside.contains(writer) {
+                        let write = Event::write(variable.clone(), version.clone());
+                        let carried_writes = carried.entry(*writer).or_default();
+                        if !carried_writes.contains(&write) {
+                            carried_writes.push(write);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut sharded: Vec<Session<Variable, Version>> = (1..)
+        .zip(histories)
+        .map(|(session_id, session)| {
+            let end = (0..)
+                .zip(session)
+                .filter(|(session_height, _)| {
+                    inside.contains(&TransactionId {
+                        session_id,
+                        session_height: *session_height,
+                    })
+                })
+                .map(|(session_height, _)| session_height + 1)
+                .max()
+                .unwrap_or(0);
+            (0..end)
+                .zip(session)
+                .map(|(session_height, transaction)| {
+                    let mut transaction = transaction.clone();
+                    if !inside.contains(&TransactionId {
+                        session_id,
+                        session_height,
+                    }) {
+                        transaction.events.clear();
+                    }
+                    transaction
+                })
+                .collect()
+        })
+        .collect();
+    for (writer, events) in carried {
+        let original = transaction(histories, writer);
+        let mut seed = Transaction::committed(events);
+        seed.committed = original.committed;
+        seed.unknown = original.unknown;
+        sharded.push(Vec::from([seed]));
+    }
+    sharded
+}
+
+/// Checks the shards of the windows of `size` transactions of `order`, overlapping by `overlap`
+/// transactions, and returns their violations. See the module documentation.
+///
+/// With the `parallel` feature, the shards are checked in parallel.
+///
+/// # Panics
+///
+/// Panics if `overlap` is not smaller than `size`.
+#[must_use]
+pub fn check_sharded<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    level: Consistency,
+    order: &[TransactionId],
+    size: usize,
+    overlap: usize,
+) -> Vec<ShardViolation<Variable, Version>>
+where
+    Variable: Clone + Eq + Ord + Hash + Send + Sync,
+    Version: Clone + Eq + Hash + Send + Sync,
+{
+    let windows = windows(order.len(), size, overlap);
+    let check_window = |window: Range<usize>| {
+        check(&shard(histories, &order[window.clone()]), level)
+            .err()
+            .map(|error| ShardViolation { window, error })
+    };
+    #[cfg(feature = "parallel")]
+    return windows.into_par_iter().filter_map(check_window).collect();
+    #[cfg(not(feature = "parallel"))]
+    windows.into_iter().filter_map(check_window).collect()
+}
+
+fn transaction<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    id: TransactionId,
+) -> &Transaction<Variable, Version> {
+    &histories[usize::try_from(id.session_id - 1).expect("session id fits in usize")]
+        [usize::try_from(id.session_height).expect("session height fits in usize")]
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn test_check_sharded() {
+        assert_eq!(windows(5, 2, 1), vec![0..2, 1..3, 2..4, 3..5]);
+        assert_eq!(windows(3, 4, 1), vec![0..3]);
+
+        // s2.t1 reads x=1 after s2.t0 reads x=2 from s1.t1, a causal violation within a window
+        let histories = vec![
+            vec![
+                Transaction::committed(vec![Event::write("x", 1)]),
+                Transaction::committed(vec![Event::read("x", 1), Event::write("x", 2)]),
+            ],
+            vec![
+                Transaction::committed(vec![Event::read("x", 2)]),
+                Transaction::committed(vec![Event::read("x", 1)]),
+            ],
+        ];
+        let order = round_order(&histories);
+        let id = |session_id, session_height| TransactionId {
+            session_id,
+            session_height,
+        };
+        assert_eq!(order, vec![id(1, 0), id(2, 0), id(1, 1), id(2, 1)]);
+
+        // s1.t0, the writer of x=1, is carried forward as a new session
+        let sharded = shard(&histories, &order[2..]);
+        assert_eq!(sharded.len(), 3);
+        assert!(sharded[0][0].events.is_empty());
+        assert!(sharded[1][0].events.is_empty());
+        assert_eq!(sharded[2].len(), 1);
+        assert_eq!(sharded[2][0].events, vec![Event::write("x", 1)]);
+        assert!(sharded[2][0].committed);
+
+        assert_eq!(
+            check_sharded(&histories, Consistency::Causal, &order, 4, 0).len(),
+            1
+        );
+        // the windows of 2 transactions miss the violation, but not the ones of 3
+        assert!(check_sharded(&histories, Consistency::Causal, &order, 2, 0).is_empty());
+        let violations = check_sharded(&histories, Consistency::Causal, &order, 3, 2);
+        assert_eq!(
+            violations
+                .iter()
+                .map(|v| v.window.clone())
+                .collect::<Vec<_>>(),
+            vec![1..4]
+        );
+
+        let later = vec![vec![Transaction::committed(vec![Event::read("x", 2)])]];
+        let concatenated = concat(&histories, &later);
+        assert_eq!(concatenated[0].len(), 3);
+        let timed = concat(
+            &[vec![
+                Transaction::<&str, u64>::committed(vec![]).with_meta("time", "2"),
+                Transaction::committed(vec![]).with_meta("time", "3"),
+            ]],
+            &[
+                vec![],
+                vec![Transaction::committed(vec![]).with_meta("time", "1")],
+            ],
+        );
+        assert_eq!(
+            order_by_meta(&timed, "time"),
+            vec![id(2, 0), id(1, 0), id(1, 1)]
+        );
+    }
+}
@@ -1,6 +1,6 @@
 //! Checks if a valid history maintains atomic read.
 
-use crate::history::atomic::types::AtomicTransactionHistory;
+use crate::history::atomic::types::{AtomicTransactionHistory, EdgeKind};
 use crate::history::atomic::AtomicTransactionPO;
 use crate::history::non_atomic::types::Session;
 use crate::solver::error::Error;
@@ -11,7 +11,7 @@ use ::core::hash::Hash;
 /// checks if a valid history maintains atomic read
 /// # Errors
 ///
-/// Returns [`Error::Invalid`] if the history does not maintain atomic read.
+/// Returns [`Error::Cycle`] if the history does not maintain atomic read.
 pub fn check_atomic_read<Variable, Version>(
     histories: &[Session<Variable, Version>],
 ) -> Result<AtomicTransactionPO<Variable>, Error<Variable, Version>>
@@ -22,28 +22,63 @@ where
     let mut atomic_history =
         AtomicTransactionPO::from(AtomicTransactionHistory::try_from(histories)?);
 
-    atomic_history.vis_includes(&atomic_history.get_wr());
+    atomic_history.vis_includes_wr();
 
     let ww_rel = atomic_history.causal_ww();
 
-    for ww_x in ww_rel.values() {
-        atomic_history.vis_includes(ww_x);
+    for (x, ww_x) in &ww_rel {
+        atomic_history.vis_includes_from(ww_x, &EdgeKind::WriteWrite(x.clone()));
     }
 
-    atomic_history
-        .has_valid_visibility()
-        .then_some(atomic_history)
-        .ok_or(Error::Invalid(Consistency::AtomicRead))
+    if atomic_history.has_valid_visibility() {
+        Ok(atomic_history)
+    } else {
+        Err(atomic_history
+            .vis_cycle()
+            .map_or(Error::Invalid(Consistency::AtomicRead), |cycle| {
+                Error::Cycle {
+                    level: Consistency::AtomicRead,
+                    cycle,
+                }
+            }))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::history::non_atomic::types::{Event, Transaction};
+    use crate::solver::causal::check_causal_read;
 
     use super::*;
 
     #[test]
     fn test_atomic_read() {
+        // fractured read: observes the write on x, but not the write on y
+        let histories = vec![
+            vec![Transaction::committed(vec![
+                Event::write("x", 1),
+                Event::write("y", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read("x", 1),
+                Event::read_empty("y"),
+            ])],
+        ];
+
+        let result = check_atomic_read(&histories);
+
+        assert!(matches!(
+            result,
+            Err(Error::Cycle {
+                level: Consistency::AtomicRead,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_non_monotonic_reads() {
+        // the second reader does not observe `x <= 2`, as visibility is not transitive
         let histories = vec![
             vec![
                 Transaction::committed(vec![Event::write("x", 1)]),
@@ -55,11 +90,14 @@ mod tests {
             ],
         ];
 
-        let result = check_atomic_read(&histories);
+        assert!(check_atomic_read(&histories).is_ok());
 
         assert!(matches!(
-            result,
-            Err(Error::Invalid(Consistency::AtomicRead))
+            check_causal_read(&histories),
+            Err(Error::Cycle {
+                level: Consistency::Causal,
+                ..
+            })
         ));
     }
 }
@@ -0,0 +1,153 @@
+//! Searches for the lexicographically smallest linearization, so that witnesses do not depend on
+//! the search that found them.
+//!
+//! The search is the depth-first search of [`LinearizationStepper`], except that it tries the
+//! vertices that can be placed next in increasing order. The first linearization it finds is then
+//! the smallest one. Sets of choices already explored have no linearization, so skipping them does
+//! not skip a smaller one.
+//!
+//! [`LinearizationStepper`]: crate::solver::constrained_linearization::LinearizationStepper
+
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+
+use hashbrown::{HashMap, HashSet};
+
+use crate::solver::constrained_linearization::ConstrainedLinearizationSolver;
+
+/// A node of the search: its choices in increasing order, the next one to try, and the one placed
+/// with the choices it made available.
+struct Frame<Vertex> {
+    choices: Vec<Vertex>,
+    i_choice: usize,
+    placed: Option<(Vertex, Vec<Vertex>)>,
+}
+
+/// Returns the lexicographically smallest linearization, if there is one.
+pub fn canonical_linearization<S>(solver: &mut S) -> Option<Vec<S::Vertex>>
+where
+    S: ConstrainedLinearizationSolver,
+{
+    let mut active_parent: HashMap<S::Vertex, usize> = HashMap::new();
+    for u in solver.vertices() {
+        active_parent.entry(u.clone()).or_insert(0);
+        for v in solver.children_of(&u).into_iter().flatten() {
+            *active_parent.entry(v).or_insert(0) += 1;
+        }
+    }
+    let mut choices: BTreeSet<S::Vertex> = active_parent
+        .iter()
+        .filter(|(_, n)| **n == 0)
+        .map(|(u, _)| u.clone())
+        .collect();
+
+    let mut linearization: Vec<S::Vertex> = Vec::new();
+    let mut seen: HashSet<BTreeSet<S::Vertex>> = HashSet::new();
+    let mut frames: Vec<Frame<S::Vertex>> = Vec::new();
+    let mut entering = true;
+
+    loop {
+        if entering {
+            entering = false;
+            if choices.is_empty() {
+                // the vertices left, if any, are on a cycle
+                if linearization.len() == active_parent.len() {
+                    return Some(linearization);
+                }
+            } else if seen.insert(choices.clone()) {
+                frames.push(Frame {
+                    choices: choices.iter().cloned().collect(),
+                    i_choice: 0,
+                    placed: None,
+                });
+            }
+        }
+
+        let frame = frames.last_mut()?;
+
+        if let Some((u, available)) = frame.placed.take() {
+            solver.backtrack_book_keeping(&linearization);
+            linearization.pop();
+            for v in solver.children_of(&u).into_iter().flatten() {
+                if let Some(n) = active_parent.get_mut(&v) {
+                    *n += 1;
+                }
+            }
+            for v in &available {
+                choices.remove(v);
+            }
+            choices.insert(u);
+        }
+
+        let Some(i) = (frame.i_choice..frame.choices.len())
+            .find(|i| solver.allow_next(&linearization, &frame.choices[*i]))
+        else {
+            frames.pop();
+            continue;
+        };
+        frame.i_choice = i + 1;
+        let u = frame.choices[i].clone();
+
+        choices.remove(&u);
+        let mut available = Vec::new();
+        for v in solver.children_of(&u).into_iter().flatten() {
+            if let Some(n) = active_parent.get_mut(&v) {
+                *n -= 1;
+                if *n == 0 {
+                    choices.insert(v.clone());
+                    available.push(v);
+                }
+            }
+        }
+        linearization.push(u.clone());
+        solver.forward_book_keeping(&linearization);
+        frame.placed = Some((u, available));
+        entering = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::history::atomic::types::TransactionId;
+    use crate::history::non_atomic::types::{Event, Transaction};
+    use crate::solver::causal::check_causal_read;
+    use crate::solver::serializable::SerializabilitySolver;
+
+    #[test]
+    fn test_canonical_linearization() {
+        // (2, 0) must precede (1, 0), which overwrites the version it reads
+        let histories = vec![
+            vec![Transaction::committed(vec![Event::write("x", 1)])],
+            vec![Transaction::committed(vec![Event::read_empty("x")])],
+            vec![Transaction::committed(vec![Event::write("y", 1)])],
+        ];
+        let po = check_causal_read(&histories).unwrap();
+        let id = |session_id| TransactionId {
+            session_id,
+            session_height: 0,
+        };
+
+        assert_eq!(
+            canonical_linearization(&mut SerializabilitySolver::from(po)),
+            Some(vec![id(2), id(1), id(3)])
+        );
+        // write skew has no linearization
+        let histories = vec![
+            vec![Transaction::committed(vec![
+                Event::read_empty("x"),
+                Event::read_empty("y"),
+                Event::write("x", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read_empty("x"),
+                Event::read_empty("y"),
+                Event::write("y", 1),
+            ])],
+        ];
+        let po = check_causal_read(&histories).unwrap();
+        assert!(canonical_linearization(&mut SerializabilitySolver::from(po)).is_none());
+    }
+}
@@ -1,16 +1,20 @@
 //! Checks if a valid history maintains causal consistency.
 
-use crate::history::atomic::types::AtomicTransactionHistory;
+use ::core::hash::Hash;
+use hashbrown::HashMap;
+
+use crate::graph::digraph::DiGraph;
+use crate::history::atomic::types::{
+    AtomicTransactionHistory, EdgeKind, TransactionId, WriteOrderReason,
+};
 use crate::history::atomic::AtomicTransactionPO;
 use crate::history::non_atomic::types::Session;
 use crate::solver::error::Error;
 use crate::Consistency;
 
-use ::core::hash::Hash;
-
 /// # Errors
 ///
-/// Returns [`Error::Invalid`] if the history does not maintain causal consistency.
+/// Returns [`Error::Cycle`] if the history does not maintain causal consistency.
 pub fn check_causal_read<Variable, Version>(
     histories: &[Session<Variable, Version>],
 ) -> Result<AtomicTransactionPO<Variable>, Error<Variable, Version>>
@@ -21,37 +25,106 @@ where
     let mut atomic_history =
         AtomicTransactionPO::from(AtomicTransactionHistory::try_from(histories)?);
 
-    atomic_history.vis_includes(&atomic_history.get_wr());
+    atomic_history.vis_includes_wr();
+
+    saturate_causal(atomic_history)
+}
 
+/// Closes the visibility relation of a partial order under causal consistency, such as one
+/// returned by [`check_causal_read`] and then extended with
+/// [`AtomicTransactionPO::vis_includes_external`].
+///
+/// # Errors
+///
+/// Returns [`Error::Cycle`] if the closed relation is cyclic.
+pub fn saturate_causal<Variable, Version>(
+    mut atomic_history: AtomicTransactionPO<Variable>,
+) -> Result<AtomicTransactionPO<Variable>, Error<Variable, Version>>
+where
+    Variable: Eq + Hash + Clone,
+{
+    #[cfg(feature = "tracing")]
+    let span = tracing::debug_span!(
+        "saturation",
+        transactions = atomic_history.history.0.len(),
+        iterations = tracing::field::Empty,
+    );
+    #[cfg(feature = "tracing")]
+    let _entered = span.enter();
+    #[cfg(feature = "tracing")]
+    let mut iterations: u64 = 0;
     loop {
+        #[cfg(feature = "tracing")]
+        {
+            iterations += 1;
+        }
         atomic_history.vis_is_trans();
 
         let ww_rel = atomic_history.causal_ww();
         let mut changed = false;
 
-        for ww_x in ww_rel.values() {
-            changed |= atomic_history.vis_includes(ww_x);
+        for (x, ww_x) in &ww_rel {
+            changed |= atomic_history.vis_includes_from(ww_x, &EdgeKind::WriteWrite(x.clone()));
         }
 
         if !changed {
             break;
         }
     }
+    #[cfg(feature = "tracing")]
+    span.record("iterations", iterations);
+
+    if atomic_history.has_valid_visibility() {
+        Ok(atomic_history)
+    } else {
+        Err(atomic_history
+            .vis_cycle()
+            .map_or(Error::Invalid(Consistency::Causal), |cycle| Error::Cycle {
+                level: Consistency::Causal,
+                cycle,
+            }))
+    }
+}
 
-    atomic_history
-        .has_valid_visibility()
-        .then_some(atomic_history)
-        .ok_or(Error::Invalid(Consistency::Causal))
+/// The order of the writes of a variable that every causal version order extends.
+#[derive(Debug, Clone, Default)]
+pub struct VersionOrder {
+    /// An edge from an earlier write to a later one
+    pub order: DiGraph<TransactionId>,
+    pub reasons: HashMap<(TransactionId, TransactionId), WriteOrderReason>,
+}
+
+/// Infers the write order of each variable from the saturated causal partial order.
+///
+/// # Errors
+///
+/// Returns the error of [`check_causal_read`].
+pub fn infer_version_order<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+) -> Result<HashMap<Variable, VersionOrder>, Error<Variable, Version>>
+where
+    Variable: Eq + Hash + Clone,
+    Version: Eq + Hash + Clone,
+{
+    Ok(check_causal_read(histories)?
+        .causal_ww_reasons()
+        .into_iter()
+        .map(|(x, reasons)| {
+            let mut order: DiGraph<TransactionId> = DiGraph::default();
+            for (source, target) in reasons.keys() {
+                order.add_edge(*source, *target);
+            }
+            (x, VersionOrder { order, reasons })
+        })
+        .collect())
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{
-        history::non_atomic::types::{Event, Transaction},
-        solver::atomic_read::check_atomic_read,
-    };
-
     use super::*;
+    use crate::history::atomic::types::{Edge, TransactionId};
+    use crate::history::non_atomic::types::{Event, Transaction};
+    use crate::solver::atomic_read::check_atomic_read;
 
     #[test]
     fn test_atomic_read() {
@@ -90,7 +163,159 @@ mod tests {
 
         assert!(matches!(
             check_causal_read(&histories),
-            Err(Error::Invalid(Consistency::Causal))
+            Err(Error::Cycle {
+                level: Consistency::Causal,
+                ..
+            })
         ));
     }
+
+    #[test]
+    fn test_concurrent_writes() {
+        // t1 and t2 are concurrent, t3 observes both
+        let histories = vec![
+            vec![Transaction::committed(vec![
+                Event::read_empty("y"),
+                Event::write("x", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read_empty("x"),
+                Event::write("y", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read("y", 1),
+                Event::read("x", 1),
+            ])],
+        ];
+
+        let result = check_causal_read(&histories);
+
+        assert!(result.is_ok(), "result: {result:?}");
+    }
+
+    #[test]
+    fn test_cycle_provenance() {
+        // t3 depends on t1, but t4 observes t3 and still reads `a` from t1
+        let histories = vec![
+            vec![Transaction::committed(vec![
+                Event::write("x", 1),
+                Event::write("a", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read("x", 1),
+                Event::write("y", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read("y", 1),
+                Event::write("a", 2),
+                Event::write("z", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read("z", 1),
+                Event::read("a", 1),
+            ])],
+        ];
+
+        let Err(Error::Cycle { level, mut cycle }) = check_causal_read(&histories) else {
+            panic!("expected a causal cycle");
+        };
+        assert_eq!(level, Consistency::Causal);
+
+        let t = |session_id| TransactionId {
+            session_id,
+            session_height: 0,
+        };
+        let start = cycle.iter().position(|edge| edge.source == t(1)).unwrap();
+        cycle.rotate_left(start);
+        assert_eq!(
+            cycle,
+            vec![
+                Edge {
+                    source: t(1),
+                    target: t(2),
+                    kind: EdgeKind::WriteRead("x"),
+                },
+                Edge {
+                    source: t(2),
+                    target: t(3),
+                    kind: EdgeKind::WriteRead("y"),
+                },
+                Edge {
+                    source: t(3),
+                    target: t(1),
+                    kind: EdgeKind::WriteWrite("a"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_partial_session_order() {
+        // a pipelined client: the read is not ordered after the write of its session
+        let histories = vec![vec![
+            Transaction::committed(vec![Event::write("x", 1)]),
+            Transaction::committed(vec![Event::read_empty("x")]).with_predecessors(vec![]),
+            Transaction::committed(vec![Event::read("x", 1)]).with_predecessors(vec![0]),
+        ]];
+
+        let po = check_causal_read(&histories).unwrap();
+        let t = |session_height| TransactionId {
+            session_id: 1,
+            session_height,
+        };
+        assert!(!po.session_order.has_edge(&t(0), &t(1)));
+        assert!(po.session_order.has_edge(&t(0), &t(2)));
+
+        // with the total session order, the second transaction misses the first one's write
+        let mut histories = histories;
+        histories[0][1].predecessors = None;
+        assert!(check_causal_read(&histories).is_err());
+    }
+
+    #[test]
+    fn test_infer_version_order() {
+        // (1, 0) is visible to (2, 0), which reads the write of (3, 0)
+        let histories = vec![
+            vec![Transaction::committed(vec![Event::write("x", 1)])],
+            vec![Transaction::committed(vec![
+                Event::read("x", 1),
+                Event::read("x", 2),
+            ])],
+            vec![Transaction::committed(vec![Event::write("x", 2)])],
+        ];
+        assert!(infer_version_order(&histories).is_err());
+
+        let histories = vec![
+            vec![Transaction::committed(vec![
+                Event::write("x", 1),
+                Event::write("y", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read("y", 1),
+                Event::read("x", 2),
+            ])],
+            vec![Transaction::committed(vec![Event::write("x", 2)])],
+        ];
+        let version_order = infer_version_order(&histories).unwrap();
+        let x = &version_order["x"];
+        let first = TransactionId {
+            session_id: 1,
+            session_height: 0,
+        };
+        let second = TransactionId {
+            session_id: 3,
+            session_height: 0,
+        };
+        assert!(x.order.has_edge(&first, &second));
+        assert_eq!(
+            x.reasons[&(first, second)],
+            WriteOrderReason::VisibleToReader {
+                reader: TransactionId {
+                    session_id: 2,
+                    session_height: 0,
+                },
+            }
+        );
+        assert_eq!(x.reasons.len(), 1);
+    }
 }
@@ -1,13 +1,16 @@
 //! Checks if a valid history is a committed read history.
 
+use ::alloc::vec::Vec;
 use ::core::hash::Hash;
 use ::hashbrown::HashMap;
 
 use crate::graph::digraph::DiGraph;
-use crate::history::atomic::types::TransactionId;
+use crate::history::atomic::types::{Edge, EdgeKind, TransactionId};
 use crate::history::non_atomic::error::Error as NonAtomicError;
 use crate::history::non_atomic::types::{Event, EventId, Session};
-use crate::history::non_atomic::{get_all_writes, get_committed_writes, is_valid_history};
+use crate::history::non_atomic::{
+    get_all_writes, get_committed_writes, is_valid_history, session_order_edges,
+};
 use crate::solver::error::Error;
 use crate::Consistency;
 
@@ -19,38 +22,49 @@ use crate::Consistency;
 pub fn check_committed_read<Variable, Version>(
     histories: &[Session<Variable, Version>],
 ) -> Result<(), Error<Variable, Version>>
+where
+    Variable: Eq + Hash + Clone,
+    Version: Eq + Hash + Clone,
+{
+    let mut committed_order: DiGraph<TransactionId> = DiGraph::default();
+
+    for edge in committed_order_edges(histories)? {
+        committed_order.add_edge(edge.source, edge.target);
+    }
+
+    committed_order = committed_order.closure();
+
+    committed_order
+        .is_acyclic()
+        .then_some(())
+        .ok_or(Error::Invalid(Consistency::CommittedRead))
+}
+
+/// Returns the edges that the commit order of a valid history must contain, with their origin.
+///
+/// # Errors
+///
+/// Returns `Error::NonAtomic` if the history is not valid.
+pub fn committed_order_edges<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+) -> Result<Vec<Edge<Variable>>, Error<Variable, Version>>
 where
     Variable: Eq + Hash + Clone,
     Version: Eq + Hash + Clone,
 {
     is_valid_history(histories)?;
 
-    let mut committed_order: DiGraph<TransactionId> = DiGraph::default();
+    let mut committed_order: Vec<Edge<Variable>> = Vec::new();
 
     let init_transaction = TransactionId::root();
 
-    for (i_node, session) in (1..).zip(histories.iter()) {
-        // Add the edge from the initial transaction to the first transaction of the session
-        committed_order.add_edge(
-            init_transaction,
-            TransactionId {
-                session_id: i_node,
-                session_height: 0,
-            },
-        );
-        for i_transaction in (0..).take(session.len()).skip(1) {
-            // Add the edge from the previous transaction to the current transaction
-            committed_order.add_edge(
-                TransactionId {
-                    session_id: i_node,
-                    session_height: i_transaction - 1,
-                },
-                TransactionId {
-                    session_id: i_node,
-                    session_height: i_transaction,
-                },
-            );
-        }
+    // the session order, from the initial transaction to the first transactions of the sessions
+    for (source, target) in session_order_edges(histories)? {
+        committed_order.push(Edge {
+            source,
+            target,
+            kind: EdgeKind::SessionOrder,
+        });
     }
 
     let all_writes = get_all_writes(histories)?;
@@ -75,6 +89,11 @@ where
                                 id: current_event_id,
                             })?;
 
+                    if write_event_id.transaction_id() == current_event_id.transaction_id() {
+                        // local reads are checked by `is_valid_history`
+                        continue;
+                    }
+
                     if let Some((committed_version, committed_event_id)) =
                         committed_writes.get(&(write_event_id.transaction_id(), variable.clone()))
                     {
@@ -91,7 +110,8 @@ where
                             }
                             .into());
                         }
-                    } else {
+                    } else if write_event_id.transaction_id() != init_transaction {
+                        // initial versions are committed by the root transaction
                         return Err(NonAtomicError::UncommittedWrite {
                             read_event: event.clone(),
                             read_event_id: current_event_id,
@@ -100,42 +120,39 @@ where
                         .into());
                     }
 
-                    // if read from another transaction
-                    if write_event_id.transaction_id() != current_event_id.transaction_id() {
-                        // there is a previous read
-                        if let Some(&prevision_event_id) = local_reads.get(variable) {
-                            // t1: prevision_event_id.transaction_id()
-                            // t2: write_event_id.transaction_id()
-                            //  t1─────────>r1
-                            //  │    wr_x    │
-                            //  │vis       po│
-                            //  v    wr_x    v
-                            //  t2 ────────>r2
-                            committed_order.add_edge(
-                                prevision_event_id.transaction_id(),
-                                write_event_id.transaction_id(),
-                            );
-                        }
+                    // there is a previous read
+                    if let Some(&prevision_event_id) = local_reads
+                        .get(variable)
+                        .filter(|id| id.transaction_id() != write_event_id.transaction_id())
+                    {
+                        // t1: prevision_event_id.transaction_id()
+                        // t2: write_event_id.transaction_id()
+                        //  t1─────────>r1
+                        //  │    wr_x    │
+                        //  │vis       po│
+                        //  v    wr_x    v
+                        //  t2 ────────>r2
+                        committed_order.push(Edge {
+                            source: prevision_event_id.transaction_id(),
+                            target: write_event_id.transaction_id(),
+                            kind: EdgeKind::WriteWrite(variable.clone()),
+                        });
+                    }
 
-                        local_reads.insert(variable.clone(), *write_event_id);
+                    local_reads.insert(variable.clone(), *write_event_id);
 
-                        // add wr_x edge
-                        committed_order.add_edge(
-                            write_event_id.transaction_id(),
-                            current_event_id.transaction_id(),
-                        );
-                    }
+                    // add wr_x edge
+                    committed_order.push(Edge {
+                        source: write_event_id.transaction_id(),
+                        target: current_event_id.transaction_id(),
+                        kind: EdgeKind::WriteRead(variable.clone()),
+                    });
                 }
             }
         }
     }
 
-    committed_order = committed_order.closure();
-
-    committed_order
-        .is_acyclic()
-        .then_some(())
-        .ok_or(Error::Invalid(Consistency::CommittedRead))
+    Ok(committed_order)
 }
 
 #[cfg(test)]
@@ -1,8 +1,14 @@
 use alloc::collections::btree_set::BTreeSet;
 use alloc::collections::vec_deque::VecDeque;
+#[cfg(feature = "parallel")]
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::fmt::Debug;
 use core::hash::Hash;
+#[cfg(feature = "parallel")]
+use core::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "parallel")]
+use std::sync::Mutex;
 
 use hashbrown::{HashMap, HashSet};
 
@@ -20,79 +26,295 @@ pub trait ConstrainedLinearizationSolver {
     fn forward_book_keeping(&mut self, linearization: &[Self::Vertex]);
     fn backtrack_book_keeping(&mut self, linearization: &[Self::Vertex]);
 
+    /// Returns a stepper over the search for a linearization.
+    fn stepper(&mut self) -> LinearizationStepper<'_, Self>
+    where
+        Self: Sized,
+    {
+        LinearizationStepper::new(self)
+    }
+
+    fn get_linearization(&mut self) -> Option<Vec<Self::Vertex>>
+    where
+        Self: Sized,
+    {
+        self.stepper().run()
+    }
+
+    /// Searches for a linearization and writes it to `linearization`. Returns true if there is one.
+    ///
+    /// The search state is kept by the [`LinearizationStepper`], which always starts from the
+    /// root, so the given choices, parent counts and explored sets are not used.
+    #[deprecated(note = "use `get_linearization`, or `stepper` to follow the search")]
     fn do_dfs(
         &mut self,
-        non_det_choices: &mut VecDeque<Self::Vertex>,
-        active_parent: &mut HashMap<Self::Vertex, usize>,
+        _non_det_choices: &mut VecDeque<Self::Vertex>,
+        _active_parent: &mut HashMap<Self::Vertex, usize>,
         linearization: &mut Vec<Self::Vertex>,
-        seen: &mut HashSet<BTreeSet<Self::Vertex>>,
-    ) -> bool {
-        // println!("explored {}", seen.len());
-        if !seen.insert(non_det_choices.iter().cloned().collect()) {
-            // seen is not modified
-            // non-det choices are already explored
-            false
-        } else if non_det_choices.is_empty() {
-            true
-        } else {
-            let curr_non_det_choices = non_det_choices.len();
-            for _ in 0..curr_non_det_choices {
-                if let Some(u) = non_det_choices.pop_front() {
-                    if self.allow_next(linearization, &u) {
-                        // access it again
-                        if let Some(vs) = self.children_of(&u) {
-                            for v in vs {
-                                let entry = active_parent
-                                    .get_mut(&v)
-                                    .expect("all vertices are expected in active parent");
-                                *entry -= 1;
-                                if *entry == 0 {
-                                    non_det_choices.push_back(v);
-                                }
-                            }
-                        }
-
-                        linearization.push(u.clone());
-
-                        self.forward_book_keeping(linearization);
-
-                        if self.do_dfs(non_det_choices, active_parent, linearization, seen) {
-                            return true;
-                        }
-
-                        self.backtrack_book_keeping(linearization);
-
-                        linearization.pop();
-
-                        if let Some(vs) = self.children_of(&u) {
-                            for v in vs {
-                                let entry = active_parent
-                                    .get_mut(&v)
-                                    .expect("all vertices are expected in active parent");
-                                *entry += 1;
-                            }
-                        }
-                        non_det_choices.drain(curr_non_det_choices - 1..);
-                    }
-                    non_det_choices.push_back(u);
-                }
+        _seen: &mut HashSet<BTreeSet<Self::Vertex>>,
+    ) -> bool
+    where
+        Self: Sized,
+    {
+        let Some(found) = LinearizationStepper::new(self).run() else {
+            return false;
+        };
+        *linearization = found;
+        true
+    }
+
+    /// Searches for a linearization on all the threads of the rayon pool.
+    /// See [`crate::solver::parallel`].
+    #[cfg(feature = "parallel")]
+    fn get_linearization_parallel(&self) -> Option<Vec<Self::Vertex>>
+    where
+        Self: Sized + Clone + Send + Sync,
+        Self::Vertex: Send + Sync,
+    {
+        crate::solver::parallel::get_linearization(self)
+    }
+}
+
+/// Sets of choices explored by one search, forgetting the least recently used ones past a
+/// capacity.
+///
+/// The sets are kept in two generations. A set found in the old generation moves to the current
+/// one. Once the current generation holds half the capacity, the old one is evicted and the current
+/// one becomes old. Forgetting a set only costs exploring it again.
+#[derive(Debug)]
+struct Memo<Vertex> {
+    current: HashSet<BTreeSet<Vertex>>,
+    old: HashSet<BTreeSet<Vertex>>,
+    capacity: Option<usize>,
+    evictions: u64,
+}
+
+impl<Vertex> Memo<Vertex>
+where
+    Vertex: Hash + Ord + Eq,
+{
+    fn new() -> Self {
+        Self {
+            current: HashSet::new(),
+            old: HashSet::new(),
+            capacity: None,
+            evictions: 0,
+        }
+    }
+
+    /// Returns true if the set of choices is not remembered.
+    fn insert(&mut self, choices: BTreeSet<Vertex>) -> bool {
+        if self.current.contains(&choices) {
+            return false;
+        }
+        let fresh = !self.old.remove(&choices);
+        if let Some(capacity) = self.capacity {
+            if self.current.len() >= (capacity / 2).max(1) {
+                self.evictions += self.old.len() as u64;
+                self.old = core::mem::take(&mut self.current);
             }
-            false
         }
+        self.current.insert(choices);
+        fresh
+    }
+}
+
+/// The sets of choices explored so far, owned by one search or shared between parallel searches.
+#[derive(Debug)]
+enum Explored<Vertex> {
+    Local(Memo<Vertex>),
+    #[cfg(feature = "parallel")]
+    Shared {
+        seen: Arc<Mutex<HashSet<BTreeSet<Vertex>>>>,
+        /// Set once any search finds a linearization
+        stop: Arc<AtomicBool>,
+    },
+}
+
+impl<Vertex> Explored<Vertex>
+where
+    Vertex: Hash + Ord + Eq,
+{
+    /// Returns true if the set of choices was not explored yet.
+    fn insert(&mut self, choices: BTreeSet<Vertex>) -> bool {
+        match self {
+            Self::Local(memo) => memo.insert(choices),
+            #[cfg(feature = "parallel")]
+            Self::Shared { seen, .. } => seen
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+                .insert(choices),
+        }
+    }
+
+    /// Returns the number of sets of choices forgotten so far.
+    const fn evictions(&self) -> u64 {
+        match self {
+            Self::Local(memo) => memo.evictions,
+            #[cfg(feature = "parallel")]
+            Self::Shared { .. } => 0,
+        }
+    }
+
+    // only const without the parallel feature
+    #[allow(clippy::missing_const_for_fn)]
+    fn is_stopped(&self) -> bool {
+        match self {
+            Self::Local(_) => false,
+            #[cfg(feature = "parallel")]
+            Self::Shared { stop, .. } => stop.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Why the search stepped back from a vertex.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BacktrackReason {
+    /// The set of choices after the vertex was already explored
+    AlreadyExplored,
+    /// No choice after the vertex leads to a linearization
+    DeadEnd,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepEvent<Vertex> {
+    /// The vertex is appended to the linearization
+    Placed(Vertex),
+    /// The vertex is not allowed next, so it is skipped
+    Rejected(Vertex),
+    /// The vertex is removed from the end of the linearization
+    Backtracked {
+        vertex: Vertex,
+        reason: BacktrackReason,
+    },
+    /// The linearization is complete
+    Found,
+    /// There is no linearization, or another search of a parallel search found one
+    Exhausted,
+}
+
+#[derive(Debug, Clone)]
+pub struct LinearizationStep<Vertex> {
+    pub event: StepEvent<Vertex>,
+    /// The linearization after the step
+    pub prefix: Vec<Vertex>,
+    /// The vertices that can be placed next, after the step
+    pub frontier: Vec<Vertex>,
+}
+
+/// Counters of a linearization search.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SearchStats {
+    /// Vertices placed, that is, nodes of the search tree expanded
+    pub placed: u64,
+    /// Vertices not allowed next when they were tried
+    pub rejected: u64,
+    /// Vertices removed from the end of the linearization
+    pub backtracks: u64,
+    /// Sets of choices found already explored
+    pub memo_hits: u64,
+    /// Explored sets of choices forgotten to stay within the memo capacity
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub memo_evictions: u64,
+}
+
+impl SearchStats {
+    /// Records the counters as the fields of the same names of `span`.
+    #[cfg(feature = "tracing")]
+    pub(crate) fn record(&self, span: &tracing::Span) {
+        span.record("placed", self.placed);
+        span.record("rejected", self.rejected);
+        span.record("backtracks", self.backtracks);
+        span.record("memo_hits", self.memo_hits);
+        span.record("memo_evictions", self.memo_evictions);
+    }
+}
+
+/// The solver of a search, borrowed or owned by it.
+#[derive(Debug)]
+enum SolverRef<'a, S> {
+    Borrowed(&'a mut S),
+    Owned(S),
+}
+
+impl<S> core::ops::Deref for SolverRef<'_, S> {
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        match self {
+            Self::Borrowed(solver) => solver,
+            Self::Owned(solver) => solver,
+        }
+    }
+}
+
+impl<S> core::ops::DerefMut for SolverRef<'_, S> {
+    fn deref_mut(&mut self) -> &mut S {
+        match self {
+            Self::Borrowed(solver) => solver,
+            Self::Owned(solver) => solver,
+        }
+    }
+}
+
+/// A node of the depth-first search: the choices at it and the one being explored.
+#[derive(Debug)]
+struct Frame<Vertex> {
+    n_choice: usize,
+    i_choice: usize,
+    placed: Option<Vertex>,
+}
+
+/// Resumable depth-first search for a linearization.
+///
+/// The search places the vertices whose parents are all placed, one at a time, and backtracks when
+/// a choice leads nowhere. Sets of choices that were already explored are not explored again,
+/// unless the memo of explored sets is bounded and forgot them.
+/// Each step of the iterator is one placement, rejection or backtrack.
+#[derive(Debug)]
+pub struct LinearizationStepper<'a, S>
+where
+    S: ConstrainedLinearizationSolver,
+{
+    solver: SolverRef<'a, S>,
+    non_det_choices: VecDeque<S::Vertex>,
+    active_parent: HashMap<S::Vertex, usize>,
+    linearization: Vec<S::Vertex>,
+    seen: Explored<S::Vertex>,
+    frames: Vec<Frame<S::Vertex>>,
+    /// The top frame just placed a vertex, and the search has to enter the next node
+    entering: bool,
+    reason: BacktrackReason,
+    found: bool,
+    done: bool,
+    stats: SearchStats,
+}
+
+impl<'a, S> LinearizationStepper<'a, S>
+where
+    S: ConstrainedLinearizationSolver,
+{
+    pub fn new(solver: &'a mut S) -> Self {
+        Self::start(SolverRef::Borrowed(solver))
     }
 
-    fn get_linearization(&mut self) -> Option<Vec<Self::Vertex>> {
-        let mut non_det_choices: VecDeque<Self::Vertex> = VecDeque::default();
-        let mut active_parent: HashMap<Self::Vertex, usize> = HashMap::default();
-        let mut linearization: Vec<Self::Vertex> = Vec::default();
-        let mut seen: HashSet<BTreeSet<Self::Vertex>> = HashSet::default();
+    /// Starts a search that owns its solver, so that it can be kept and resumed, such as with
+    /// [`LinearizationStepper::run_budget`], independently of the solver.
+    pub fn owned(solver: S) -> Self {
+        Self::start(SolverRef::Owned(solver))
+    }
+
+    fn start(solver: SolverRef<'a, S>) -> Self {
+        let mut non_det_choices: VecDeque<S::Vertex> = VecDeque::default();
+        let mut active_parent: HashMap<S::Vertex, usize> = HashMap::default();
 
         // do active_parent counting
-        for u in self.vertices() {
+        for u in solver.vertices() {
             {
                 active_parent.entry(u.clone()).or_insert(0);
             }
-            if let Some(vs) = self.children_of(&u) {
+            if let Some(vs) = solver.children_of(&u) {
                 for v in vs {
                     let entry = active_parent.entry(v).or_insert(0);
                     *entry += 1;
@@ -107,17 +329,397 @@ pub trait ConstrainedLinearizationSolver {
             }
         });
 
-        self.do_dfs(
-            &mut non_det_choices,
-            &mut active_parent,
-            &mut linearization,
-            &mut seen,
+        Self {
+            solver,
+            non_det_choices,
+            active_parent,
+            linearization: Vec::default(),
+            seen: Explored::Local(Memo::new()),
+            frames: Vec::default(),
+            entering: true,
+            reason: BacktrackReason::DeadEnd,
+            found: false,
+            done: false,
+            stats: SearchStats::default(),
+        }
+    }
+
+    /// Starts the search after `prefix`, which is placed without backtracking.
+    /// Returns `None` if the prefix can not be placed in order.
+    pub fn with_prefix(solver: &'a mut S, prefix: &[S::Vertex]) -> Option<Self> {
+        let mut stepper = Self::new(solver);
+        for u in prefix {
+            let position = stepper.non_det_choices.iter().position(|v| v == u)?;
+            if !stepper.solver.allow_next(&stepper.linearization, u) {
+                return None;
+            }
+            stepper.non_det_choices.remove(position);
+            stepper.place(u.clone());
+        }
+        Some(stepper)
+    }
+
+    /// Remembers about `capacity` explored sets of choices at most, evicting the least recently
+    /// used ones. The search stays complete, but may explore a forgotten set again. The sets shared
+    /// with other searches are not bounded.
+    #[must_use]
+    // only irrefutable without the parallel feature
+    #[allow(irrefutable_let_patterns)]
+    pub fn with_memo_capacity(mut self, capacity: usize) -> Self {
+        if let Explored::Local(memo) = &mut self.seen {
+            memo.capacity = Some(capacity);
+        }
+        self
+    }
+
+    /// Shares the explored sets of choices with other searches, and stops when `stop` is set.
+    #[cfg(feature = "parallel")]
+    #[must_use]
+    pub(crate) fn sharing(
+        mut self,
+        seen: Arc<Mutex<HashSet<BTreeSet<S::Vertex>>>>,
+        stop: Arc<AtomicBool>,
+    ) -> Self {
+        self.seen = Explored::Shared { seen, stop };
+        self
+    }
+
+    #[must_use]
+    pub fn linearization(&self) -> &[S::Vertex] {
+        &self.linearization
+    }
+
+    /// Returns the vertices that can be placed next.
+    #[must_use]
+    pub fn allowed_next(&self) -> Vec<S::Vertex> {
+        self.non_det_choices
+            .iter()
+            .filter(|u| self.solver.allow_next(&self.linearization, u))
+            .cloned()
+            .collect()
+    }
+
+    /// Returns true if every vertex is placed.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.non_det_choices.is_empty()
+    }
+
+    /// Runs the search to the end and returns the linearization, if there is one.
+    #[must_use]
+    pub fn run(self) -> Option<Vec<S::Vertex>> {
+        self.run_with_stats().0
+    }
+
+    /// Runs the search to the end, like [`LinearizationStepper::run`], and also returns its counters.
+    #[must_use]
+    pub fn run_with_stats(mut self) -> (Option<Vec<S::Vertex>>, SearchStats) {
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!(
+            "linearization",
+            prefix = self.linearization.len(),
+            found = tracing::field::Empty,
+            placed = tracing::field::Empty,
+            rejected = tracing::field::Empty,
+            backtracks = tracing::field::Empty,
+            memo_hits = tracing::field::Empty,
+            memo_evictions = tracing::field::Empty,
         );
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+        while self.advance().is_some() {}
+        let linearization = self.found.then_some(self.linearization);
+        #[cfg(feature = "tracing")]
+        {
+            span.record("found", linearization.is_some());
+            self.stats.record(&span);
+        }
+        (linearization, self.stats)
+    }
+
+    /// Takes at most `budget` steps of the search, so that a long search can be run in chunks.
+    /// Returns the outcome of [`LinearizationStepper::run`] once the search is over, or `None` if
+    /// the budget runs out before.
+    pub fn run_budget(&mut self, budget: u64) -> Option<Option<Vec<S::Vertex>>> {
+        for _ in 0..budget {
+            if self.advance().is_none() {
+                break;
+            }
+        }
+        self.done
+            .then(|| self.found.then(|| self.linearization.clone()))
+    }
+
+    /// Returns the counters of the search so far.
+    #[must_use]
+    pub const fn stats(&self) -> SearchStats {
+        self.stats
+    }
+
+    fn place(&mut self, u: S::Vertex) {
+        if let Some(vs) = self.solver.children_of(&u) {
+            for v in vs {
+                let entry = self
+                    .active_parent
+                    .get_mut(&v)
+                    .expect("all vertices are expected in active parent");
+                *entry -= 1;
+                if *entry == 0 {
+                    self.non_det_choices.push_back(v);
+                }
+            }
+        }
+
+        self.linearization.push(u);
+
+        self.solver.forward_book_keeping(&self.linearization);
+    }
+
+    fn unplace(&mut self, u: &S::Vertex, n_choice: usize) {
+        self.solver.backtrack_book_keeping(&self.linearization);
+
+        self.linearization.pop();
+
+        if let Some(vs) = self.solver.children_of(u) {
+            for v in vs {
+                let entry = self
+                    .active_parent
+                    .get_mut(&v)
+                    .expect("all vertices are expected in active parent");
+                *entry += 1;
+            }
+        }
+        self.non_det_choices.drain(n_choice - 1..);
+    }
 
-        if linearization.is_empty() {
-            None
-        } else {
-            Some(linearization)
+    /// Takes one step of the search. Returns `None` once the search is over.
+    fn advance(&mut self) -> Option<StepEvent<S::Vertex>> {
+        if self.done {
+            return None;
         }
+        loop {
+            if self.seen.is_stopped() {
+                self.done = true;
+                return Some(StepEvent::Exhausted);
+            }
+            if self.entering {
+                self.entering = false;
+                let fresh = self
+                    .seen
+                    .insert(self.non_det_choices.iter().cloned().collect());
+                self.stats.memo_evictions = self.seen.evictions();
+                if !fresh {
+                    // non-det choices are already explored
+                    self.reason = BacktrackReason::AlreadyExplored;
+                    self.stats.memo_hits += 1;
+                } else if self.non_det_choices.is_empty() {
+                    // the vertices left, if any, are on a cycle
+                    if self.linearization.len() == self.active_parent.len() {
+                        self.found = true;
+                        self.done = true;
+                        return Some(StepEvent::Found);
+                    }
+                    self.reason = BacktrackReason::DeadEnd;
+                } else {
+                    self.frames.push(Frame {
+                        n_choice: self.non_det_choices.len(),
+                        i_choice: 0,
+                        placed: None,
+                    });
+                }
+            }
+
+            let Some(frame) = self.frames.last_mut() else {
+                self.done = true;
+                return Some(StepEvent::Exhausted);
+            };
+            let n_choice = frame.n_choice;
+
+            if let Some(u) = frame.placed.take() {
+                frame.i_choice += 1;
+                self.unplace(&u, n_choice);
+                self.non_det_choices.push_back(u.clone());
+                self.stats.backtracks += 1;
+                return Some(StepEvent::Backtracked {
+                    vertex: u,
+                    reason: self.reason,
+                });
+            }
+
+            if frame.i_choice == n_choice {
+                self.frames.pop();
+                self.reason = BacktrackReason::DeadEnd;
+                continue;
+            }
+
+            let u = self
+                .non_det_choices
+                .pop_front()
+                .expect("a frame has as many choices as it explores");
+            if self.solver.allow_next(&self.linearization, &u) {
+                frame.placed = Some(u.clone());
+                self.place(u.clone());
+                self.entering = true;
+                self.stats.placed += 1;
+                return Some(StepEvent::Placed(u));
+            }
+            frame.i_choice += 1;
+            self.non_det_choices.push_back(u.clone());
+            self.stats.rejected += 1;
+            return Some(StepEvent::Rejected(u));
+        }
+    }
+}
+
+impl<S> Iterator for LinearizationStepper<'_, S>
+where
+    S: ConstrainedLinearizationSolver,
+{
+    type Item = LinearizationStep<S::Vertex>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let event = self.advance()?;
+        Some(LinearizationStep {
+            event,
+            prefix: self.linearization.clone(),
+            frontier: self.non_det_choices.iter().cloned().collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::history::non_atomic::types::{Event, Transaction};
+    use crate::solver::causal::check_causal_read;
+    use crate::solver::serializable::SerializabilitySolver;
+
+    #[test]
+    fn test_stepper() {
+        let histories = vec![
+            vec![Transaction::committed(vec![Event::write("x", 1)])],
+            vec![Transaction::committed(vec![
+                Event::read("x", 1),
+                Event::write("x", 2),
+            ])],
+        ];
+
+        let mut solver = SerializabilitySolver::from(check_causal_read(&histories).unwrap());
+        let steps: Vec<_> = solver.stepper().collect();
+
+        let last = steps.last().unwrap();
+        assert_eq!(last.event, StepEvent::Found);
+        assert!(last.frontier.is_empty());
+        assert_eq!(
+            Some(last.prefix.clone()),
+            SerializabilitySolver::from(check_causal_read(&histories).unwrap()).get_linearization()
+        );
+        assert_eq!(
+            steps
+                .iter()
+                .filter(|step| matches!(step.event, StepEvent::Placed(_)))
+                .count(),
+            last.prefix.len()
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_do_dfs() {
+        let histories = vec![
+            vec![Transaction::committed(vec![Event::write("x", 1)])],
+            vec![Transaction::committed(vec![Event::read("x", 1)])],
+        ];
+
+        let mut solver = SerializabilitySolver::from(check_causal_read(&histories).unwrap());
+        let mut linearization = Vec::new();
+        assert!(solver.do_dfs(
+            &mut VecDeque::new(),
+            &mut HashMap::new(),
+            &mut linearization,
+            &mut HashSet::new(),
+        ));
+        assert_eq!(
+            Some(linearization),
+            SerializabilitySolver::from(check_causal_read(&histories).unwrap()).get_linearization()
+        );
+    }
+
+    #[test]
+    fn test_stepper_backtracks() {
+        // lost update: two transactions read the initial version of x and overwrite it
+        let histories = vec![
+            vec![Transaction::committed(vec![
+                Event::read_empty("x"),
+                Event::write("x", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read_empty("x"),
+                Event::write("x", 2),
+            ])],
+            vec![Transaction::committed(vec![Event::write("y", 1)])],
+        ];
+
+        let mut solver = SerializabilitySolver::from(check_causal_read(&histories).unwrap());
+        let steps: Vec<_> = solver.stepper().collect();
+
+        assert_eq!(steps.last().unwrap().event, StepEvent::Exhausted);
+        let (linearization, stats) =
+            SerializabilitySolver::from(check_causal_read(&histories).unwrap())
+                .stepper()
+                .run_with_stats();
+        assert!(linearization.is_none());
+        assert_eq!(stats.placed, stats.backtracks);
+        assert_eq!(
+            usize::try_from(stats.placed + stats.rejected + stats.backtracks).unwrap() + 1,
+            steps.len()
+        );
+        assert!(steps
+            .iter()
+            .any(|step| matches!(step.event, StepEvent::Rejected(_))));
+        assert!(steps.iter().any(|step| matches!(
+            step.event,
+            StepEvent::Backtracked {
+                reason: BacktrackReason::DeadEnd,
+                ..
+            }
+        )));
+        assert!(steps.last().unwrap().prefix.is_empty());
+    }
+
+    #[test]
+    fn test_bounded_memo() {
+        // lost update, with independent writes that multiply the sets of choices
+        let mut histories = vec![
+            vec![Transaction::committed(vec![
+                Event::read_empty("x"),
+                Event::write("x", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read_empty("x"),
+                Event::write("x", 2),
+            ])],
+        ];
+        histories.extend(
+            ["y", "z", "w"]
+                .map(|variable| vec![Transaction::committed(vec![Event::write(variable, 1)])]),
+        );
+        let po = check_causal_read(&histories).unwrap();
+
+        let (linearization, unbounded) = SerializabilitySolver::from(po.clone())
+            .stepper()
+            .run_with_stats();
+        assert!(linearization.is_none());
+        assert_eq!(unbounded.memo_evictions, 0);
+
+        let (linearization, bounded) = SerializabilitySolver::from(po)
+            .stepper()
+            .with_memo_capacity(2)
+            .run_with_stats();
+        assert!(linearization.is_none());
+        assert!(bounded.memo_evictions > 0);
+        assert!(bounded.placed > unbounded.placed);
     }
 }
@@ -0,0 +1,123 @@
+//! Checks that the sessions converge to the same state, as causal+ consistency requires of
+//! replicated stores such as CRDT-backed ones.
+//!
+//! The state a session ends with is, for each variable, the version of its last access in a
+//! committed transaction: the version it reads, or the version it writes. A transaction of unknown
+//! commit status counts as committed if it is read from, as in [`observed_unknown`]. The sessions
+//! converge if they end with the same version of each variable they access. This is independent of
+//! causal consistency, and is meaningful when every session ends by reading the variables after
+//! the writes have quiesced.
+
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+use hashbrown::HashMap;
+
+use crate::history::atomic::types::TransactionId;
+use crate::history::non_atomic::observed_unknown;
+use crate::history::non_atomic::types::{Event, Session};
+
+/// A variable the sessions end with different versions of.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence<Variable, Version> {
+    pub variable: Variable,
+    /// The session ids that access the variable, with the version each ends with, `None` for the
+    /// initial version
+    pub finals: Vec<(u64, Option<Version>)>,
+}
+
+/// Returns the variables the sessions do not converge on, in order of first access. An empty
+/// result means the sessions converge.
+#[must_use]
+pub fn divergences<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+) -> Vec<Divergence<Variable, Version>>
+where
+    Variable: Eq + Hash + Clone,
+    Version: Eq + Hash + Clone,
+{
+    let mut index: HashMap<&Variable, usize> = HashMap::new();
+    let mut finals: Vec<Divergence<Variable, Version>> = Vec::new();
+
+    let observed = observed_unknown(histories);
+    for (session_id, session) in (1..).zip(histories) {
+        let mut last: HashMap<&Variable, Option<&Version>> = HashMap::new();
+        for event in (0..)
+            .zip(session)
+            .filter(|(session_height, transaction)| {
+                transaction.committed
+                    || observed.contains(&TransactionId {
+                        session_id,
+                        session_height: *session_height,
+                    })
+            })
+            .flat_map(|(_, transaction)| &transaction.events)
+        {
+            match event {
+                Event::Read { variable, version } => last.insert(variable, version.as_ref()),
+                Event::Write { variable, version } => last.insert(variable, Some(version)),
+            };
+        }
+        // in order of first access in the history
+        let mut accessed: Vec<(usize, Option<&Version>)> = last
+            .into_iter()
+            .map(|(variable, version)| {
+                let i = *index.entry(variable).or_insert_with(|| {
+                    finals.push(Divergence {
+                        variable: variable.clone(),
+                        finals: Vec::new(),
+                    });
+                    finals.len() - 1
+                });
+                (i, version)
+            })
+            .collect();
+        accessed.sort_unstable_by_key(|(i, _)| *i);
+        for (i, version) in accessed {
+            finals[i].finals.push((session_id, version.cloned()));
+        }
+    }
+
+    finals.retain(|divergence| {
+        divergence
+            .finals
+            .windows(2)
+            .any(|pair| pair[0].1 != pair[1].1)
+    });
+    finals
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::history::non_atomic::types::Transaction;
+
+    #[test]
+    fn test_divergences() {
+        let histories = vec![
+            vec![
+                Transaction::committed(vec![Event::write("x", 1), Event::write("y", 1)]),
+                Transaction::committed(vec![Event::read("x", 2), Event::read("y", 1)]),
+            ],
+            vec![
+                Transaction::committed(vec![Event::read("x", 1), Event::write("x", 2)]),
+                Transaction::committed(vec![Event::read("y", 1)]),
+                // an aborted read does not count
+                Transaction::uncommitted(vec![Event::read_empty("y")]),
+            ],
+            vec![Transaction::committed(vec![Event::read("x", 1)])],
+        ];
+
+        assert_eq!(
+            divergences(&histories),
+            vec![Divergence {
+                variable: "x",
+                finals: vec![(1, Some(2)), (2, Some(2)), (3, Some(1))],
+            }]
+        );
+        assert!(divergences(&histories[..2]).is_empty());
+    }
+}
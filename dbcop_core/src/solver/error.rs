@@ -1,10 +1,153 @@
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter, Result};
+
 use ::derive_more::From;
 
+use crate::history::atomic::types::{Edge, TransactionId};
 use crate::history::non_atomic::error::Error as NonAtomicError;
 use crate::Consistency;
 
-#[derive(Debug, From)]
+/// Error checking a history against a consistency level.
+///
+/// With the `serde` feature, it serializes as `{"non_atomic": {"kind": .., ..}}`
+/// `{"invalid": "<consistency>"}`, `{"cycle": {"level": "<consistency>", "cycle": [..]}}` or
+/// `{"unknown_transaction": {"session_id": .., "session_height": ..}}`.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[derive(Debug, Clone, From)]
 pub enum Error<Variable, Version> {
     NonAtomic(NonAtomicError<Variable, Version>),
     Invalid(Consistency),
+    /// The visibility relation required by `level` has a cycle
+    #[from(ignore)]
+    Cycle {
+        level: Consistency,
+        cycle: Vec<Edge<Variable>>,
+    },
+    /// An order hint names a transaction that is not in the history
+    #[from(ignore)]
+    UnknownTransaction(TransactionId),
+}
+
+/// Renders a cycle as `violates <level>: s1.t0 -wr(x)-> s2.t0 -so-> s1.t0`.
+impl<Variable, Version> Display for Error<Variable, Version>
+where
+    Variable: Display,
+    Version: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::NonAtomic(error) => write!(f, "{error}"),
+            Self::Invalid(level) => write!(f, "violates {level}"),
+            Self::Cycle { level, cycle } => {
+                write!(f, "violates {level}")?;
+                if let Some(first) = cycle.first() {
+                    write!(f, ": {}", first.source)?;
+                }
+                for edge in cycle {
+                    write!(f, " -{}-> {}", edge.kind, edge.target)?;
+                }
+                Ok(())
+            }
+            Self::UnknownTransaction(id) => write!(f, "unknown transaction {id}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::atomic::types::{Edge, EdgeKind, TransactionId};
+    use crate::history::non_atomic::types::{Event, EventId};
+
+    #[test]
+    fn test_display_error() {
+        let error: Error<&str, u64> = NonAtomicError::UncommittedWrite {
+            read_event: Event::read("x", 1),
+            read_event_id: EventId {
+                session_id: 2,
+                session_height: 0,
+                transaction_height: 1,
+            },
+            write_event_id: EventId {
+                session_id: 1,
+                session_height: 3,
+                transaction_height: 0,
+            },
+        }
+        .into();
+        assert_eq!(
+            error.to_string(),
+            "r(x)=1 at s2.t0.e1 reads the uncommitted write at s1.t3.e0"
+        );
+
+        let first = TransactionId {
+            session_id: 1,
+            session_height: 0,
+        };
+        let second = TransactionId {
+            session_id: 2,
+            session_height: 0,
+        };
+        let error: Error<&str, u64> = Error::Cycle {
+            level: Consistency::Causal,
+            cycle: vec![
+                Edge {
+                    source: first,
+                    target: second,
+                    kind: EdgeKind::WriteRead("x"),
+                },
+                Edge {
+                    source: second,
+                    target: first,
+                    kind: EdgeKind::WriteWrite("y"),
+                },
+            ],
+        };
+        assert_eq!(
+            error.to_string(),
+            "violates causal consistency: s1.t0 -wr(x)-> s2.t0 -ww(y)-> s1.t0"
+        );
+        assert_eq!(
+            Error::<&str, u64>::Invalid(Consistency::Serializable).to_string(),
+            "violates serializability"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize_error() {
+        let error: Error<&str, u64> = NonAtomicError::UncommittedWrite {
+            read_event: Event::read("x", 1),
+            read_event_id: EventId {
+                session_id: 2,
+                session_height: 0,
+                transaction_height: 1,
+            },
+            write_event_id: EventId {
+                session_id: 1,
+                session_height: 3,
+                transaction_height: 0,
+            },
+        }
+        .into();
+
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({
+                "non_atomic": {
+                    "kind": "uncommitted_write",
+                    "read_event": {"Read": {"variable": "x", "version": 1}},
+                    "read_event_id": {"session_id": 2, "session_height": 0, "transaction_height": 1},
+                    "write_event_id": {"session_id": 1, "session_height": 3, "transaction_height": 0},
+                }
+            })
+        );
+
+        let error: Error<&str, u64> = Error::Invalid(Consistency::SnapshotIsolation);
+        assert_eq!(
+            serde_json::to_value(error).unwrap(),
+            serde_json::json!({"invalid": "snapshot_isolation"})
+        );
+    }
 }
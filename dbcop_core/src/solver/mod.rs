@@ -1,9 +1,17 @@
 pub mod atomic_read;
+pub mod canonical;
 pub mod causal;
 pub mod committed_read;
 pub mod constrained_linearization;
+pub mod convergence;
 pub mod error;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod phenomena;
+pub mod polygraph;
 pub mod prefix;
+pub mod pruning;
 pub mod repeatable_read;
+pub mod saturation;
 pub mod serializable;
 pub mod snapshot_isolation;
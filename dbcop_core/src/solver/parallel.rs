@@ -0,0 +1,130 @@
+//! Parallel search for a constrained linearization.
+//!
+//! The search space is split into disjoint subtrees by expanding the allowed prefixes breadth
+//! first, until there are a few subtrees per thread of the rayon pool. Each subtree is searched by
+//! a [`LinearizationStepper`] on its own clone of the solver, and rayon lets idle threads steal the
+//! remaining subtrees. The sets of choices explored by any search are shared, so a state is
+//! explored only once across threads. The subtrees cover every linearization, so the search is
+//! complete. The first linearization found stops the other searches.
+
+use alloc::collections::BTreeSet;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use hashbrown::HashSet;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::solver::constrained_linearization::{
+    ConstrainedLinearizationSolver, LinearizationStepper,
+};
+
+/// Number of subtrees per thread, so that threads finishing early can steal work.
+const SUBTREES_PER_THREAD: usize = 4;
+
+/// Returns disjoint prefixes whose subtrees contain every linearization.
+fn split<S>(solver: &S) -> Vec<Vec<S::Vertex>>
+where
+    S: ConstrainedLinearizationSolver + Clone,
+{
+    let target = rayon::current_num_threads() * SUBTREES_PER_THREAD;
+    let mut prefixes: Vec<Vec<S::Vertex>> = vec![Vec::new()];
+
+    while prefixes.len() < target {
+        let mut expanded = false;
+        let mut next = Vec::new();
+        for prefix in prefixes {
+            let mut solver = solver.clone();
+            let Some(stepper) = LinearizationStepper::with_prefix(&mut solver, &prefix) else {
+                continue;
+            };
+            if stepper.is_complete() {
+                next.push(prefix);
+                continue;
+            }
+            // a prefix without allowed choices is a dead end, and is dropped
+            for u in stepper.allowed_next() {
+                let mut extended = prefix.clone();
+                extended.push(u);
+                next.push(extended);
+                expanded = true;
+            }
+        }
+        prefixes = next;
+        if !expanded {
+            break;
+        }
+    }
+
+    prefixes
+}
+
+/// Searches for a linearization on all the threads of the rayon pool.
+///
+/// It finds a linearization if and only if [`ConstrainedLinearizationSolver::get_linearization`]
+/// does, though not necessarily the same one.
+pub fn get_linearization<S>(solver: &S) -> Option<Vec<S::Vertex>>
+where
+    S: ConstrainedLinearizationSolver + Clone + Send + Sync,
+    S::Vertex: Send + Sync,
+{
+    let seen: Arc<Mutex<HashSet<BTreeSet<S::Vertex>>>> = Arc::default();
+    let stop = Arc::new(AtomicBool::new(false));
+
+    split(solver).into_par_iter().find_map_any(|prefix| {
+        if stop.load(Ordering::Relaxed) {
+            return None;
+        }
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("attempt", prefix = prefix.len()).entered();
+        let mut solver = solver.clone();
+        let linearization = LinearizationStepper::with_prefix(&mut solver, &prefix)?
+            .sharing(Arc::clone(&seen), Arc::clone(&stop))
+            .run();
+        if linearization.is_some() {
+            stop.store(true, Ordering::Relaxed);
+        }
+        linearization
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::non_atomic::types::{Event, Transaction};
+    use crate::solver::causal::check_causal_read;
+    use crate::solver::serializable::SerializabilitySolver;
+    use crate::solver::snapshot_isolation::SnapshotIsolationSolver;
+
+    #[test]
+    fn test_parallel_linearization() {
+        // write skew: snapshot isolation, but not serializable
+        let histories = vec![
+            vec![Transaction::committed(vec![
+                Event::read_empty("x"),
+                Event::read_empty("y"),
+                Event::write("x", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read_empty("x"),
+                Event::read_empty("y"),
+                Event::write("y", 1),
+            ])],
+            vec![Transaction::committed(vec![Event::write("z", 1)])],
+        ];
+        let po = check_causal_read(&histories).unwrap();
+
+        let linearization = SnapshotIsolationSolver::from(po.clone()).get_linearization_parallel();
+        assert_eq!(
+            linearization.map(|l| l.len()),
+            SnapshotIsolationSolver::from(po.clone())
+                .get_linearization()
+                .map(|l| l.len())
+        );
+        assert!(SerializabilitySolver::from(po)
+            .get_linearization_parallel()
+            .is_none());
+    }
+}
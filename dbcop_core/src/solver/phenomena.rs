@@ -0,0 +1,222 @@
+//! Checks the G1 phenomena of Adya event by event, to tell which part of PL-2 a history violates.
+//!
+//! [`check_committed_read`] checks read committed at the granularity of transactions and stops at
+//! the first problem. [`g1_anomalies`] reports every read that exhibits one of
+//! - G1a, aborted read: it reads a write of an aborted transaction.
+//! - G1b, intermediate read: it reads a write that its committed transaction overwrites later.
+//! - G1c, circular information flow: the committed transactions depend on each other in a cycle.
+//!
+//! The version order is not observed, so G1c is a cycle of write-read dependencies only.
+//!
+//! [`check_committed_read`]: crate::solver::committed_read::check_committed_read
+
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+use hashbrown::HashMap;
+
+use crate::graph::digraph::DiGraph;
+use crate::history::atomic::types::TransactionId;
+use crate::history::non_atomic::error::Error as NonAtomicError;
+use crate::history::non_atomic::types::{Event, EventId, Session};
+use crate::history::non_atomic::{get_all_writes, observed_unknown};
+use crate::solver::error::Error;
+
+/// A phenomenon of the G1 family, each of which PL-2 proscribes.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phenomenon {
+    G1a,
+    G1b,
+    G1c,
+}
+
+/// An occurrence of a G1 phenomenon.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Anomaly<Variable, Version> {
+    /// Reads a write of an aborted transaction
+    AbortedRead {
+        read_event: Event<Variable, Version>,
+        read_event_id: EventId,
+        write_event_id: EventId,
+    },
+    /// Reads a write that is not the last write of the variable in its transaction
+    IntermediateRead {
+        read_event: Event<Variable, Version>,
+        read_event_id: EventId,
+        write_event_id: EventId,
+        final_write_event_id: EventId,
+    },
+    /// Committed transactions that read from each other in a cycle
+    CircularInformationFlow { cycle: Vec<TransactionId> },
+}
+
+impl<Variable, Version> Anomaly<Variable, Version> {
+    #[must_use]
+    pub const fn phenomenon(&self) -> Phenomenon {
+        match self {
+            Self::AbortedRead { .. } => Phenomenon::G1a,
+            Self::IntermediateRead { .. } => Phenomenon::G1b,
+            Self::CircularInformationFlow { .. } => Phenomenon::G1c,
+        }
+    }
+}
+
+/// Returns every G1 anomaly of a history, G1a and G1b in event order, then a G1c cycle if any.
+/// An empty result means the history is PL-2.
+///
+/// # Errors
+///
+/// Returns [`Error::NonAtomic`] if a read has no matching write, or two writes share a version.
+pub fn g1_anomalies<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+) -> Result<Vec<Anomaly<Variable, Version>>, Error<Variable, Version>>
+where
+    Variable: Eq + Hash + Clone,
+    Version: Eq + Hash + Clone,
+{
+    let all_writes = get_all_writes(histories)?;
+
+    // the last write of each variable in each transaction
+    let mut final_writes: HashMap<(TransactionId, &Variable), EventId> = HashMap::new();
+    for (session_id, session) in (1..).zip(histories) {
+        for (session_height, transaction) in (0..).zip(session) {
+            for (transaction_height, event) in (0..).zip(&transaction.events) {
+                if let Event::Write { variable, .. } = event {
+                    let id = EventId {
+                        session_id,
+                        session_height,
+                        transaction_height,
+                    };
+                    final_writes.insert((id.transaction_id(), variable), id);
+                }
+            }
+        }
+    }
+
+    let observed = observed_unknown(histories);
+    let mut anomalies = Vec::new();
+    let mut write_read: DiGraph<TransactionId> = DiGraph::default();
+
+    for (session_id, session) in (1..).zip(histories) {
+        for (session_height, transaction) in (0..).zip(session) {
+            for (transaction_height, event) in (0..).zip(&transaction.events) {
+                let Event::Read { variable, .. } = event else {
+                    continue;
+                };
+                let read_event_id = EventId {
+                    session_id,
+                    session_height,
+                    transaction_height,
+                };
+                let Some(&write_event_id) = all_writes.get(event) else {
+                    return Err(NonAtomicError::IncompleteHistory {
+                        event: event.clone(),
+                        id: read_event_id,
+                    }
+                    .into());
+                };
+                let writer = write_event_id.transaction_id();
+                if writer == read_event_id.transaction_id() || writer == TransactionId::root() {
+                    continue;
+                }
+
+                let writer_committed = usize::try_from(writer.session_id - 1)
+                    .ok()
+                    .zip(usize::try_from(writer.session_height).ok())
+                    .and_then(|(i_session, i_transaction)| {
+                        histories.get(i_session)?.get(i_transaction)
+                    })
+                    // a transaction of unknown status that is read from is taken as committed
+                    .is_some_and(|transaction| transaction.committed || transaction.unknown);
+
+                if !writer_committed {
+                    anomalies.push(Anomaly::AbortedRead {
+                        read_event: event.clone(),
+                        read_event_id,
+                        write_event_id,
+                    });
+                    continue;
+                }
+
+                if let Some(&final_write_event_id) = final_writes.get(&(writer, variable)) {
+                    if final_write_event_id != write_event_id {
+                        anomalies.push(Anomaly::IntermediateRead {
+                            read_event: event.clone(),
+                            read_event_id,
+                            write_event_id,
+                            final_write_event_id,
+                        });
+                    }
+                }
+
+                if transaction.committed || observed.contains(&read_event_id.transaction_id()) {
+                    write_read.add_edge(writer, read_event_id.transaction_id());
+                }
+            }
+        }
+    }
+
+    if let Some(cycle) = write_read.find_cycle() {
+        anomalies.push(Anomaly::CircularInformationFlow { cycle });
+    }
+
+    Ok(anomalies)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::history::non_atomic::types::Transaction;
+
+    #[test]
+    fn test_g1_anomalies() {
+        let histories = vec![
+            vec![
+                Transaction::uncommitted(vec![Event::write("x", 1)]),
+                Transaction::committed(vec![Event::write("y", 1), Event::write("y", 2)]),
+            ],
+            vec![Transaction::committed(vec![
+                Event::read("x", 1),
+                Event::read("y", 1),
+            ])],
+        ];
+
+        let phenomena: Vec<_> = g1_anomalies(&histories)
+            .unwrap()
+            .iter()
+            .map(Anomaly::phenomenon)
+            .collect();
+        assert_eq!(phenomena, vec![Phenomenon::G1a, Phenomenon::G1b]);
+    }
+
+    #[test]
+    fn test_circular_information_flow() {
+        let histories = vec![
+            vec![Transaction::committed(vec![
+                Event::write("x", 1),
+                Event::read("y", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::write("y", 1),
+                Event::read("x", 1),
+            ])],
+        ];
+
+        let anomalies = g1_anomalies(&histories).unwrap();
+        assert!(matches!(
+            anomalies.as_slice(),
+            [Anomaly::CircularInformationFlow { cycle }] if cycle.len() == 2
+        ));
+
+        assert!(
+            g1_anomalies(&[vec![Transaction::committed(vec![Event::write("x", 1)])]])
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,258 @@
+//! Checks serializability on the polygraph of a history, as Cobra does.
+//!
+//! The known edges are the causal visibility relation. Every pair of transactions writing the same
+//! variable adds a constraint with two sides: either the first is ordered before the second, along
+//! with the anti-dependencies of the readers of the first, or the other way around. A side that
+//! closes a cycle with the known edges is infeasible, so the other side becomes known. Most
+//! histories are decided by this propagation alone; the remaining constraints are searched by
+//! backtracking, propagating again after every choice.
+
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+use hashbrown::HashMap;
+
+use crate::graph::digraph::DiGraph;
+use crate::history::atomic::types::TransactionId;
+use crate::history::atomic::AtomicTransactionPO;
+
+/// Edges of which at least one side must hold in a serial order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraint {
+    pub either: Vec<(TransactionId, TransactionId)>,
+    pub or: Vec<(TransactionId, TransactionId)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Polygraph {
+    /// Edges every serial order satisfies, transitively closed
+    pub known: DiGraph<TransactionId>,
+    pub constraints: Vec<Constraint>,
+}
+
+impl<Variable> From<&AtomicTransactionPO<Variable>> for Polygraph
+where
+    Variable: Clone + Eq + Hash,
+{
+    fn from(po: &AtomicTransactionPO<Variable>) -> Self {
+        let mut known = po.visibility_relation.clone();
+        known.add_vertex(po.root);
+        for transaction in po.history.0.keys() {
+            known.add_vertex(*transaction);
+        }
+
+        let mut constraints = Vec::new();
+        for (variable, wr_x) in &po.write_read_relation {
+            let mut writers: Vec<TransactionId> = po
+                .history
+                .0
+                .iter()
+                .filter(|(_, info)| info.writes.contains(variable))
+                .map(|(id, _)| *id)
+                .chain([po.root])
+                .collect();
+            writers.sort_unstable();
+
+            // the edges ordering `first` right before `second` for `variable`
+            let side = |first: &TransactionId, second: &TransactionId| {
+                let readers = wr_x.adj_map.get(first).into_iter().flatten();
+                core::iter::once(first)
+                    .chain(readers)
+                    .filter(|source| *source != second)
+                    .map(|source| (*source, *second))
+                    .collect()
+            };
+
+            for (i, first) in writers.iter().enumerate() {
+                for second in &writers[i + 1..] {
+                    constraints.push(Constraint {
+                        either: side(first, second),
+                        or: side(second, first),
+                    });
+                }
+            }
+        }
+
+        Self {
+            known: known.closure(),
+            constraints,
+        }
+    }
+}
+
+impl Polygraph {
+    /// Adds the only feasible side of every constraint to the known edges, until none is left with
+    /// a single feasible side. Returns false if a constraint has no feasible side.
+    pub fn propagate(&mut self) -> bool {
+        loop {
+            let mut forced = Vec::new();
+            let mut undecided = Vec::new();
+            for constraint in core::mem::take(&mut self.constraints) {
+                match (
+                    self.is_feasible(&constraint.either),
+                    self.is_feasible(&constraint.or),
+                ) {
+                    (false, false) => return false,
+                    (true, false) => forced.extend(constraint.either),
+                    (false, true) => forced.extend(constraint.or),
+                    (true, true)
+                        if self.holds(&constraint.either) || self.holds(&constraint.or) => {}
+                    (true, true) => undecided.push(constraint),
+                }
+            }
+            self.constraints = undecided;
+            if forced.is_empty() {
+                return true;
+            }
+            for (source, target) in forced {
+                self.known.add_edge(source, target);
+            }
+            self.known = self.known.closure();
+            if self.known.has_cycle() {
+                return false;
+            }
+        }
+    }
+
+    /// Returns a serial order of the transactions, without the root, that satisfies every
+    /// constraint, if there is one.
+    #[must_use]
+    pub fn solve(mut self) -> Option<Vec<TransactionId>> {
+        #[cfg(feature = "tracing")]
+        let _span =
+            tracing::trace_span!("polygraph", constraints = self.constraints.len()).entered();
+        if self.known.has_cycle() || !self.propagate() {
+            return None;
+        }
+        let Some(constraint) = self.constraints.pop() else {
+            return Some(self.topological_order());
+        };
+        [constraint.either, constraint.or]
+            .into_iter()
+            .find_map(|side| {
+                let mut choice = self.clone();
+                for (source, target) in side {
+                    choice.known.add_edge(source, target);
+                }
+                choice.known = choice.known.closure();
+                choice.solve()
+            })
+    }
+
+    /// Returns true if no edge of `side` goes against a known edge.
+    fn is_feasible(&self, side: &[(TransactionId, TransactionId)]) -> bool {
+        side.iter()
+            .all(|(source, target)| source != target && !self.known.has_edge(target, source))
+    }
+
+    fn holds(&self, side: &[(TransactionId, TransactionId)]) -> bool {
+        side.iter()
+            .all(|(source, target)| self.known.has_edge(source, target))
+    }
+
+    /// Orders the known edges, which are acyclic, breaking ties by transaction id.
+    fn topological_order(&self) -> Vec<TransactionId> {
+        let mut in_degree: HashMap<TransactionId, usize> = self
+            .known
+            .adj_map
+            .keys()
+            .map(|vertex| (*vertex, 0))
+            .collect();
+        for target in self.known.adj_map.values().flatten() {
+            *in_degree.entry(*target).or_default() += 1;
+        }
+
+        let mut ready: BTreeSet<TransactionId> = in_degree
+            .iter()
+            .filter(|(_, degree)| **degree == 0)
+            .map(|(vertex, _)| *vertex)
+            .collect();
+        let mut order = Vec::with_capacity(in_degree.len());
+        while let Some(vertex) = ready.pop_first() {
+            for target in self.known.adj_map.get(&vertex).into_iter().flatten() {
+                let degree = in_degree.entry(*target).or_default();
+                *degree -= 1;
+                if *degree == 0 {
+                    ready.insert(*target);
+                }
+            }
+            if vertex != TransactionId::root() {
+                order.push(vertex);
+            }
+        }
+        order
+    }
+}
+
+/// Returns a serial order of the transactions of a causal partial order, if there is one.
+#[must_use]
+pub fn serializable_order<Variable>(
+    po: &AtomicTransactionPO<Variable>,
+) -> Option<Vec<TransactionId>>
+where
+    Variable: Clone + Eq + Hash,
+{
+    Polygraph::from(po).solve()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::history::non_atomic::types::{Event, Transaction};
+    use crate::solver::causal::check_causal_read;
+
+    #[test]
+    fn test_polygraph() {
+        // write skew: causal, but not serializable
+        let histories = vec![
+            vec![Transaction::committed(vec![
+                Event::read_empty("x"),
+                Event::read_empty("y"),
+                Event::write("x", 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read_empty("x"),
+                Event::read_empty("y"),
+                Event::write("y", 1),
+            ])],
+        ];
+        let po = check_causal_read(&histories).unwrap();
+        assert!(serializable_order(&po).is_none());
+
+        let histories = vec![
+            vec![
+                Transaction::committed(vec![Event::write("x", 1)]),
+                Transaction::committed(vec![Event::read("x", 2), Event::write("x", 3)]),
+            ],
+            vec![Transaction::committed(vec![
+                Event::read("x", 1),
+                Event::write("x", 2),
+            ])],
+        ];
+        let po = check_causal_read(&histories).unwrap();
+        // every write is read, so propagation decides the whole order
+        let mut polygraph = Polygraph::from(&po);
+        assert!(polygraph.propagate());
+        assert!(polygraph.constraints.is_empty());
+        assert_eq!(
+            serializable_order(&po),
+            Some(vec![
+                TransactionId {
+                    session_id: 1,
+                    session_height: 0,
+                },
+                TransactionId {
+                    session_id: 2,
+                    session_height: 0,
+                },
+                TransactionId {
+                    session_id: 1,
+                    session_height: 1,
+                },
+            ])
+        );
+    }
+}
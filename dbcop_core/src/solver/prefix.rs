@@ -1,19 +1,20 @@
 use alloc::vec::Vec;
 use core::hash::Hash;
 
-use hashbrown::{HashMap, HashSet};
+use hashbrown::HashMap;
 
+use crate::graph::small_set::SmallSet;
 use crate::history::atomic::types::TransactionId;
 use crate::history::atomic::AtomicTransactionPO;
 use crate::solver::constrained_linearization::ConstrainedLinearizationSolver;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PrefixConsistencySolver<Variable>
 where
     Variable: Clone + Eq + Ord + Hash,
 {
     pub history: AtomicTransactionPO<Variable>,
-    pub active_write: HashMap<Variable, HashSet<TransactionId>>,
+    pub active_write: HashMap<Variable, SmallSet<TransactionId>>,
 }
 
 impl<Variable> From<AtomicTransactionPO<Variable>> for PrefixConsistencySolver<Variable>
@@ -22,8 +23,9 @@ where
 {
     fn from(history: AtomicTransactionPO<Variable>) -> Self {
         Self {
+            // the root transaction is placed before every other transaction
+            active_write: history.initial_readers(),
             history,
-            active_write: HashMap::default(),
         }
     }
 }
@@ -0,0 +1,254 @@
+//! Removes transactions that cannot take part in a violation before a linearization search.
+//!
+//! A read-only transaction that reads each of its variables from its only writer is never in the
+//! way of another transaction: no write can be placed between its read and the write it reads,
+//! and it blocks no write. Any linearization of the other transactions extends to one with it,
+//! by placing it right after its last predecessor in the visibility relation.
+//!
+//! [`prune`] removes such transactions from a partial order, keeping the order between their
+//! predecessors and successors, and [`Pruning::restore`] puts them back in a linearization.
+
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+use hashbrown::{HashMap, HashSet};
+
+use crate::graph::digraph::DiGraph;
+use crate::history::atomic::types::TransactionId;
+use crate::history::atomic::AtomicTransactionPO;
+
+/// How much [`prune`] shrank a partial order.
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Reduction {
+    /// Transactions before pruning
+    pub transactions: usize,
+    /// Transactions removed
+    pub pruned: usize,
+}
+
+/// The transactions removed by [`prune`], with their predecessors when they were removed.
+#[derive(Debug, Clone, Default)]
+pub struct Pruning {
+    transactions: usize,
+    removed: Vec<(TransactionId, HashSet<TransactionId>)>,
+}
+
+impl Pruning {
+    #[must_use]
+    pub fn reduction(&self) -> Reduction {
+        Reduction {
+            transactions: self.transactions,
+            pruned: self.removed.len(),
+        }
+    }
+
+    /// Inserts the removed transactions in a linearization of the pruned partial order.
+    ///
+    /// `transaction_of` maps a vertex of the linearization to its transaction, and `sections`
+    /// gives the vertices of a removed transaction, in order.
+    pub fn restore<Vertex>(
+        &self,
+        mut linearization: Vec<Vertex>,
+        transaction_of: impl Fn(&Vertex) -> TransactionId,
+        sections: impl Fn(TransactionId) -> Vec<Vertex>,
+    ) -> Vec<Vertex> {
+        // in reverse order, the predecessors of a transaction are already in the linearization
+        for (transaction, predecessors) in self.removed.iter().rev() {
+            let position = linearization
+                .iter()
+                .rposition(|vertex| predecessors.contains(&transaction_of(vertex)))
+                .map_or(0, |i| i + 1);
+            linearization.splice(position..position, sections(*transaction));
+        }
+        linearization
+    }
+}
+
+/// Removes every read-only transaction that reads each variable from the only transaction that
+/// writes it.
+pub fn prune<Variable>(po: &mut AtomicTransactionPO<Variable>) -> Pruning
+where
+    Variable: Clone + Eq + Hash,
+{
+    let mut irrelevant: Vec<TransactionId> = {
+        let mut writers: HashMap<&Variable, usize> = HashMap::new();
+        for info in po.history.0.values() {
+            for variable in &info.writes {
+                *writers.entry(variable).or_default() += 1;
+            }
+        }
+        po.history
+            .0
+            .iter()
+            .filter(|(_, info)| {
+                info.writes.is_empty()
+                    && info.reads.iter().all(|(variable, writer)| {
+                        *writer != po.root && writers.get(variable).copied() == Some(1)
+                    })
+            })
+            .map(|(id, _)| *id)
+            .collect()
+    };
+    irrelevant.sort_unstable();
+
+    let mut pruning = Pruning {
+        transactions: po.history.0.len(),
+        removed: Vec::with_capacity(irrelevant.len()),
+    };
+
+    for transaction in irrelevant {
+        let predecessors = remove_vertex(&mut po.visibility_relation, &transaction);
+        remove_vertex(&mut po.session_order, &transaction);
+        for wr_x in po.write_read_relation.values_mut() {
+            remove_vertex(wr_x, &transaction);
+        }
+        po.provenance
+            .retain(|(source, target), _| *source != transaction && *target != transaction);
+        po.history.0.remove(&transaction);
+        pruning.removed.push((transaction, predecessors));
+    }
+
+    pruning
+}
+
+/// Removes a vertex, connecting its predecessors to its successors, and returns its predecessors.
+fn remove_vertex(
+    graph: &mut DiGraph<TransactionId>,
+    vertex: &TransactionId,
+) -> HashSet<TransactionId> {
+    let successors = graph.adj_map.remove(vertex).unwrap_or_default();
+    let mut predecessors = HashSet::new();
+    for (source, targets) in &mut graph.adj_map {
+        if targets.remove(vertex) {
+            targets.extend(successors.iter().copied());
+            predecessors.insert(*source);
+        }
+    }
+    predecessors
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::check::{check, Witness};
+    use crate::history::non_atomic::types::{Event, Transaction};
+    use crate::solver::causal::check_causal_read;
+    use crate::solver::constrained_linearization::ConstrainedLinearizationSolver;
+    use crate::solver::serializable::SerializabilitySolver;
+    use crate::Consistency;
+
+    #[test]
+    fn test_prune() {
+        let histories = vec![
+            vec![
+                Transaction::committed(vec![Event::write("x", 1)]),
+                Transaction::committed(vec![Event::read("x", 1)]),
+                Transaction::committed(vec![Event::write("y", 1)]),
+            ],
+            vec![
+                Transaction::committed(vec![Event::read("x", 1), Event::read("y", 1)]),
+                Transaction::committed(vec![Event::read_empty("z")]),
+            ],
+            vec![Transaction::committed(vec![Event::write("z", 1)])],
+        ];
+        let mut po = check_causal_read(&histories).unwrap();
+        let pruning = prune(&mut po);
+
+        // (2, 1) reads the initial version of z, which is overwritten
+        assert_eq!(
+            pruning.reduction(),
+            Reduction {
+                transactions: 6,
+                pruned: 2,
+            }
+        );
+        assert!(po.history.0.contains_key(&TransactionId {
+            session_id: 2,
+            session_height: 1,
+        }));
+        // the session order through the removed (1, 1) is kept
+        assert!(po.visibility_relation.has_edge(
+            &TransactionId {
+                session_id: 1,
+                session_height: 0,
+            },
+            &TransactionId {
+                session_id: 1,
+                session_height: 2,
+            },
+        ));
+
+        let order = SerializabilitySolver::from(po).get_linearization().unwrap();
+        assert_eq!(order.len(), 4);
+        let order = pruning.restore(order, |id| *id, |id| vec![id]);
+        assert_eq!(order.len(), 6);
+
+        let position = |session_id, session_height| {
+            order
+                .iter()
+                .position(|id| {
+                    *id == TransactionId {
+                        session_id,
+                        session_height,
+                    }
+                })
+                .unwrap()
+        };
+        assert!(position(1, 0) < position(1, 1));
+        assert!(position(1, 1) < position(1, 2));
+        assert!(position(1, 2) < position(2, 0));
+        assert!(position(2, 0) < position(2, 1));
+    }
+
+    #[test]
+    fn test_prune_all() {
+        let histories = vec![
+            vec![
+                Transaction::<&str, u64>::committed(vec![]),
+                Transaction::committed(vec![]),
+            ],
+            vec![Transaction::committed(vec![])],
+        ];
+        let mut po = check_causal_read(&histories).unwrap();
+        let pruning = prune(&mut po);
+        assert_eq!(
+            pruning.reduction(),
+            Reduction {
+                transactions: 3,
+                pruned: 3,
+            }
+        );
+        assert!(po.history.0.is_empty());
+
+        let order = SerializabilitySolver::from(po).get_linearization().unwrap();
+        let order = pruning.restore(order, |id| *id, |id| vec![id]);
+        let first = TransactionId {
+            session_id: 1,
+            session_height: 0,
+        };
+        let second = TransactionId {
+            session_id: 1,
+            session_height: 1,
+        };
+        assert_eq!(order.len(), 3);
+        assert!(
+            order.iter().position(|id| *id == first) < order.iter().position(|id| *id == second)
+        );
+
+        // the witnesses of the checks have every transaction back
+        for level in [
+            Consistency::Prefix,
+            Consistency::SnapshotIsolation,
+            Consistency::Serializable,
+        ] {
+            match check(&histories, level) {
+                Ok(Witness::CommitOrder(order)) => assert_eq!(order.len(), 3, "{level:?}"),
+                Ok(Witness::SplitCommitOrder(order)) => assert_eq!(order.len(), 6, "{level:?}"),
+                witness => panic!("{level:?}: {witness:?}"),
+            }
+        }
+    }
+}
@@ -26,6 +26,22 @@ where
 
     check_committed_read(histories)?;
 
+    repeatable_reads(histories)
+}
+
+/// Checks that every transaction reads each variable from a single write, without checking that
+/// the history is valid and committed read.
+///
+/// # Errors
+///
+/// Returns [`NonAtomicError::NonRepeatableRead`] on the first read that sees another write.
+pub fn repeatable_reads<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+) -> Result<(), Error<Variable, Version>>
+where
+    Variable: Eq + Hash + Clone,
+    Version: Eq + Hash + Clone,
+{
     let all_writes = get_all_writes(histories)?;
 
     for (i_node, session) in (1..).zip(histories.iter()) {
@@ -0,0 +1,299 @@
+//! Step-by-step saturation of the visibility relation, for debuggers and visualizers.
+//!
+//! [`SaturationDebugger`] runs the saturation of [`check_committed_read`],
+//! [`check_atomic_read`] and [`check_causal_read`] one step at a time. Each [`SaturationStep`] lists
+//! the edges added in the step with their origin, and the edges derived by transitivity.
+//!
+//! - committed read: a single step adds the edges the commit order must contain.
+//! - repeatable read: as committed read, once the reads of every transaction are repeatable.
+//! - atomic read: the first step adds the write-read edges, the second one the write-write edges.
+//! - causal: the first step adds the write-read edges. Every next step takes the transitive closure
+//!   and adds the write-write edges it implies, until no edge is added.
+//!
+//! Levels stronger than causal are saturated as causal, before their linearization search.
+//!
+//! [`check_committed_read`]: crate::solver::committed_read::check_committed_read
+//! [`check_atomic_read`]: crate::solver::atomic_read::check_atomic_read
+//! [`check_causal_read`]: crate::solver::causal::check_causal_read
+
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+use crate::graph::digraph::DiGraph;
+use crate::history::atomic::types::{AtomicTransactionHistory, Edge, EdgeKind, TransactionId};
+use crate::history::atomic::AtomicTransactionPO;
+use crate::history::non_atomic::types::Session;
+use crate::solver::committed_read::committed_order_edges;
+use crate::solver::error::Error;
+use crate::solver::repeatable_read::repeatable_reads;
+use crate::Consistency;
+
+#[derive(Debug, Clone)]
+pub struct SaturationStep<Variable> {
+    pub iteration: usize,
+    /// Edges added in this step, with their origin
+    pub added: Vec<Edge<Variable>>,
+    /// Edges derived by transitivity in this step
+    pub derived: Vec<(TransactionId, TransactionId)>,
+    /// Whether the relation is still acyclic after this step
+    pub acyclic: bool,
+}
+
+#[derive(Debug)]
+enum Relation<Variable>
+where
+    Variable: Clone + Eq + Hash,
+{
+    Committed {
+        edges: Vec<Edge<Variable>>,
+        order: DiGraph<TransactionId>,
+    },
+    Visibility(AtomicTransactionPO<Variable>),
+}
+
+#[derive(Debug)]
+pub struct SaturationDebugger<Variable>
+where
+    Variable: Clone + Eq + Hash,
+{
+    level: Consistency,
+    relation: Relation<Variable>,
+    iteration: usize,
+    finished: bool,
+}
+
+/// Returns the edges of `graph` that are not in `relation`, sorted.
+fn new_edges(
+    relation: &DiGraph<TransactionId>,
+    graph: &DiGraph<TransactionId>,
+) -> Vec<(TransactionId, TransactionId)> {
+    let mut edges: Vec<_> = graph
+        .adj_map
+        .iter()
+        .flat_map(|(source, targets)| targets.iter().map(|target| (*source, *target)))
+        .filter(|(source, target)| !relation.has_edge(source, target))
+        .collect();
+    edges.sort_unstable();
+    edges
+}
+
+impl<Variable> SaturationDebugger<Variable>
+where
+    Variable: Clone + Eq + Hash,
+{
+    /// # Errors
+    ///
+    /// Returns [`Error::NonAtomic`] if the history is not valid for the level.
+    pub fn new<Version>(
+        histories: &[Session<Variable, Version>],
+        level: Consistency,
+    ) -> Result<Self, Error<Variable, Version>>
+    where
+        Version: Clone + Eq + Hash,
+    {
+        let relation = if matches!(
+            level,
+            Consistency::CommittedRead | Consistency::RepeatableRead
+        ) {
+            let edges = committed_order_edges(histories)?;
+            if level == Consistency::RepeatableRead {
+                repeatable_reads(histories)?;
+            }
+            Relation::Committed {
+                edges,
+                order: DiGraph::default(),
+            }
+        } else {
+            Relation::Visibility(AtomicTransactionPO::from(
+                AtomicTransactionHistory::try_from(histories)?,
+            ))
+        };
+        Ok(Self {
+            level,
+            relation,
+            iteration: 0,
+            finished: false,
+        })
+    }
+
+    #[must_use]
+    pub const fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Returns the partial order of the atomic history, for levels from atomic read upwards.
+    #[must_use]
+    pub const fn po(&self) -> Option<&AtomicTransactionPO<Variable>> {
+        match &self.relation {
+            Relation::Visibility(po) => Some(po),
+            Relation::Committed { .. } => None,
+        }
+    }
+
+    #[must_use]
+    pub fn into_po(self) -> Option<AtomicTransactionPO<Variable>> {
+        match self.relation {
+            Relation::Visibility(po) => Some(po),
+            Relation::Committed { .. } => None,
+        }
+    }
+
+    /// Adds `graph` to the visibility relation and returns the new edges.
+    fn include(
+        po: &mut AtomicTransactionPO<Variable>,
+        graph: &DiGraph<TransactionId>,
+        kind: &EdgeKind<Variable>,
+    ) -> Vec<Edge<Variable>> {
+        let added = new_edges(&po.visibility_relation, graph)
+            .into_iter()
+            .map(|(source, target)| Edge {
+                source,
+                target,
+                kind: kind.clone(),
+            })
+            .collect();
+        po.vis_includes_from(graph, kind);
+        added
+    }
+
+    fn include_ww(po: &mut AtomicTransactionPO<Variable>) -> Vec<Edge<Variable>> {
+        let mut added = Vec::new();
+        for (x, ww_x) in &po.causal_ww() {
+            added.extend(Self::include(po, ww_x, &EdgeKind::WriteWrite(x.clone())));
+        }
+        added
+    }
+
+    fn include_wr(po: &mut AtomicTransactionPO<Variable>) -> Vec<Edge<Variable>> {
+        let write_read_relation = core::mem::take(&mut po.write_read_relation);
+        let mut added = Vec::new();
+        for (x, wr_x) in &write_read_relation {
+            added.extend(Self::include(po, wr_x, &EdgeKind::WriteRead(x.clone())));
+        }
+        po.write_read_relation = write_read_relation;
+        added
+    }
+
+    fn close(po: &mut AtomicTransactionPO<Variable>) -> Vec<(TransactionId, TransactionId)> {
+        let before = po.visibility_relation.clone();
+        po.vis_is_trans();
+        new_edges(&before, &po.visibility_relation)
+    }
+}
+
+impl<Variable> Iterator for SaturationDebugger<Variable>
+where
+    Variable: Clone + Eq + Hash,
+{
+    type Item = SaturationStep<Variable>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let (added, derived, acyclic) = match &mut self.relation {
+            Relation::Committed { edges, order } => {
+                let added = core::mem::take(edges);
+                for edge in &added {
+                    order.add_edge(edge.source, edge.target);
+                }
+                let closure = order.closure();
+                let derived = new_edges(order, &closure);
+                *order = closure;
+                self.finished = true;
+                (added, derived, order.is_acyclic())
+            }
+            Relation::Visibility(po) => {
+                let (added, derived) = if self.iteration == 0 {
+                    (Self::include_wr(po), Vec::new())
+                } else if self.level == Consistency::AtomicRead {
+                    self.finished = true;
+                    (Self::include_ww(po), Vec::new())
+                } else {
+                    let derived = Self::close(po);
+                    let added = Self::include_ww(po);
+                    self.finished = added.is_empty();
+                    (added, derived)
+                };
+                (added, derived, po.has_valid_visibility())
+            }
+        };
+
+        let step = SaturationStep {
+            iteration: self.iteration,
+            added,
+            derived,
+            acyclic,
+        };
+        self.iteration += 1;
+        Some(step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::history::non_atomic::types::{Event, Transaction};
+
+    #[test]
+    fn test_causal_steps() {
+        let histories = vec![
+            vec![
+                Transaction::committed(vec![Event::write("x", 1)]),
+                Transaction::committed(vec![Event::write("x", 2)]),
+            ],
+            vec![
+                Transaction::committed(vec![Event::read("x", 2)]),
+                Transaction::committed(vec![Event::read("x", 1)]),
+            ],
+        ];
+        let t = |session_id, session_height| TransactionId {
+            session_id,
+            session_height,
+        };
+
+        let steps: Vec<_> = SaturationDebugger::new(&histories, Consistency::Causal)
+            .unwrap()
+            .collect();
+
+        assert_eq!(
+            steps[0].added,
+            vec![
+                Edge {
+                    source: t(1, 0),
+                    target: t(2, 1),
+                    kind: EdgeKind::WriteRead("x"),
+                },
+                Edge {
+                    source: t(1, 1),
+                    target: t(2, 0),
+                    kind: EdgeKind::WriteRead("x"),
+                },
+            ]
+        );
+        assert!(steps[0].acyclic);
+        assert!(steps[1].derived.contains(&(t(1, 1), t(2, 1))));
+        assert!(steps[1].added.contains(&Edge {
+            source: t(1, 1),
+            target: t(1, 0),
+            kind: EdgeKind::WriteWrite("x"),
+        }));
+        assert!(!steps[1].acyclic);
+        assert!(steps.last().unwrap().added.is_empty());
+
+        let steps: Vec<_> = SaturationDebugger::new(&histories, Consistency::AtomicRead)
+            .unwrap()
+            .collect();
+        assert_eq!(steps.len(), 2);
+        assert!(steps[1].acyclic);
+
+        let steps: Vec<_> = SaturationDebugger::new(&histories, Consistency::CommittedRead)
+            .unwrap()
+            .collect();
+        assert_eq!(steps.len(), 1);
+        assert!(steps[0].acyclic);
+    }
+}
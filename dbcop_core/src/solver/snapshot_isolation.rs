@@ -3,17 +3,18 @@ use core::hash::Hash;
 
 use hashbrown::{HashMap, HashSet};
 
+use crate::graph::small_set::SmallSet;
 use crate::history::atomic::types::TransactionId;
 use crate::history::atomic::AtomicTransactionPO;
 use crate::solver::constrained_linearization::ConstrainedLinearizationSolver;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SnapshotIsolationSolver<Variable>
 where
     Variable: Clone + Eq + Ord + Hash,
 {
     pub history: AtomicTransactionPO<Variable>,
-    pub active_write: HashMap<Variable, HashSet<TransactionId>>,
+    pub active_write: HashMap<Variable, SmallSet<TransactionId>>,
     pub active_variable: HashSet<Variable>,
 }
 
@@ -23,8 +24,9 @@ where
 {
     fn from(history: AtomicTransactionPO<Variable>) -> Self {
         Self {
+            // the root transaction is placed before every other transaction
+            active_write: history.initial_readers(),
             history,
-            active_write: HashMap::default(),
             active_variable: HashSet::default(),
         }
     }
@@ -0,0 +1,29 @@
+#ifndef DBCOP_H
+#define DBCOP_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The history was checked, and the result is written. */
+#define DBCOP_OK 0
+/* An argument is null, not UTF-8, or not a history or a level. An error message is written, as
+ * {"error": "<message>"}. */
+#define DBCOP_INVALID_ARGUMENT 1
+/* The checker failed on a bug rather than returning. The failure does not unwind into the caller,
+ * and an error message is written, as {"error": "<message>"}. */
+#define DBCOP_INTERNAL_ERROR 2
+
+/* Checks a JSON history against a level, such as "serializable", and writes the JSON result,
+ * {"Ok": <witness>} or {"Err": <error>}, to out_result_json, unless it is null. The written string
+ * is freed with dbcop_free_string. */
+int dbcop_check(const char *history_json, const char *level, char **out_result_json);
+
+/* Frees a string written by dbcop_check. Does nothing on null. */
+void dbcop_free_string(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
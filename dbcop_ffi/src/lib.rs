@@ -0,0 +1,176 @@
+//! C bindings of the consistency checker, with JSON in and JSON out.
+//!
+//! [`dbcop_check`] takes a history, as the JSON of a `Vec<Session<u64, u64>>`, and the name of a
+//! level, such as `snapshot_isolation`. It writes the result of the check as the JSON of a
+//! `Result<Witness, Error<u64, u64>>`, that is, `{"Ok": <witness>}` or `{"Err": <error>}`. The
+//! caller owns the written string and frees it with [`dbcop_free_string`].
+//!
+//! A panic of the checker does not unwind into the caller, which may not be Rust, but is returned
+//! as [`DBCOP_INTERNAL_ERROR`].
+//!
+//! The C declarations are in `include/dbcop.h`.
+
+use std::any::Any;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use dbcop_core::check::check;
+use dbcop_core::history::non_atomic::types::Session;
+use dbcop_core::Consistency;
+use serde_json::Value;
+
+/// The history was checked, and the result is written.
+pub const DBCOP_OK: c_int = 0;
+/// An argument is null, not UTF-8, or not a history or a level. An error message is written, as
+/// `{"error": "<message>"}`.
+pub const DBCOP_INVALID_ARGUMENT: c_int = 1;
+/// The checker failed on a bug rather than returning, which is reported instead of unwinding into
+/// the caller. An error message is written, as `{"error": "<message>"}`.
+pub const DBCOP_INTERNAL_ERROR: c_int = 2;
+
+fn check_json(history_json: &str, level: &str) -> Result<String, String> {
+    let histories: Vec<Session<u64, u64>> =
+        serde_json::from_str(history_json).map_err(|error| format!("invalid history: {error}"))?;
+    let level: Consistency = serde_json::from_value(Value::String(level.to_owned()))
+        .map_err(|_| format!("unknown level {level}"))?;
+    serde_json::to_string(&check(&histories, level)).map_err(|error| error.to_string())
+}
+
+/// Runs `check` and returns the code and the JSON to write. A panic is caught, as unwinding out of
+/// an `extern "C"` function is undefined behavior.
+fn respond(check: impl FnOnce() -> Result<String, String>) -> (c_int, String) {
+    let error = |message: String| serde_json::json!({ "error": message }).to_string();
+    match panic::catch_unwind(AssertUnwindSafe(check)) {
+        Ok(Ok(json)) => (DBCOP_OK, json),
+        Ok(Err(message)) => (DBCOP_INVALID_ARGUMENT, error(message)),
+        Err(payload) => (
+            DBCOP_INTERNAL_ERROR,
+            error(format!("internal error: {}", panic_message(&*payload))),
+        ),
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("panic")
+}
+
+/// # Safety
+///
+/// `string` must be null or point to a NUL-terminated string that outlives `'a`.
+unsafe fn read<'a>(string: *const c_char) -> Option<&'a str> {
+    if string.is_null() {
+        return None;
+    }
+    unsafe { CStr::from_ptr(string) }.to_str().ok()
+}
+
+/// Checks a JSON history against a level, and writes the JSON result to `out_result_json`, unless
+/// it is null. Returns [`DBCOP_OK`], [`DBCOP_INVALID_ARGUMENT`] or [`DBCOP_INTERNAL_ERROR`].
+///
+/// # Safety
+///
+/// `history_json` and `level` must be null or point to NUL-terminated strings, and
+/// `out_result_json` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn dbcop_check(
+    history_json: *const c_char,
+    level: *const c_char,
+    out_result_json: *mut *mut c_char,
+) -> c_int {
+    let (code, json) = respond(|| {
+        unsafe { read(history_json).zip(read(level)) }
+            .ok_or_else(|| "null or non UTF-8 argument".to_owned())
+            .and_then(|(history_json, level)| check_json(history_json, level))
+    });
+    if !out_result_json.is_null() {
+        // JSON escapes NUL, so the conversion does not fail
+        let json = CString::new(json).map_or(ptr::null_mut(), CString::into_raw);
+        unsafe { *out_result_json = json };
+    }
+    code
+}
+
+/// Frees a string written by [`dbcop_check`]. Does nothing on null.
+///
+/// # Safety
+///
+/// `string` must be null or a string written by [`dbcop_check`], not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn dbcop_free_string(string: *mut c_char) {
+    if !string.is_null() {
+        drop(unsafe { CString::from_raw(string) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dbcop_core::history::non_atomic::types::{Event, Transaction};
+
+    use super::*;
+
+    /// Calls [`dbcop_check`] and returns its code and result.
+    fn call(history_json: &str, level: &str) -> (c_int, Value) {
+        let history_json = CString::new(history_json).unwrap();
+        let level = CString::new(level).unwrap();
+        let mut out = ptr::null_mut();
+        let code = unsafe { dbcop_check(history_json.as_ptr(), level.as_ptr(), &mut out) };
+        let result = serde_json::from_str(unsafe { CStr::from_ptr(out) }.to_str().unwrap());
+        unsafe { dbcop_free_string(out) };
+        (code, result.unwrap())
+    }
+
+    #[test]
+    fn test_dbcop_check() {
+        // write skew: snapshot isolation, but not serializable
+        let histories: Vec<Session<u64, u64>> = vec![
+            vec![Transaction::committed(vec![
+                Event::read_empty(0),
+                Event::read_empty(1),
+                Event::write(0, 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read_empty(0),
+                Event::read_empty(1),
+                Event::write(1, 1),
+            ])],
+        ];
+        let history_json = serde_json::to_string(&histories).unwrap();
+
+        let (code, result) = call(&history_json, "snapshot_isolation");
+        assert_eq!(code, DBCOP_OK);
+        assert!(result.get("Ok").is_some());
+
+        let (code, result) = call(&history_json, "serializable");
+        assert_eq!(code, DBCOP_OK);
+        assert_eq!(result["Err"]["invalid"], "serializable");
+
+        let (code, result) = call(&history_json, "linearizable");
+        assert_eq!(code, DBCOP_INVALID_ARGUMENT);
+        assert_eq!(result["error"], "unknown level linearizable");
+
+        let (code, _) = call("[", "serializable");
+        assert_eq!(code, DBCOP_INVALID_ARGUMENT);
+
+        assert_eq!(
+            unsafe { dbcop_check(ptr::null(), ptr::null(), ptr::null_mut()) },
+            DBCOP_INVALID_ARGUMENT
+        );
+    }
+
+    #[test]
+    fn test_internal_error() {
+        let (code, json) = respond(|| panic!("unreachable state"));
+        assert_eq!(code, DBCOP_INTERNAL_ERROR);
+        let result: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(result["error"], "internal error: unreachable state");
+
+        let (code, json) = respond(|| panic!("{} sessions", 2));
+        assert_eq!(code, DBCOP_INTERNAL_ERROR);
+        assert!(json.contains("internal error: 2 sessions"));
+    }
+}
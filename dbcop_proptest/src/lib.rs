@@ -0,0 +1,4 @@
+//! Property-based testing strategies and reusable properties for the consistency checkers.
+
+pub mod properties;
+pub mod strategy;
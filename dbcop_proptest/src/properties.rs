@@ -0,0 +1,43 @@
+//! Properties expected to hold for every history.
+
+use core::fmt::Debug;
+use core::hash::Hash;
+
+use dbcop_core::check::{check, CheckSession};
+use dbcop_core::history::non_atomic::types::Session;
+use dbcop_core::Consistency;
+
+/// Returns true if the history satisfies the consistency level.
+#[must_use]
+pub fn satisfies<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+    level: Consistency,
+) -> bool
+where
+    Variable: Eq + Ord + Hash + Clone + Debug,
+    Version: Eq + Hash + Clone,
+{
+    check(histories, level).is_ok()
+}
+
+/// Checks that a history satisfying a level satisfies every weaker level.
+///
+/// # Errors
+///
+/// Returns `(weaker, stronger)` if the history satisfies `stronger` but not `weaker`.
+pub fn hierarchy_is_monotone<Variable, Version>(
+    histories: &[Session<Variable, Version>],
+) -> Result<(), (Consistency, Consistency)>
+where
+    Variable: Eq + Ord + Hash + Clone + Debug,
+    Version: Eq + Hash + Clone,
+{
+    let mut session = CheckSession::new(histories);
+    let verdicts = Consistency::LEVELS.map(|level| (level, session.check(level).is_ok()));
+    for (i, &(weaker, weaker_ok)) in verdicts.iter().enumerate() {
+        if let Some(&(stronger, _)) = verdicts[i + 1..].iter().find(|(_, ok)| *ok && !weaker_ok) {
+            return Err((weaker, stronger));
+        }
+    }
+    Ok(())
+}
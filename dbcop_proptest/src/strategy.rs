@@ -0,0 +1,223 @@
+//! Strategies generating histories of committed transactions over `u64` variables and versions.
+//!
+//! A history is generated from a random schedule: a sequence of transactions, each assigned to a session.
+//! The schedule is executed serially, each read observing the latest version of its variable.
+//! [`serial_history`] returns the execution as is, so it is serializable.
+//! [`arbitrary_history`] additionally lets some reads observe an older version, which usually breaks
+//! some of the consistency levels.
+//! [`mutated_history`] applies random mutations to a given history, for differential testing of the
+//! checkers on histories that are not even valid.
+
+use std::collections::HashMap;
+
+use dbcop_core::history::non_atomic::types::{Event, Session, Transaction};
+use proptest::collection::vec;
+use proptest::option;
+use proptest::prelude::{any, prop_oneof, Just, Strategy};
+use proptest::sample::Index;
+
+/// Bounds of the generated histories.
+#[derive(Debug, Clone, Copy)]
+pub struct HistoryShape {
+    pub n_session: usize,
+    pub n_transaction: usize,
+    pub n_event: usize,
+    pub n_variable: u64,
+}
+
+impl Default for HistoryShape {
+    fn default() -> Self {
+        Self {
+            n_session: 4,
+            n_transaction: 12,
+            n_event: 4,
+            n_variable: 3,
+        }
+    }
+}
+
+/// A transaction of the schedule: its session and its events as `(is_read, variable)`.
+type Schedule = Vec<(usize, Vec<(bool, u64)>)>;
+
+fn schedule(shape: HistoryShape) -> impl Strategy<Value = Schedule> {
+    vec(
+        (
+            0..shape.n_session,
+            vec((any::<bool>(), 0..shape.n_variable), 1..=shape.n_event),
+        ),
+        1..=shape.n_transaction,
+    )
+}
+
+/// Executes the schedule serially.
+/// The `i`-th read observes the version chosen by `stale_reads[i]` among the versions written so far,
+/// or the latest version if it is `None`.
+fn execute(schedule: Schedule, stale_reads: &[Option<Index>]) -> Vec<Session<u64, u64>> {
+    let mut sessions: Vec<Session<u64, u64>> = Vec::new();
+    // versions of each variable, in the order they were written
+    let mut versions: HashMap<u64, Vec<u64>> = HashMap::new();
+    let mut stale_reads = stale_reads.iter();
+
+    for (session, events) in schedule {
+        if sessions.len() <= session {
+            sessions.resize_with(session + 1, Vec::new);
+        }
+        let events = events
+            .into_iter()
+            .map(|(is_read, variable)| {
+                let written = versions.entry(variable).or_default();
+                if is_read {
+                    let version = stale_reads.next().copied().flatten().map_or_else(
+                        || written.last().copied(),
+                        // index 0 picks the initial version
+                        |index| {
+                            index
+                                .index(written.len() + 1)
+                                .checked_sub(1)
+                                .map(|i| written[i])
+                        },
+                    );
+                    Event::Read { variable, version }
+                } else {
+                    let version = written.len() as u64 + 1;
+                    written.push(version);
+                    Event::write(variable, version)
+                }
+            })
+            .collect();
+        sessions[session].push(Transaction::committed(events));
+    }
+
+    sessions.retain(|session| !session.is_empty());
+    sessions
+}
+
+/// Histories from a serial execution. These satisfy every consistency level.
+pub fn serial_history(shape: HistoryShape) -> impl Strategy<Value = Vec<Session<u64, u64>>> {
+    schedule(shape).prop_map(|schedule| execute(schedule, &[]))
+}
+
+/// Histories from a serial execution, where some of the reads observe an older version.
+pub fn arbitrary_history(shape: HistoryShape) -> impl Strategy<Value = Vec<Session<u64, u64>>> {
+    let n_read = shape.n_transaction * shape.n_event;
+    (
+        schedule(shape),
+        vec(option::weighted(0.3, any::<Index>()), n_read),
+    )
+        .prop_map(|(schedule, stale_reads)| execute(schedule, &stale_reads))
+}
+
+/// A random change to a history.
+#[derive(Debug, Clone)]
+enum Mutation {
+    /// Makes a read observe another version of its variable, or the initial version
+    Reread(Index, Index),
+    /// Removes an event
+    Drop(Index),
+    /// Aborts a transaction
+    Abort(Index),
+    /// Swaps a transaction with the next one of its session
+    Swap(Index),
+}
+
+fn mutation() -> impl Strategy<Value = Mutation> {
+    prop_oneof![
+        (any::<Index>(), any::<Index>())
+            .prop_map(|(read, version)| Mutation::Reread(read, version)),
+        any::<Index>().prop_map(Mutation::Drop),
+        any::<Index>().prop_map(Mutation::Abort),
+        any::<Index>().prop_map(Mutation::Swap),
+    ]
+}
+
+/// Applies a mutation. A mutation that has nothing to change, such as a reread in a history
+/// without reads, does nothing.
+fn mutate(histories: &mut [Session<u64, u64>], mutation: &Mutation) {
+    let events: Vec<(usize, usize, usize)> = histories
+        .iter()
+        .enumerate()
+        .flat_map(|(s, session)| {
+            session
+                .iter()
+                .enumerate()
+                .flat_map(move |(t, transaction)| {
+                    (0..transaction.events.len()).map(move |e| (s, t, e))
+                })
+        })
+        .collect();
+    let transactions: Vec<(usize, usize)> = histories
+        .iter()
+        .enumerate()
+        .flat_map(|(s, session)| (0..session.len()).map(move |t| (s, t)))
+        .collect();
+
+    match mutation {
+        Mutation::Reread(read, version) => {
+            let reads: Vec<&(usize, usize, usize)> = events
+                .iter()
+                .filter(|(s, t, e)| matches!(histories[*s][*t].events[*e], Event::Read { .. }))
+                .collect();
+            if reads.is_empty() {
+                return;
+            }
+            let &(s, t, e) = reads[read.index(reads.len())];
+            let Event::Read { variable, .. } = histories[s][t].events[e] else {
+                return;
+            };
+            let versions: Vec<u64> = histories
+                .iter()
+                .flatten()
+                .flat_map(|transaction| &transaction.events)
+                .filter_map(|event| match event {
+                    Event::Write {
+                        variable: written,
+                        version,
+                    } if *written == variable => Some(*version),
+                    _ => None,
+                })
+                .collect();
+            // index 0 picks the initial version
+            let version = version
+                .index(versions.len() + 1)
+                .checked_sub(1)
+                .map(|i| versions[i]);
+            histories[s][t].events[e] = Event::Read { variable, version };
+        }
+        Mutation::Drop(event) => {
+            if !events.is_empty() {
+                let (s, t, e) = events[event.index(events.len())];
+                histories[s][t].events.remove(e);
+            }
+        }
+        Mutation::Abort(transaction) => {
+            if !transactions.is_empty() {
+                let (s, t) = transactions[transaction.index(transactions.len())];
+                histories[s][t].committed = false;
+            }
+        }
+        Mutation::Swap(transaction) => {
+            let swappable: Vec<&(usize, usize)> = transactions
+                .iter()
+                .filter(|(s, t)| t + 1 < histories[*s].len())
+                .collect();
+            if !swappable.is_empty() {
+                let &(s, t) = swappable[transaction.index(swappable.len())];
+                histories[s].swap(t, t + 1);
+            }
+        }
+    }
+}
+
+/// Histories from `seed` with up to `n_mutation` random mutations, such as a read of another
+/// version, a removed event, an aborted transaction or two swapped transactions.
+pub fn mutated_history(
+    seed: Vec<Session<u64, u64>>,
+    n_mutation: usize,
+) -> impl Strategy<Value = Vec<Session<u64, u64>>> {
+    (Just(seed), vec(mutation(), 1..=n_mutation)).prop_map(|(mut histories, mutations)| {
+        for mutation in &mutations {
+            mutate(&mut histories, mutation);
+        }
+        histories
+    })
+}
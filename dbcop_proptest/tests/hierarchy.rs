@@ -0,0 +1,200 @@
+use dbcop_core::check::{check, verify_witness, Backend, CheckSession, Witness};
+use dbcop_core::solver::causal::check_causal_read;
+use dbcop_core::solver::constrained_linearization::ConstrainedLinearizationSolver;
+use dbcop_core::solver::prefix::PrefixConsistencySolver;
+use dbcop_core::solver::saturation::SaturationDebugger;
+use dbcop_core::solver::serializable::SerializabilitySolver;
+use dbcop_core::solver::snapshot_isolation::SnapshotIsolationSolver;
+use dbcop_core::Consistency;
+use dbcop_proptest::properties::{hierarchy_is_monotone, satisfies};
+use dbcop_proptest::strategy::{arbitrary_history, mutated_history, serial_history, HistoryShape};
+use proptest::prelude::*;
+
+proptest! {
+    #[test]
+    fn serial_histories_satisfy_every_level(histories in serial_history(HistoryShape::default())) {
+        for level in Consistency::LEVELS {
+            prop_assert!(satisfies(&histories, level), "{level:?} failed on {histories:?}");
+        }
+    }
+
+    #[test]
+    fn hierarchy_is_monotone_on_arbitrary_histories(
+        histories in arbitrary_history(HistoryShape::default())
+    ) {
+        prop_assert_eq!(hierarchy_is_monotone(&histories), Ok(()), "{:?}", histories);
+    }
+
+    #[test]
+    fn saturation_debugger_agrees_with_checkers(
+        histories in arbitrary_history(HistoryShape::default())
+    ) {
+        for level in [
+            Consistency::CommittedRead,
+            Consistency::RepeatableRead,
+            Consistency::AtomicRead,
+            Consistency::Causal,
+        ] {
+            let verdict = SaturationDebugger::new(&histories, level)
+                .ok()
+                .and_then(Iterator::last)
+                .is_some_and(|step| step.acyclic);
+            prop_assert_eq!(verdict, satisfies(&histories, level), "{:?}: {:?}", level, histories);
+        }
+    }
+
+    #[test]
+    fn parallel_linearization_agrees_with_sequential(
+        histories in arbitrary_history(HistoryShape::default())
+    ) {
+        if let Ok(po) = check_causal_read(&histories) {
+            let mut prefix = PrefixConsistencySolver::from(po.clone());
+            prop_assert_eq!(
+                prefix.get_linearization_parallel().is_some(),
+                prefix.get_linearization().is_some()
+            );
+            let mut snapshot_isolation = SnapshotIsolationSolver::from(po.clone());
+            prop_assert_eq!(
+                snapshot_isolation.get_linearization_parallel().is_some(),
+                snapshot_isolation.get_linearization().is_some()
+            );
+            let mut serializable = SerializabilitySolver::from(po);
+            prop_assert_eq!(
+                serializable.get_linearization_parallel().is_some(),
+                serializable.get_linearization().is_some()
+            );
+        }
+    }
+
+    #[test]
+    fn pruning_preserves_verdicts(histories in arbitrary_history(HistoryShape::default())) {
+        if let Ok(po) = check_causal_read(&histories) {
+            let transactions = po.history.0.len();
+            let commit_order = |witness| match witness {
+                Witness::CommitOrder(order) => order.len(),
+                Witness::SplitCommitOrder(order) => order.len() / 2,
+                Witness::Saturated => 0,
+                Witness::OperationOrder(_) => unreachable!("operation witnesses are not requested"),
+            };
+
+            let verdict = check(&histories, Consistency::Prefix).map(commit_order);
+            prop_assert_eq!(
+                verdict.is_ok(),
+                PrefixConsistencySolver::from(po.clone()).get_linearization().is_some()
+            );
+            prop_assert!(verdict.map_or(true, |length| length == transactions));
+
+            let verdict = check(&histories, Consistency::SnapshotIsolation).map(commit_order);
+            prop_assert_eq!(
+                verdict.is_ok(),
+                SnapshotIsolationSolver::from(po.clone()).get_linearization().is_some()
+            );
+            prop_assert!(verdict.map_or(true, |length| length == transactions));
+
+            let verdict = check(&histories, Consistency::Serializable).map(commit_order);
+            prop_assert_eq!(
+                verdict.is_ok(),
+                SerializabilitySolver::from(po).get_linearization().is_some()
+            );
+            prop_assert!(verdict.map_or(true, |length| length == transactions));
+        }
+    }
+
+    #[test]
+    fn polygraph_agrees_with_linearization(
+        histories in arbitrary_history(HistoryShape::default())
+    ) {
+        let polygraph = CheckSession::new(&histories)
+            .with_backend(Backend::Polygraph)
+            .check(Consistency::Serializable);
+        prop_assert_eq!(polygraph.is_ok(), satisfies(&histories, Consistency::Serializable));
+        if let (Ok(Witness::CommitOrder(order)), Ok(po)) =
+            (polygraph, check_causal_read(&histories))
+        {
+            prop_assert_eq!(order.len(), po.history.0.len());
+        }
+    }
+
+    #[test]
+    fn bounded_memo_preserves_verdicts(histories in arbitrary_history(HistoryShape::default())) {
+        let mut session = CheckSession::new(&histories).with_memo_capacity(2);
+        for level in [
+            Consistency::Prefix,
+            Consistency::SnapshotIsolation,
+            Consistency::Serializable,
+        ] {
+            let witness = session.check(level);
+            prop_assert_eq!(witness.is_ok(), satisfies(&histories, level));
+            if let Ok(witness) = witness {
+                prop_assert!(verify_witness(&histories, level, &witness));
+            }
+        }
+    }
+
+    #[test]
+    fn canonical_witness_agrees_with_search(
+        histories in arbitrary_history(HistoryShape::default())
+    ) {
+        let mut session = CheckSession::new(&histories).with_canonical_witness(true);
+        for level in [
+            Consistency::Prefix,
+            Consistency::SnapshotIsolation,
+            Consistency::Serializable,
+        ] {
+            prop_assert_eq!(session.check(level).is_ok(), satisfies(&histories, level));
+        }
+    }
+
+    #[test]
+    fn witnesses_verify(histories in arbitrary_history(HistoryShape::default())) {
+        for level in Consistency::LEVELS {
+            if let Ok(witness) = check(&histories, level) {
+                prop_assert!(
+                    verify_witness(&histories, level, &witness),
+                    "{:?}: {} on {:?}", level, witness, histories
+                );
+            }
+        }
+        if let Ok(witness) = CheckSession::new(&histories)
+            .with_backend(Backend::Polygraph)
+            .check(Consistency::Serializable)
+        {
+            prop_assert!(verify_witness(&histories, Consistency::Serializable, &witness));
+        }
+    }
+
+    #[test]
+    fn checkers_agree_on_mutated_histories(
+        histories in arbitrary_history(HistoryShape::default())
+            .prop_flat_map(|seed| mutated_history(seed, 4))
+    ) {
+        let mut session = CheckSession::new(&histories);
+        let mut polygraph = CheckSession::new(&histories).with_backend(Backend::Polygraph);
+        let mut canonical = CheckSession::new(&histories).with_canonical_witness(true);
+        for level in Consistency::LEVELS {
+            let verdict = session.check(level);
+            if let Ok(witness) = &verdict {
+                prop_assert!(verify_witness(&histories, level, witness), "{:?}", histories);
+            }
+            prop_assert_eq!(
+                polygraph.check(level).is_ok(),
+                verdict.is_ok(),
+                "{:?}: {:?}", level, histories
+            );
+            prop_assert_eq!(
+                canonical.check(level).is_ok(),
+                verdict.is_ok(),
+                "{:?}: {:?}", level, histories
+            );
+            // small chunks, to resume the search many times
+            let mut chunked = session.check_chunked(level);
+            let report = loop {
+                if let Some(report) = chunked.step(4) {
+                    break report;
+                }
+            };
+            prop_assert_eq!(report.result.is_ok(), verdict.is_ok(), "{:?}: {:?}", level, histories);
+        }
+        prop_assert_eq!(hierarchy_is_monotone(&histories), Ok(()), "{:?}", histories);
+    }
+}
@@ -0,0 +1,187 @@
+//! Benchmarks of the consistency checkers on serializable histories of increasing size.
+//!
+//! The histories are generated by the testgen generator and executed serially, round-robin
+//! over the sessions, so every checker has to explore a consistent history completely.
+
+use std::collections::HashMap;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use dbcop_core::graph::digraph::DiGraph;
+use dbcop_core::history::non_atomic::types::{Event, Session, Transaction};
+use dbcop_core::solver::atomic_read::check_atomic_read;
+use dbcop_core::solver::causal::check_causal_read;
+use dbcop_core::solver::committed_read::check_committed_read;
+use dbcop_core::solver::constrained_linearization::ConstrainedLinearizationSolver;
+use dbcop_core::solver::prefix::PrefixConsistencySolver;
+use dbcop_core::solver::serializable::SerializabilitySolver;
+use dbcop_core::solver::snapshot_isolation::SnapshotIsolationSolver;
+use dbcop_testgen::generator::{generate_single_history_with_config, GeneratorConfig};
+
+/// `(n_node, n_transaction)` of the benchmarked histories.
+const SIZES: [(u64, u64); 3] = [(3, 10), (5, 20), (8, 30)];
+
+/// Executes the generated transactions serially, round-robin over the sessions.
+fn execute(plan: Vec<Session<u64, u64>>) -> Vec<Session<u64, u64>> {
+    let mut latest: HashMap<u64, u64> = HashMap::new();
+    let mut sessions: Vec<Session<u64, u64>> = plan.iter().map(|_| Vec::new()).collect();
+    let mut plan: Vec<_> = plan.into_iter().map(IntoIterator::into_iter).collect();
+    while plan.iter().any(|session| session.len() > 0) {
+        for (session, transactions) in sessions.iter_mut().zip(&mut plan) {
+            if let Some(transaction) = transactions.next() {
+                let events = transaction
+                    .events
+                    .into_iter()
+                    .map(|event| match event {
+                        Event::Read { variable, .. } => Event::Read {
+                            version: latest.get(&variable).copied(),
+                            variable,
+                        },
+                        Event::Write { variable, version } => {
+                            latest.insert(variable, version);
+                            Event::Write { variable, version }
+                        }
+                    })
+                    .collect();
+                session.push(Transaction::committed(events));
+            }
+        }
+    }
+    sessions
+}
+
+fn histories() -> Vec<(String, Vec<Session<u64, u64>>)> {
+    SIZES
+        .iter()
+        .map(|&(n_node, n_transaction)| {
+            let config = GeneratorConfig::builder().seed(n_node).build();
+            let plan = generate_single_history_with_config(n_node, 10, n_transaction, 4, &config);
+            (format!("{n_node}x{n_transaction}"), execute(plan))
+        })
+        .collect()
+}
+
+fn saturation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("saturation");
+    for (size, history) in &histories() {
+        group.bench_with_input(BenchmarkId::new("committed_read", size), history, |b, h| {
+            b.iter(|| check_committed_read(h).unwrap());
+        });
+        group.bench_with_input(BenchmarkId::new("atomic_read", size), history, |b, h| {
+            b.iter(|| check_atomic_read(h).unwrap());
+        });
+        group.bench_with_input(BenchmarkId::new("causal", size), history, |b, h| {
+            b.iter(|| check_causal_read(h).unwrap());
+        });
+    }
+    group.finish();
+}
+
+fn linearization(c: &mut Criterion) {
+    let mut group = c.benchmark_group("linearization");
+    group.sample_size(10);
+    for (size, history) in &histories() {
+        group.bench_with_input(BenchmarkId::new("prefix", size), history, |b, h| {
+            b.iter(|| {
+                PrefixConsistencySolver::from(check_causal_read(h).unwrap())
+                    .get_linearization()
+                    .unwrap()
+            });
+        });
+        group.bench_with_input(
+            BenchmarkId::new("snapshot_isolation", size),
+            history,
+            |b, h| {
+                b.iter(|| {
+                    SnapshotIsolationSolver::from(check_causal_read(h).unwrap())
+                        .get_linearization()
+                        .unwrap()
+                });
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("serializable", size), history, |b, h| {
+            b.iter(|| {
+                SerializabilitySolver::from(check_causal_read(h).unwrap())
+                    .get_linearization()
+                    .unwrap()
+            });
+        });
+    }
+    group.finish();
+}
+
+fn graph(c: &mut Criterion) {
+    let mut group = c.benchmark_group("graph");
+    for n in [32_u64, 128, 512] {
+        // a chain with a few forward shortcuts
+        let mut graph: DiGraph<u64> = DiGraph::default();
+        for i in 0..n {
+            graph.add_edge(i, i + 1);
+            graph.add_edge(i, i + 2 + i % 5);
+        }
+        let closure = graph.closure();
+        group.bench_with_input(BenchmarkId::new("closure", n), &graph, |b, g| {
+            b.iter(|| g.closure());
+        });
+        group.bench_with_input(BenchmarkId::new("has_cycle", n), &closure, |b, g| {
+            b.iter(|| g.has_cycle());
+        });
+        // the same chain, shifted, so that the union adds a few edges to every vertex
+        let mut shifted: DiGraph<u64> = DiGraph::default();
+        for i in 0..n {
+            shifted.add_edge(i, i + 3);
+        }
+        group.bench_with_input(BenchmarkId::new("union", n), &graph, |b, g| {
+            b.iter(|| g.clone().union(&shifted));
+        });
+    }
+    group.finish();
+}
+
+/// A chain with a few forward shortcuts, whose closure has about half of all the pairs.
+fn chain(n: u64) -> DiGraph<u64> {
+    let mut graph = DiGraph::default();
+    for i in 0..n {
+        graph.add_edge(i, i + 1);
+        graph.add_edge(i, i + 2 + i % 5);
+    }
+    graph
+}
+
+/// Disjoint paths of four vertices, whose closure stays sparse.
+fn paths(n: u64) -> DiGraph<u64> {
+    let mut graph = DiGraph::default();
+    for i in (0..n).filter(|i| i % 4 != 3) {
+        graph.add_edge(i, i + 1);
+    }
+    graph
+}
+
+/// Compares the closure over the adjacency sets with the closure over the bit matrix on both sides
+/// of the thresholds of [`DiGraph::closure`]: chains, whose closure is dense, around
+/// [`DENSE_CLOSURE_THRESHOLD`](dbcop_core::graph::digraph::DENSE_CLOSURE_THRESHOLD) vertices, and
+/// short paths, whose closure is sparse, around
+/// [`DENSE_CLOSURE_MAX_VERTICES`](dbcop_core::graph::digraph::DENSE_CLOSURE_MAX_VERTICES).
+fn closure(c: &mut Criterion) {
+    let mut group = c.benchmark_group("closure");
+    group.sample_size(10);
+    let graphs = [16, 32, 64, 128, 256, 512, 1024, 2048]
+        .map(|n| ("chain", n, chain(n)))
+        .into_iter()
+        .chain([1024, 4096, 8192, 16384].map(|n| ("paths", n, paths(n))));
+    for (shape, n, graph) in graphs {
+        group.bench_with_input(
+            BenchmarkId::new(format!("{shape}/sparse"), n),
+            &graph,
+            |b, g| b.iter(|| g.sparse_closure()),
+        );
+        group.bench_with_input(
+            BenchmarkId::new(format!("{shape}/dense"), n),
+            &graph,
+            |b, g| b.iter(|| g.dense_closure()),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, saturation, linearization, graph, closure);
+criterion_main!(benches);
@@ -0,0 +1,345 @@
+//! Compact binary format for large corpora of histories.
+//!
+//! A file starts with a header: the magic bytes `DBCOP`, the format version and the kind of its
+//! records. Each record follows as a little-endian `u32` length and a postcard payload, so a
+//! [`Reader`] loads one record at a time.
+//!
+//! - [`Kind::Sessions`]: each record is a `Vec<Session<u64, u64>>`.
+//! - [`Kind::Histories`]: each record is a [`History`]. Its sessions are encoded with postcard and
+//!   the rest of it, which is small, as JSON.
+
+use std::fmt::{Display, Formatter};
+use std::io::{ErrorKind, Read, Write};
+
+use dbcop_core::history::non_atomic::types::Session;
+
+use crate::generator::History;
+
+pub const MAGIC: &[u8; 5] = b"DBCOP";
+pub const FORMAT_VERSION: u8 = 3;
+
+/// The type of the records of a file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Sessions,
+    Histories,
+}
+
+impl Kind {
+    const fn to_byte(self) -> u8 {
+        match self {
+            Self::Sessions => 0,
+            Self::Histories => 1,
+        }
+    }
+
+    const fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Self::Sessions),
+            1 => Some(Self::Histories),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    Postcard(postcard::Error),
+    Json(serde_json::Error),
+    /// The input does not start with [`MAGIC`]
+    BadMagic,
+    /// The format version or the kind of records is unknown
+    UnsupportedHeader {
+        version: u8,
+        kind: u8,
+    },
+    /// A history is written to or read from a file of sessions
+    WrongKind {
+        expected: Kind,
+        found: Kind,
+    },
+    /// A record is larger than 4 GiB
+    RecordTooLarge {
+        len: usize,
+    },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "io error: {error}"),
+            Self::Postcard(error) => write!(f, "invalid record: {error}"),
+            Self::Json(error) => write!(f, "invalid history metadata: {error}"),
+            Self::BadMagic => write!(f, "not a dbcop binary file"),
+            Self::UnsupportedHeader { version, kind } => {
+                write!(f, "unsupported format version {version} with kind {kind}")
+            }
+            Self::WrongKind { expected, found } => {
+                write!(f, "expected records of {expected:?}, found {found:?}")
+            }
+            Self::RecordTooLarge { len } => write!(f, "record of {len} bytes is too large"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<postcard::Error> for Error {
+    fn from(error: postcard::Error) -> Self {
+        Self::Postcard(error)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Json(error)
+    }
+}
+
+/// Writes records after a header.
+#[derive(Debug)]
+pub struct Writer<W> {
+    inner: W,
+    kind: Kind,
+}
+
+impl<W: Write> Writer<W> {
+    /// Writes the header.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if writing fails.
+    pub fn new(mut writer: W, kind: Kind) -> Result<Self, Error> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[FORMAT_VERSION, kind.to_byte()])?;
+        Ok(Self {
+            inner: writer,
+            kind,
+        })
+    }
+
+    fn write_record(&mut self, payload: &[u8]) -> Result<(), Error> {
+        let len = u32::try_from(payload.len())
+            .map_err(|_| Error::RecordTooLarge { len: payload.len() })?;
+        self.inner.write_all(&len.to_le_bytes())?;
+        self.inner.write_all(payload)?;
+        Ok(())
+    }
+
+    /// Appends the sessions of a history to a file of [`Kind::Sessions`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the file is of another kind or writing fails.
+    pub fn write_sessions(&mut self, histories: &[Session<u64, u64>]) -> Result<(), Error> {
+        if self.kind != Kind::Sessions {
+            return Err(Error::WrongKind {
+                expected: self.kind,
+                found: Kind::Sessions,
+            });
+        }
+        self.write_record(&postcard::to_stdvec(histories)?)
+    }
+
+    /// Appends a history to a file of [`Kind::Histories`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the file is of another kind or writing fails.
+    pub fn write_history(&mut self, history: &History) -> Result<(), Error> {
+        if self.kind != Kind::Histories {
+            return Err(Error::WrongKind {
+                expected: self.kind,
+                found: Kind::Histories,
+            });
+        }
+        let metadata = history.metadata_json()?;
+        self.write_record(&postcard::to_stdvec(&(metadata, history.get_data()))?)
+    }
+
+    /// Flushes and returns the underlying writer.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if flushing fails.
+    pub fn into_inner(mut self) -> Result<W, Error> {
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+/// Reads records one at a time after checking the header.
+#[derive(Debug)]
+pub struct Reader<R> {
+    inner: R,
+    kind: Kind,
+    buffer: Vec<u8>,
+}
+
+impl<R: Read> Reader<R> {
+    /// Reads the header.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the header is missing or unsupported.
+    pub fn new(mut reader: R) -> Result<Self, Error> {
+        let mut magic = [0; MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(Error::BadMagic);
+        }
+        let mut header = [0; 2];
+        reader.read_exact(&mut header)?;
+        let [version, kind] = header;
+        let kind = Kind::from_byte(kind)
+            .filter(|_| version == FORMAT_VERSION)
+            .ok_or(Error::UnsupportedHeader { version, kind })?;
+        Ok(Self {
+            inner: reader,
+            kind,
+            buffer: Vec::new(),
+        })
+    }
+
+    #[must_use]
+    pub const fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    /// Reads the next record into the buffer. Returns false at the end of the input.
+    fn read_record(&mut self) -> Result<bool, Error> {
+        let mut len = [0; 4];
+        match self.inner.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(error) if error.kind() == ErrorKind::UnexpectedEof => return Ok(false),
+            Err(error) => return Err(error.into()),
+        }
+        let len = u32::from_le_bytes(len) as usize;
+        self.buffer.resize(len, 0);
+        self.inner.read_exact(&mut self.buffer)?;
+        Ok(true)
+    }
+
+    /// Reads the sessions of the next record. The metadata of a history is skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the record is malformed or reading fails.
+    pub fn read_sessions(&mut self) -> Result<Option<Vec<Session<u64, u64>>>, Error> {
+        if !self.read_record()? {
+            return Ok(None);
+        }
+        Ok(Some(match self.kind {
+            Kind::Sessions => postcard::from_bytes(&self.buffer)?,
+            Kind::Histories => postcard::from_bytes::<(&str, _)>(&self.buffer)?.1,
+        }))
+    }
+
+    /// Reads the next history of a file of [`Kind::Histories`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the file is of another kind, the record is malformed or reading
+    /// fails.
+    pub fn read_history(&mut self) -> Result<Option<History>, Error> {
+        if self.kind != Kind::Histories {
+            return Err(Error::WrongKind {
+                expected: Kind::Histories,
+                found: self.kind,
+            });
+        }
+        if !self.read_record()? {
+            return Ok(None);
+        }
+        let (metadata, data): (&str, _) = postcard::from_bytes(&self.buffer)?;
+        Ok(Some(History::from_metadata_json(metadata, data)?))
+    }
+}
+
+impl<R: Read> Iterator for Reader<R> {
+    type Item = Result<Vec<Session<u64, u64>>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_sessions().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Local;
+    use dbcop_core::history::non_atomic::types::{Event, Transaction};
+
+    use super::*;
+    use crate::generator::HistParams;
+
+    fn sessions(version: u64) -> Vec<Session<u64, u64>> {
+        vec![
+            vec![Transaction::committed(vec![
+                Event::write(0, version),
+                Event::read_empty(1),
+            ])],
+            vec![Transaction::uncommitted(vec![Event::read(0, version)])],
+        ]
+    }
+
+    #[test]
+    fn test_sessions_roundtrip() {
+        let mut writer = Writer::new(Vec::new(), Kind::Sessions).unwrap();
+        writer.write_sessions(&sessions(1)).unwrap();
+        writer.write_sessions(&sessions(2)).unwrap();
+        let bytes = writer.into_inner().unwrap();
+
+        assert!(bytes.starts_with(b"DBCOP\x03\x00"));
+
+        let records: Vec<_> = Reader::new(bytes.as_slice())
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1][0][0].events, sessions(2)[0][0].events);
+        assert!(!records[1][1][0].committed);
+    }
+
+    #[test]
+    fn test_history_roundtrip() {
+        let params = HistParams::builder()
+            .id(7)
+            .n_node(2)
+            .n_variable(2)
+            .n_transaction(1)
+            .n_event(2)
+            .build();
+        let now = Local::now();
+        let history = History::new(params, "test".to_owned(), now, now, sessions(1));
+
+        let mut writer = Writer::new(Vec::new(), Kind::Histories).unwrap();
+        writer.write_history(&history).unwrap();
+        assert!(matches!(
+            writer.write_sessions(&sessions(1)),
+            Err(Error::WrongKind { .. })
+        ));
+        let bytes = writer.into_inner().unwrap();
+
+        let mut reader = Reader::new(bytes.as_slice()).unwrap();
+        let read = reader.read_history().unwrap().unwrap();
+        assert_eq!(read.get_id(), 7);
+        assert_eq!(read.get_duration(), history.get_duration());
+        assert_eq!(
+            read.get_data()[1][0].events,
+            history.get_data()[1][0].events
+        );
+        assert!(reader.read_history().unwrap().is_none());
+
+        assert!(matches!(
+            Reader::new(&b"DBCOP\x04\x00"[..]),
+            Err(Error::UnsupportedHeader { version: 4, .. })
+        ));
+    }
+}
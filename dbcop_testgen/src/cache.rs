@@ -0,0 +1,233 @@
+//! Persistent cache of check results, one JSON file per result in a directory.
+//!
+//! A result is keyed by the content hash of the history, the level, the backend and the version of
+//! the crate, so re-checking a corpus skips the histories already checked, and a new version checks
+//! them all again. The hash is 64-bit FNV-1a of the JSON history, which is stable across platforms
+//! and compiler versions.
+
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use dbcop_core::check::{Backend, CheckSession, Witness};
+use dbcop_core::history::non_atomic::types::Session;
+use dbcop_core::solver::error::Error as CheckError;
+use dbcop_core::Consistency;
+
+/// The outcome of a check, as stored in the cache.
+pub type CheckResult = Result<Witness, CheckError<u64, u64>>;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "cache i/o error: {error}"),
+            Self::Json(error) => write!(f, "invalid cache entry: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Json(error)
+    }
+}
+
+/// Lookups and stores of a cache since it was opened.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub stores: u64,
+}
+
+impl Display for CacheStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} hits, {} misses, {} stores",
+            self.hits, self.misses, self.stores
+        )
+    }
+}
+
+/// A cache of check results in a directory.
+#[derive(Debug)]
+pub struct ResultCache {
+    dir: PathBuf,
+    stats: CacheStats,
+}
+
+impl ResultCache {
+    /// Opens the cache in `dir`, creating the directory if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::Io`] if the directory can not be created.
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self, Error> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            stats: CacheStats::default(),
+        })
+    }
+
+    /// Returns the key of the result of checking `histories` against `level` with `backend`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::Json`] if the history can not be serialized.
+    pub fn key(
+        histories: &[Session<u64, u64>],
+        level: Consistency,
+        backend: Backend,
+    ) -> Result<String, Error> {
+        let hash = fnv1a(&serde_json::to_vec(histories)?);
+        Ok(format!(
+            "{hash:016x}-{level:?}-{backend:?}-{}",
+            env!("CARGO_PKG_VERSION")
+        ))
+    }
+
+    /// Returns the cached result under `key`, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the entry can not be read or parsed.
+    pub fn get(&mut self, key: &str) -> Result<Option<CheckResult>, Error> {
+        match fs::read(self.path(key)) {
+            Ok(bytes) => {
+                self.stats.hits += 1;
+                Ok(Some(serde_json::from_slice(&bytes)?))
+            }
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                self.stats.misses += 1;
+                Ok(None)
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Stores `result` under `key`. The entry is written to a temporary file first, so a reader
+    /// never sees a partial entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the entry can not be written.
+    pub fn insert(&mut self, key: &str, result: &CheckResult) -> Result<(), Error> {
+        let path = self.path(key);
+        let partial = path.with_extension("json.partial");
+        fs::write(&partial, serde_json::to_vec(result)?)?;
+        fs::rename(partial, path)?;
+        self.stats.stores += 1;
+        Ok(())
+    }
+
+    /// Checks `histories` against `level` with `backend`, or returns the cached result.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the cache can not be read or written.
+    pub fn check(
+        &mut self,
+        histories: &[Session<u64, u64>],
+        level: Consistency,
+        backend: Backend,
+    ) -> Result<CheckResult, Error> {
+        let key = Self::key(histories, level, backend)?;
+        if let Some(result) = self.get(&key)? {
+            return Ok(result);
+        }
+        let result = CheckSession::new(histories)
+            .with_backend(backend)
+            .check(level);
+        self.insert(&key, &result)?;
+        Ok(result)
+    }
+
+    #[must_use]
+    pub const fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.json"))
+    }
+}
+
+/// 64-bit FNV-1a
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use dbcop_core::history::non_atomic::types::{Event, Transaction};
+
+    use super::*;
+
+    #[test]
+    fn test_result_cache() {
+        let dir = std::env::temp_dir().join(format!("dbcop-cache-{}", std::process::id()));
+        let mut cache = ResultCache::open(&dir).unwrap();
+
+        // write skew: snapshot isolation, but not serializable
+        let histories = vec![
+            vec![Transaction::committed(vec![
+                Event::read_empty(0),
+                Event::read_empty(1),
+                Event::write(0, 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read_empty(0),
+                Event::read_empty(1),
+                Event::write(1, 1),
+            ])],
+        ];
+        for _ in 0..2 {
+            for level in [Consistency::SnapshotIsolation, Consistency::Serializable] {
+                let result = cache
+                    .check(&histories, level, Backend::Linearization)
+                    .unwrap();
+                assert_eq!(result.is_ok(), level == Consistency::SnapshotIsolation);
+            }
+        }
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 2,
+                misses: 2,
+                stores: 2,
+            }
+        );
+        assert_ne!(
+            ResultCache::key(
+                &histories,
+                Consistency::Serializable,
+                Backend::Linearization
+            )
+            .unwrap(),
+            ResultCache::key(&histories, Consistency::Serializable, Backend::Polygraph).unwrap()
+        );
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,190 @@
+//! Checkpoint of a verification run over a corpus, to resume it after an interruption.
+//!
+//! The checkpoint is a file of JSON lines, one [`Entry`] per completed history, appended and
+//! flushed as soon as the history is checked. An interrupted write leaves at most a partial last
+//! line, which [`Checkpoint::open`] drops, so the history is checked again on resume.
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::Path;
+
+use dbcop_core::Consistency;
+use serde::{Deserialize, Serialize};
+
+use crate::cache::{CheckResult, Error};
+
+/// A completed history of the run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    /// The file of the history, as given to [`Checkpoint::record`]
+    pub file: String,
+    pub level: Consistency,
+    pub result: CheckResult,
+}
+
+/// The histories completed so far, backed by the checkpoint file.
+#[derive(Debug)]
+pub struct Checkpoint {
+    file: File,
+    completed: HashMap<(String, Consistency), CheckResult>,
+}
+
+impl Checkpoint {
+    /// Opens the checkpoint at `path`, creating it if needed, and loads its entries.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::Io`] if the file can not be opened or repaired, or an [`Error::Json`] if
+    /// a complete line is not an entry.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+        let mut content = String::new();
+        file.read_to_string(&mut content)?;
+
+        // a partial last line is a write interrupted before its newline; the file is opened for
+        // appending, so the next entry is written right after the truncated length
+        let complete = content.rfind('\n').map_or(0, |i| i + 1);
+        if complete < content.len() {
+            file.set_len(complete as u64)?;
+        }
+
+        let mut completed = HashMap::new();
+        for line in content[..complete].lines().filter(|line| !line.is_empty()) {
+            let entry: Entry = serde_json::from_str(line)?;
+            completed.insert((entry.file, entry.level), entry.result);
+        }
+        Ok(Self { file, completed })
+    }
+
+    /// Returns the recorded result of checking `file` against `level`, if it is completed.
+    #[must_use]
+    pub fn get(&self, file: &str, level: Consistency) -> Option<&CheckResult> {
+        self.completed.get(&(file.to_owned(), level))
+    }
+
+    /// Records the result of checking `file` against `level`, and flushes it to the disk.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the entry can not be written.
+    pub fn record(
+        &mut self,
+        file: &str,
+        level: Consistency,
+        result: CheckResult,
+    ) -> Result<(), Error> {
+        let entry = Entry {
+            file: file.to_owned(),
+            level,
+            result,
+        };
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        self.file.sync_data()?;
+        self.completed.insert((entry.file, level), entry.result);
+        Ok(())
+    }
+
+    /// Returns the recorded result of checking `file` against `level`, or checks it with `check`
+    /// and records the result.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the entry can not be written.
+    pub fn check(
+        &mut self,
+        file: &str,
+        level: Consistency,
+        check: impl FnOnce() -> CheckResult,
+    ) -> Result<CheckResult, Error> {
+        if let Some(result) = self.get(file, level) {
+            return Ok(result.clone());
+        }
+        let result = check();
+        self.record(file, level, result.clone())?;
+        Ok(result)
+    }
+
+    /// Returns the number of completed histories.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.completed.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.completed.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dbcop_core::check::check;
+    use dbcop_core::history::non_atomic::types::{Event, Transaction};
+
+    use super::*;
+
+    #[test]
+    fn test_checkpoint() {
+        let path =
+            std::env::temp_dir().join(format!("dbcop-checkpoint-{}.jsonl", std::process::id()));
+        // lost update: prefix consistent, but not snapshot isolated
+        let histories = vec![
+            vec![Transaction::committed(vec![
+                Event::read_empty(0),
+                Event::write(0, 1),
+            ])],
+            vec![Transaction::committed(vec![
+                Event::read_empty(0),
+                Event::write(0, 2),
+            ])],
+        ];
+
+        let mut checkpoint = Checkpoint::open(&path).unwrap();
+        assert!(checkpoint.is_empty());
+        for level in [Consistency::Prefix, Consistency::SnapshotIsolation] {
+            let result = checkpoint
+                .check("lost-update.json", level, || check(&histories, level))
+                .unwrap();
+            assert_eq!(result.is_ok(), level == Consistency::Prefix);
+        }
+
+        // interrupted while writing the next entry
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(br#"{"file":"next.json","lev"#)
+            .unwrap();
+
+        let mut checkpoint = Checkpoint::open(&path).unwrap();
+        assert_eq!(checkpoint.len(), 2);
+        let result = checkpoint
+            .check("lost-update.json", Consistency::SnapshotIsolation, || {
+                unreachable!("completed before the interruption")
+            })
+            .unwrap();
+        assert!(result.is_err());
+        checkpoint
+            .record(
+                "next.json",
+                Consistency::Prefix,
+                check(&histories, Consistency::Prefix),
+            )
+            .unwrap();
+
+        let checkpoint = Checkpoint::open(&path).unwrap();
+        assert_eq!(checkpoint.len(), 3);
+        assert!(checkpoint
+            .get("next.json", Consistency::Prefix)
+            .is_some_and(Result::is_ok));
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,146 @@
+//! Transparent compression of history files, chosen by their extension.
+//!
+//! A file ending in `.gz` is gzip-compressed and one ending in `.zst` is zstd-compressed, such as
+//! `history.json.gz` or `corpus.hist.zst`. Any other file is read and written as is. [`open`] and
+//! [`create`] wrap a file accordingly, so corpora of histories can be read and generated compressed
+//! without an intermediate file.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Result, Write};
+use std::path::Path;
+
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+
+/// The compression of a file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Returns the compression of `path`, from its extension.
+    #[must_use]
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("gz") => Self::Gzip,
+            Some("zst") => Self::Zstd,
+            _ => Self::None,
+        }
+    }
+}
+
+/// Opens `path` for reading, decompressing it according to its extension.
+///
+/// # Errors
+///
+/// Returns an error if the file can not be opened, or the zstd decoder can not be created.
+pub fn open(path: impl AsRef<Path>) -> Result<Box<dyn Read>> {
+    let path = path.as_ref();
+    let file = BufReader::new(File::open(path)?);
+    Ok(match Compression::from_path(path) {
+        Compression::None => Box::new(file),
+        Compression::Gzip => Box::new(MultiGzDecoder::new(file)),
+        Compression::Zstd => Box::new(zstd::Decoder::with_buffer(file)?),
+    })
+}
+
+/// A file being written, compressed according to its extension.
+///
+/// [`Writer::finish`] must be called once everything is written, to write the end of the
+/// compressed stream and report any error doing so.
+pub struct Writer(Encoder);
+
+enum Encoder {
+    None(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+    Zstd(zstd::Encoder<'static, BufWriter<File>>),
+}
+
+/// Creates `path` for writing, compressing it according to its extension.
+///
+/// # Errors
+///
+/// Returns an error if the file can not be created, or the zstd encoder can not be created.
+pub fn create(path: impl AsRef<Path>) -> Result<Writer> {
+    let path = path.as_ref();
+    let file = BufWriter::new(File::create(path)?);
+    Ok(Writer(match Compression::from_path(path) {
+        Compression::None => Encoder::None(file),
+        Compression::Gzip => Encoder::Gzip(GzEncoder::new(file, flate2::Compression::default())),
+        Compression::Zstd => Encoder::Zstd(zstd::Encoder::new(file, 0)?),
+    }))
+}
+
+impl Writer {
+    /// Ends the compressed stream and flushes the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    pub fn finish(self) -> Result<()> {
+        match self.0 {
+            Encoder::None(mut file) => file.flush(),
+            Encoder::Gzip(encoder) => encoder.finish()?.flush(),
+            Encoder::Zstd(encoder) => encoder.finish()?.flush(),
+        }
+    }
+}
+
+impl Write for Writer {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        match &mut self.0 {
+            Encoder::None(file) => file.write(buf),
+            Encoder::Gzip(encoder) => encoder.write(buf),
+            Encoder::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        match &mut self.0 {
+            Encoder::None(file) => file.flush(),
+            Encoder::Gzip(encoder) => encoder.flush(),
+            Encoder::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dbcop_core::history::non_atomic::types::{Event, Session, Transaction};
+
+    use super::*;
+
+    #[test]
+    fn test_compression() {
+        let histories: Vec<Session<u64, u64>> = vec![
+            vec![Transaction::committed(vec![Event::write(0, 1)])],
+            vec![Transaction::committed(vec![Event::read(0, 1)])],
+        ];
+        let dir = std::env::temp_dir().join(format!("dbcop-compress-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        for (name, magic) in [
+            ("history.json", &b"[["[..]),
+            ("history.json.gz", &[0x1f, 0x8b][..]),
+            ("history.hist.zst", &[0x28, 0xb5, 0x2f, 0xfd][..]),
+        ] {
+            let path = dir.join(name);
+            let mut writer = create(&path).unwrap();
+            serde_json::to_writer(&mut writer, &histories).unwrap();
+            writer.finish().unwrap();
+
+            assert!(std::fs::read(&path).unwrap().starts_with(magic), "{name}");
+            let read: Vec<Session<u64, u64>> =
+                serde_json::from_reader(open(&path).unwrap()).unwrap();
+            assert_eq!(read.len(), histories.len(), "{name}");
+            for (read, session) in read.iter().zip(&histories) {
+                assert_eq!(read[0].events, session[0].events, "{name}");
+            }
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
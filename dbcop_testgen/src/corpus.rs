@@ -0,0 +1,130 @@
+//! A corpus of histories with known verdicts, to catch regressions of the checkers.
+//!
+//! A corpus is a directory of JSON histories with a `manifest.json`, a list of [`ManifestEntry`]
+//! giving the expected verdict of each history for some levels. [`verify`] checks every history
+//! against its levels with every [`Backend`], and reports the verdicts that drifted from the
+//! manifest. The corpus of the repository is in `histories/corpus`.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::Path;
+
+use dbcop_core::check::{Backend, CheckSession};
+use dbcop_core::history::non_atomic::types::Session;
+use dbcop_core::Consistency;
+use serde::{Deserialize, Serialize};
+
+use crate::cache::Error;
+
+/// The name of the manifest in a corpus directory.
+pub const MANIFEST: &str = "manifest.json";
+
+/// The backends every history is checked with.
+const BACKENDS: [Backend; 2] = [Backend::Linearization, Backend::Polygraph];
+
+/// A history of the corpus and its expected verdicts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// The path of the history, relative to the corpus directory
+    pub file: String,
+    /// Whether the history satisfies each level
+    pub expected: HashMap<Consistency, bool>,
+}
+
+/// A verdict that differs from the manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Drift {
+    pub file: String,
+    pub level: Consistency,
+    pub backend: Backend,
+    pub expected: bool,
+}
+
+/// Renders as `lost_update.json: expected to satisfy snapshot isolation with Polygraph`.
+impl Display for Drift {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: expected to {} {} with {:?}",
+            self.file,
+            if self.expected { "satisfy" } else { "violate" },
+            self.level,
+            self.backend
+        )
+    }
+}
+
+/// Checks every history of the corpus in `dir` against the levels of its manifest entry, and
+/// returns the verdicts that differ from the manifest, in its order.
+///
+/// # Errors
+///
+/// Returns an [`Error`] if the manifest or a history can not be read.
+pub fn verify(dir: impl AsRef<Path>) -> Result<Vec<Drift>, Error> {
+    let dir = dir.as_ref();
+    let manifest: Vec<ManifestEntry> = serde_json::from_slice(&fs::read(dir.join(MANIFEST))?)?;
+    let mut drifts = Vec::new();
+    for entry in manifest {
+        let histories: Vec<Session<u64, u64>> =
+            serde_json::from_slice(&fs::read(dir.join(&entry.file))?)?;
+        // in the order of the hierarchy, so the drifts are reported the same way every time
+        for level in Consistency::LEVELS {
+            let Some(&expected) = entry.expected.get(&level) else {
+                continue;
+            };
+            for backend in BACKENDS {
+                let actual = CheckSession::new(&histories)
+                    .with_backend(backend)
+                    .check(level)
+                    .is_ok();
+                if actual != expected {
+                    drifts.push(Drift {
+                        file: entry.file.clone(),
+                        level,
+                        backend,
+                        expected,
+                    });
+                }
+            }
+        }
+    }
+    Ok(drifts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_corpus() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../histories/corpus");
+        let drifts = verify(&dir).unwrap();
+        assert!(
+            drifts.is_empty(),
+            "{}",
+            drifts
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+
+        // a wrong manifest is reported
+        let wrong = std::env::temp_dir().join(format!("dbcop-corpus-{}", std::process::id()));
+        fs::create_dir_all(&wrong).unwrap();
+        fs::copy(dir.join("lost_update.json"), wrong.join("lost_update.json")).unwrap();
+        fs::write(
+            wrong.join(MANIFEST),
+            r#"[{"file": "lost_update.json", "expected": {"snapshot_isolation": true}}]"#,
+        )
+        .unwrap();
+        let drifts = verify(&wrong).unwrap();
+        fs::remove_dir_all(&wrong).unwrap();
+        assert_eq!(drifts.len(), 2);
+        assert_eq!(
+            drifts[1].to_string(),
+            "lost_update.json: expected to satisfy snapshot isolation with Polygraph"
+        );
+    }
+}
@@ -0,0 +1,164 @@
+//! Execution of generated sessions on a Cockroach cluster, with stale reads.
+//!
+//! Cockroach serves a read-only transaction `AS OF SYSTEM TIME` in the past from any replica,
+//! such as the closest follower, instead of the leaseholder. Such a transaction reads a consistent
+//! snapshot, but a stale one: it may miss writes committed before it started, so a history with
+//! stale reads is expected to satisfy prefix consistency and causal consistency, not serializability.
+//!
+//! [`run_session`] executes a fraction of the read-only transactions of a session as stale reads,
+//! chosen by a [`StaleReads`] configuration, and annotates each of them with its `AS OF SYSTEM TIME`
+//! expression under the [`STALENESS`] key of its metadata. A transaction that writes is always
+//! executed at the present time, as Cockroach rejects writes in a historical transaction.
+
+use dbcop_core::history::non_atomic::types::{Event, Session, Transaction};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::driver::galera::DbError;
+
+/// Metadata key of the `AS OF SYSTEM TIME` expression of a stale read transaction.
+pub const STALENESS: &str = "staleness";
+
+/// How far in the past a stale read transaction reads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Staleness {
+    /// The most recent time a follower is guaranteed to serve, `follower_read_timestamp()`
+    FollowerRead,
+    /// A fixed interval before the start of the transaction
+    Exact { millis: u64 },
+}
+
+impl Staleness {
+    /// Returns the expression of the `AS OF SYSTEM TIME` clause, such as `'-250ms'`.
+    #[must_use]
+    pub fn as_of_system_time(self) -> String {
+        match self {
+            Self::FollowerRead => "follower_read_timestamp()".to_owned(),
+            Self::Exact { millis } => format!("'-{millis}ms'"),
+        }
+    }
+}
+
+/// Which transactions are executed as stale reads.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct StaleReads {
+    /// Probability that a read-only transaction is a stale read, between 0 and 1
+    pub fraction: f64,
+    pub staleness: Staleness,
+}
+
+impl Default for StaleReads {
+    fn default() -> Self {
+        Self {
+            fraction: 0.0,
+            staleness: Staleness::FollowerRead,
+        }
+    }
+}
+
+/// A connection to a node of the cluster.
+pub trait CockroachConnection {
+    /// Runs the events of a transaction and commits it, `AS OF SYSTEM TIME` `staleness` if it is
+    /// given.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`DbError`] that rolled the transaction back.
+    fn execute(
+        &mut self,
+        events: &[Event<u64, u64>],
+        staleness: Option<Staleness>,
+    ) -> Result<Vec<Event<u64, u64>>, DbError>;
+}
+
+/// Runs the transactions of a session in order on `connection`, and returns the observed session.
+///
+/// A committed transaction has the events returned by [`CockroachConnection::execute`], with the
+/// versions its reads observed. A rolled back transaction is kept as uncommitted.
+pub fn run_session<C: CockroachConnection, R: Rng + ?Sized>(
+    connection: &mut C,
+    transactions: &[Transaction<u64, u64>],
+    stale_reads: &StaleReads,
+    rng: &mut R,
+) -> Session<u64, u64> {
+    transactions
+        .iter()
+        .map(|transaction| {
+            let read_only = transaction
+                .events
+                .iter()
+                .all(|event| matches!(event, Event::Read { .. }));
+            let staleness = (read_only && rng.gen_bool(stale_reads.fraction.clamp(0.0, 1.0)))
+                .then_some(stale_reads.staleness);
+            let observed = connection
+                .execute(&transaction.events, staleness)
+                .map_or_else(
+                    |_| Transaction::uncommitted(transaction.events.clone()),
+                    Transaction::committed,
+                );
+            if let Some(staleness) = staleness {
+                observed.with_meta(STALENESS, staleness.as_of_system_time())
+            } else {
+                observed
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    /// Reads version 1 at the present time, and the initial version in the past.
+    struct Node;
+
+    impl CockroachConnection for Node {
+        fn execute(
+            &mut self,
+            events: &[Event<u64, u64>],
+            staleness: Option<Staleness>,
+        ) -> Result<Vec<Event<u64, u64>>, DbError> {
+            Ok(events
+                .iter()
+                .map(|event| match (event, staleness) {
+                    (Event::Read { variable, .. }, None) => Event::read(*variable, 1),
+                    (Event::Read { variable, .. }, Some(_)) => Event::read_empty(*variable),
+                    (Event::Write { .. }, _) => event.clone(),
+                })
+                .collect())
+        }
+    }
+
+    #[test]
+    fn test_run_session() {
+        let transactions = vec![
+            Transaction::committed(vec![Event::read_empty(0), Event::write(0, 2)]),
+            Transaction::committed(vec![Event::read_empty(0)]),
+        ];
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let session = run_session(&mut Node, &transactions, &StaleReads::default(), &mut rng);
+        assert!(session.iter().all(|transaction| transaction.committed));
+        assert_eq!(session[1].events, vec![Event::read(0, 1)]);
+        assert!(!session[1].meta.contains_key(STALENESS));
+
+        let stale_reads = StaleReads {
+            fraction: 1.0,
+            staleness: Staleness::Exact { millis: 250 },
+        };
+        let session = run_session(&mut Node, &transactions, &stale_reads, &mut rng);
+        // the transaction that writes runs at the present time
+        assert_eq!(session[0].events[0], Event::read(0, 1));
+        assert!(!session[0].meta.contains_key(STALENESS));
+        assert_eq!(session[1].events, vec![Event::read_empty(0)]);
+        assert_eq!(session[1].meta[STALENESS], "'-250ms'");
+        assert_eq!(
+            Staleness::FollowerRead.as_of_system_time(),
+            "follower_read_timestamp()"
+        );
+    }
+}
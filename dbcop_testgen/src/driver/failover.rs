@@ -0,0 +1,252 @@
+//! Execution of a logical session across reconnections to different nodes.
+//!
+//! A client that loses its node fails over to another one and carries on with the same session, and
+//! the database should still provide its session guarantees, such as reading its own writes.
+//! [`run_session`] moves a session along the nodes of a [`Failover`] policy, after a number of
+//! transactions, after an error, or both. Every transaction is annotated with the node it ran on,
+//! under the [`NODE`] key of its metadata, and the first transaction after a reconnection with its
+//! cause, under [`FAILOVER`].
+//!
+//! A transaction that fails with an error may still have committed before its node went away, so
+//! it is kept with an unknown commit status rather than retried, with its writes only: the versions
+//! its reads would have returned are not known. The transactions left when no node accepts the
+//! session are kept as uncommitted, as they never ran.
+
+use dbcop_core::history::non_atomic::types::{Event, Session, Transaction};
+use serde::{Deserialize, Serialize};
+
+use crate::driver::galera::DbError;
+pub use crate::driver::galera::NODE;
+
+/// Metadata key of the cause of the reconnection before a transaction, `scheduled` or `error`.
+pub const FAILOVER: &str = "failover";
+
+/// When a session moves to the next node.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Failover {
+    /// The nodes in the order the session visits them, cyclically
+    pub nodes: Vec<u64>,
+    /// Number of transactions after which the session moves to the next node
+    #[serde(default)]
+    pub every: Option<u64>,
+    /// Whether the session moves to the next node after a failed transaction
+    #[serde(default)]
+    pub on_error: bool,
+}
+
+/// A client connection that can reopen on another node.
+pub trait FailoverConnection {
+    /// Closes the connection and opens it on `node`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`DbError`] if `node` does not accept the connection.
+    fn reconnect(&mut self, node: u64) -> Result<(), DbError>;
+
+    /// Runs the events of a transaction and commits it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`DbError`] that rolled the transaction back or lost the connection.
+    fn execute(&mut self, events: &[Event<u64, u64>]) -> Result<Vec<Event<u64, u64>>, DbError>;
+}
+
+/// Runs the transactions of a session in order on `connection`, moving it along the nodes of
+/// `failover`, and returns the observed session.
+///
+/// The connection is first opened on the first node. A reconnection tries the next nodes in turn,
+/// and the session ends once all of them refuse it.
+pub fn run_session<C: FailoverConnection>(
+    connection: &mut C,
+    transactions: &[Transaction<u64, u64>],
+    failover: &Failover,
+) -> Session<u64, u64> {
+    let mut observed = Vec::with_capacity(transactions.len());
+    let mut current = connect(connection, &failover.nodes, 0);
+    let mut cause = None;
+
+    for (i, transaction) in (0..).zip(transactions) {
+        if i > 0
+            && failover
+                .every
+                .is_some_and(|every| every > 0 && i % every == 0)
+        {
+            cause.get_or_insert("scheduled");
+            current = current.and_then(|index| connect(connection, &failover.nodes, index + 1));
+        }
+        let Some(index) = current else {
+            observed.extend(
+                transactions[observed.len()..]
+                    .iter()
+                    .map(|transaction| Transaction::uncommitted(transaction.events.clone())),
+            );
+            break;
+        };
+
+        let result = connection.execute(&transaction.events);
+        let failed = result.is_err();
+        let mut executed = result.map_or_else(
+            |_| {
+                Transaction::unknown(
+                    transaction
+                        .events
+                        .iter()
+                        .filter(|event| matches!(event, Event::Write { .. }))
+                        .cloned()
+                        .collect(),
+                )
+            },
+            Transaction::committed,
+        );
+        executed = executed.with_meta(NODE, failover.nodes[index].to_string());
+        if let Some(cause) = cause.take() {
+            executed = executed.with_meta(FAILOVER, cause);
+        }
+        observed.push(executed);
+
+        if failed && failover.on_error {
+            cause = Some("error");
+            current = connect(connection, &failover.nodes, index + 1);
+        }
+    }
+    observed
+}
+
+/// Opens `connection` on the first node from `start` on, cyclically, that accepts it, and returns
+/// its index.
+fn connect<C: FailoverConnection>(
+    connection: &mut C,
+    nodes: &[u64],
+    start: usize,
+) -> Option<usize> {
+    (0..nodes.len())
+        .map(|offset| (start + offset) % nodes.len())
+        .find(|&index| connection.reconnect(nodes[index]).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use dbcop_core::check::check;
+    use dbcop_core::Consistency;
+
+    use super::*;
+
+    /// A cluster whose `refused` nodes refuse connections, and whose `lost` nodes lose the
+    /// connection during every transaction.
+    struct Client {
+        node: u64,
+        refused: Vec<u64>,
+        lost: Vec<u64>,
+        visited: Vec<u64>,
+    }
+
+    impl Client {
+        const fn new(refused: Vec<u64>, lost: Vec<u64>) -> Self {
+            Self {
+                node: 0,
+                refused,
+                lost,
+                visited: Vec::new(),
+            }
+        }
+    }
+
+    impl FailoverConnection for Client {
+        fn reconnect(&mut self, node: u64) -> Result<(), DbError> {
+            self.visited.push(node);
+            if self.refused.contains(&node) {
+                return Err(DbError {
+                    code: 2003,
+                    message: "Can't connect to server".to_owned(),
+                });
+            }
+            self.node = node;
+            Ok(())
+        }
+
+        fn execute(&mut self, events: &[Event<u64, u64>]) -> Result<Vec<Event<u64, u64>>, DbError> {
+            if self.lost.contains(&self.node) {
+                return Err(DbError {
+                    code: 2013,
+                    message: "Lost connection to server during query".to_owned(),
+                });
+            }
+            Ok(events.to_vec())
+        }
+    }
+
+    #[test]
+    fn test_run_session() {
+        let transactions: Vec<_> = (1..=5)
+            .map(|version| Transaction::committed(vec![Event::write(0, version)]))
+            .collect();
+        let nodes = |session: &Session<u64, u64>| -> Vec<String> {
+            session
+                .iter()
+                .map(|transaction| transaction.meta[NODE].clone())
+                .collect()
+        };
+
+        // moves every two transactions, skipping node 2, which refuses the session
+        let failover = Failover {
+            nodes: vec![1, 2, 3],
+            every: Some(2),
+            on_error: false,
+        };
+        let mut client = Client::new(vec![2], Vec::new());
+        let session = run_session(&mut client, &transactions, &failover);
+        assert_eq!(client.visited, vec![1, 2, 3, 1]);
+        assert_eq!(nodes(&session), vec!["1", "1", "3", "3", "1"]);
+        assert!(session.iter().all(|transaction| transaction.committed));
+        assert_eq!(session[2].meta[FAILOVER], "scheduled");
+        assert!(!session[1].meta.contains_key(FAILOVER));
+
+        // node 1 loses the first transaction, which may have committed
+        let failover = Failover {
+            nodes: vec![1, 2],
+            every: None,
+            on_error: true,
+        };
+        let mut client = Client::new(Vec::new(), vec![1]);
+        let session = run_session(&mut client, &transactions, &failover);
+        assert!(session[0].unknown);
+        assert_eq!(nodes(&session), vec!["1", "2", "2", "2", "2"]);
+        assert_eq!(session[1].meta[FAILOVER], "error");
+        assert!(session[1..].iter().all(|transaction| transaction.committed));
+
+        // no node accepts the session
+        let mut client = Client::new(vec![1, 2], Vec::new());
+        let session = run_session(&mut client, &transactions, &failover);
+        assert_eq!(session.len(), 5);
+        assert!(session
+            .iter()
+            .all(|transaction| !transaction.committed && !transaction.unknown));
+    }
+
+    #[test]
+    fn test_read_of_lost_transaction() {
+        // the second transaction is lost on node 2, after writing 1=5
+        let transactions = vec![
+            Transaction::committed(vec![Event::write(0, 1)]),
+            Transaction::committed(vec![Event::read_empty(0), Event::write(1, 5)]),
+        ];
+        let failover = Failover {
+            nodes: vec![1, 2],
+            every: Some(1),
+            on_error: false,
+        };
+        let mut client = Client::new(Vec::new(), vec![2]);
+        let session = run_session(&mut client, &transactions, &failover);
+        assert!(session[1].unknown);
+        assert_eq!(session[1].events, vec![Event::write(1, 5)]);
+
+        // another session reads the write, so the lost transaction committed
+        let histories = vec![
+            session,
+            vec![Transaction::committed(vec![Event::read(1, 5)])],
+        ];
+        for level in Consistency::LEVELS {
+            assert!(check(&histories, level).is_ok(), "{level:?}");
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Fault injection while executing a history on a cluster.
+//!
+//! A [`FaultSchedule`] lists the faults to inject and the transaction round before which each of them
+//! fires. A driver walks the rounds of a history and hands the due faults to its [`FaultInjector`].
+//! Every injected fault is recorded with its wall-clock time, so the record can be stored next to the
+//! observed history and violations can be correlated with the faults.
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+/// A fault that can be injected into the cluster under test.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Fault {
+    /// Kills the database process of a node.
+    Kill { node: u64 },
+    /// Restarts the database process of a node.
+    Restart { node: u64 },
+    /// Partitions the nodes into groups that can not talk to each other.
+    Partition { groups: Vec<Vec<u64>> },
+    /// Heals all network partitions.
+    Heal,
+    /// Adds latency to the network traffic of a node.
+    Latency { node: u64, millis: u64 },
+}
+
+/// A fault, fired before the transaction round `round`.
+///
+/// The round of a transaction is its height in its session.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ScheduledFault {
+    pub round: u64,
+    pub fault: Fault,
+}
+
+/// Faults to inject during an execution.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FaultSchedule(pub Vec<ScheduledFault>);
+
+impl FaultSchedule {
+    pub fn add(&mut self, round: u64, fault: Fault) {
+        self.0.push(ScheduledFault { round, fault });
+    }
+
+    /// Returns the faults to fire before transaction round `round`, in schedule order.
+    pub fn due(&self, round: u64) -> impl Iterator<Item = &Fault> {
+        self.0
+            .iter()
+            .filter(move |scheduled| scheduled.round == round)
+            .map(|scheduled| &scheduled.fault)
+    }
+}
+
+/// A fault that was injected during an execution.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct InjectedFault {
+    pub round: u64,
+    pub fault: Fault,
+    pub time: DateTime<Local>,
+}
+
+/// Injects faults into a cluster. Implemented by the drivers.
+pub trait FaultInjector {
+    type Error;
+
+    /// # Errors
+    ///
+    /// Returns [`FaultInjector::Error`] if the fault could not be injected.
+    fn inject(&mut self, fault: &Fault) -> Result<(), Self::Error>;
+
+    /// Injects the faults due before transaction round `round` and appends them to `record`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error from [`FaultInjector::inject`]. The faults injected before it are recorded.
+    fn inject_due(
+        &mut self,
+        schedule: &FaultSchedule,
+        round: u64,
+        record: &mut Vec<InjectedFault>,
+    ) -> Result<(), Self::Error> {
+        for fault in schedule.due(round) {
+            self.inject(fault)?;
+            record.push(InjectedFault {
+                round,
+                fault: fault.clone(),
+                time: Local::now(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Cluster {
+        killed: Vec<u64>,
+    }
+
+    impl FaultInjector for Cluster {
+        type Error = ();
+
+        fn inject(&mut self, fault: &Fault) -> Result<(), Self::Error> {
+            match fault {
+                Fault::Kill { node } => self.killed.push(*node),
+                Fault::Restart { node } => self.killed.retain(|killed| killed != node),
+                _ => return Err(()),
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_inject_due() {
+        let mut schedule = FaultSchedule::default();
+        schedule.add(1, Fault::Kill { node: 2 });
+        schedule.add(3, Fault::Restart { node: 2 });
+        schedule.add(1, Fault::Kill { node: 0 });
+
+        let mut cluster = Cluster::default();
+        let mut record = Vec::new();
+
+        for round in 0..3 {
+            cluster.inject_due(&schedule, round, &mut record).unwrap();
+        }
+        assert_eq!(cluster.killed, vec![2, 0]);
+
+        cluster.inject_due(&schedule, 3, &mut record).unwrap();
+        assert_eq!(cluster.killed, vec![0]);
+
+        assert_eq!(
+            record
+                .iter()
+                .map(|injected| injected.round)
+                .collect::<Vec<_>>(),
+            vec![1, 1, 3]
+        );
+    }
+}
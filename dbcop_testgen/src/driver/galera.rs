@@ -1 +1,228 @@
+//! Execution of generated sessions on a Galera cluster.
+//!
+//! Each session runs on one node through a [`GaleraConnection`]. Galera reports a transaction that
+//! fails certification against a concurrent write set with the same error as a local deadlock,
+//! `ER_LOCK_DEADLOCK` (1213), but with a `WSREP` message. [`AbortKind::classify`] tells them apart,
+//! and a [`RetryPolicy`] decides which aborts are retried.
+//!
+//! Every transaction of the produced session is annotated with the node it ran on, under the
+//! [`NODE`] key of its metadata, and with the kinds of its aborted attempts, under [`ABORTS`]. A
+//! transaction that is still aborted after its last attempt is kept as uncommitted, so a read of one
+//! of its writes is reported by the checkers.
 
+use std::fmt::{Display, Formatter};
+
+use dbcop_core::history::non_atomic::types::{Event, Session, Transaction};
+use serde::{Deserialize, Serialize};
+
+/// Metadata key of the node a transaction ran on.
+pub const NODE: &str = "node";
+/// Metadata key of the comma-separated kinds of the aborted attempts of a transaction.
+pub const ABORTS: &str = "aborts";
+
+/// `ER_LOCK_DEADLOCK`, returned both for deadlocks and for certification failures.
+const ER_LOCK_DEADLOCK: u16 = 1213;
+/// `ER_LOCK_WAIT_TIMEOUT`
+const ER_LOCK_WAIT_TIMEOUT: u16 = 1205;
+
+/// An error returned by the database.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DbError {
+    pub code: u16,
+    pub message: String,
+}
+
+impl Display for DbError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "error {}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for DbError {}
+
+/// Why a transaction aborted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AbortKind {
+    /// The write set conflicts with a write set certified first on another node
+    Certification,
+    /// A local deadlock or lock wait timeout
+    Deadlock,
+    Other,
+}
+
+impl AbortKind {
+    #[must_use]
+    pub fn classify(error: &DbError) -> Self {
+        match error.code {
+            ER_LOCK_DEADLOCK if error.message.contains("WSREP") => Self::Certification,
+            ER_LOCK_DEADLOCK | ER_LOCK_WAIT_TIMEOUT => Self::Deadlock,
+            _ => Self::Other,
+        }
+    }
+
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Certification => "certification",
+            Self::Deadlock => "deadlock",
+            Self::Other => "other",
+        }
+    }
+}
+
+/// Which aborts are retried, and how many times a transaction is attempted at most.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub retry_certification: bool,
+    pub retry_deadlock: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            retry_certification: true,
+            retry_deadlock: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    const fn retries(&self, kind: AbortKind) -> bool {
+        match kind {
+            AbortKind::Certification => self.retry_certification,
+            AbortKind::Deadlock => self.retry_deadlock,
+            AbortKind::Other => false,
+        }
+    }
+}
+
+/// A connection to a node of the cluster.
+pub trait GaleraConnection {
+    /// The node the connection is open on.
+    fn node(&self) -> u64;
+
+    /// Runs the events of a transaction and commits it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`DbError`] that rolled the transaction back.
+    fn execute(&mut self, events: &[Event<u64, u64>]) -> Result<Vec<Event<u64, u64>>, DbError>;
+}
+
+/// Runs the transactions of a session in order on `connection`, and returns the observed session.
+///
+/// A committed transaction has the events returned by [`GaleraConnection::execute`], with the
+/// versions its reads observed.
+pub fn run_session<C: GaleraConnection>(
+    connection: &mut C,
+    transactions: &[Transaction<u64, u64>],
+    policy: &RetryPolicy,
+) -> Session<u64, u64> {
+    transactions
+        .iter()
+        .map(|transaction| {
+            let mut aborts = Vec::new();
+            let observed = loop {
+                match connection.execute(&transaction.events) {
+                    Ok(events) => break Transaction::committed(events),
+                    Err(error) => {
+                        let kind = AbortKind::classify(&error);
+                        aborts.push(kind.as_str());
+                        if !policy.retries(kind)
+                            || aborts.len() as u64 >= policy.max_attempts.into()
+                        {
+                            break Transaction::uncommitted(transaction.events.clone());
+                        }
+                    }
+                }
+            };
+            let observed = observed.with_meta(NODE, connection.node().to_string());
+            if aborts.is_empty() {
+                observed
+            } else {
+                observed.with_meta(ABORTS, aborts.join(","))
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fails with the queued errors first, then commits and reads version 7.
+    struct Node {
+        errors: Vec<DbError>,
+    }
+
+    impl GaleraConnection for Node {
+        fn node(&self) -> u64 {
+            2
+        }
+
+        fn execute(&mut self, events: &[Event<u64, u64>]) -> Result<Vec<Event<u64, u64>>, DbError> {
+            if !self.errors.is_empty() {
+                return Err(self.errors.remove(0));
+            }
+            Ok(events
+                .iter()
+                .map(|event| match event {
+                    Event::Read { variable, .. } => Event::read(*variable, 7),
+                    Event::Write { .. } => event.clone(),
+                })
+                .collect())
+        }
+    }
+
+    #[test]
+    fn test_run_session() {
+        let certification = DbError {
+            code: 1213,
+            message:
+                "WSREP detected deadlock/conflict and aborted the transaction. Try restarting \
+                      the transaction"
+                    .to_owned(),
+        };
+        let deadlock = DbError {
+            code: 1213,
+            message: "Deadlock found when trying to get lock; try restarting transaction"
+                .to_owned(),
+        };
+        assert_eq!(
+            AbortKind::classify(&certification),
+            AbortKind::Certification
+        );
+        assert_eq!(AbortKind::classify(&deadlock), AbortKind::Deadlock);
+
+        let transactions = vec![
+            Transaction::committed(vec![Event::read_empty(0), Event::write(1, 1)]),
+            Transaction::committed(vec![Event::write(0, 2)]),
+        ];
+        let mut node = Node {
+            errors: vec![certification.clone(), deadlock],
+        };
+        let session = run_session(&mut node, &transactions, &RetryPolicy::default());
+
+        assert!(session[0].committed);
+        assert_eq!(session[0].events[0], Event::read(0, 7));
+        assert_eq!(session[0].meta[NODE], "2");
+        assert_eq!(session[0].meta[ABORTS], "certification,deadlock");
+        assert!(session[1].committed);
+        assert!(!session[1].meta.contains_key(ABORTS));
+
+        let mut node = Node {
+            errors: vec![certification],
+        };
+        let policy = RetryPolicy {
+            retry_certification: false,
+            ..RetryPolicy::default()
+        };
+        let session = run_session(&mut node, &transactions[1..], &policy);
+        assert!(!session[0].committed);
+        assert_eq!(session[0].events, transactions[1].events);
+        assert_eq!(session[0].meta[ABORTS], "certification");
+    }
+}
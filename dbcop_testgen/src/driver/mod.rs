@@ -1 +1,5 @@
+pub mod cockroach;
+pub mod failover;
+pub mod faults;
 pub mod galera;
+pub mod schedule;
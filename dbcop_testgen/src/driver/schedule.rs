@@ -0,0 +1,233 @@
+//! Control of the relative timing of the sessions while executing a history.
+//!
+//! [`run_scheduled`] runs every session on its own thread and paces its transactions with a
+//! [`Schedule`]: a barrier between transaction rounds makes every session finish a round before
+//! any starts the next one, a random jitter delays each transaction, and a rate limit spaces the
+//! transactions of a session. The same generated history can so be executed under different
+//! interleaving pressures. Each observed transaction is annotated with its round, its jitter and
+//! its start time, under the [`ROUND`], [`DELAY`] and [`START`] keys of its metadata.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Barrier;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+use dbcop_core::history::non_atomic::types::{Session, Transaction};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// Metadata key of the round of a transaction, its height in its session.
+pub const ROUND: &str = "round";
+/// Metadata key of the jitter before a transaction, in milliseconds.
+pub const DELAY: &str = "delay_millis";
+/// Metadata key of the RFC 3339 start time of a transaction.
+pub const START: &str = "start";
+
+/// Pacing of the sessions of an execution.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Schedule {
+    /// Whether every session finishes a round before any session starts the next one
+    #[serde(default)]
+    pub barrier: bool,
+    /// Upper bound of the uniform random delay before each transaction, in milliseconds
+    #[serde(default)]
+    pub jitter_millis: u64,
+    /// Maximum number of transactions per second of each session
+    #[serde(default)]
+    pub rate_limit: Option<f64>,
+}
+
+/// Runs each of `sessions` on its connection in `connections`, on its own thread, paced by
+/// `schedule`, and returns the observed sessions.
+///
+/// `execute` runs a transaction on a connection and returns the observed transaction, such as
+/// [`galera::run_session`](crate::driver::galera::run_session) on a single transaction. The jitter
+/// of session `i` is drawn from a generator seeded with `seed + i`.
+///
+/// # Panics
+///
+/// Panics if there are fewer connections than sessions, if the interval of the rate limit does not
+/// fit a [`Duration`], or if `execute` panics. A session whose `execute` panics stops, but still
+/// waits at the barrier of every round, so the other sessions run to the end first.
+pub fn run_scheduled<C, F>(
+    connections: &mut [C],
+    sessions: &[Session<u64, u64>],
+    schedule: &Schedule,
+    seed: u64,
+    execute: F,
+) -> Vec<Session<u64, u64>>
+where
+    C: Send,
+    F: Fn(&mut C, &Transaction<u64, u64>) -> Transaction<u64, u64> + Sync,
+{
+    assert!(
+        connections.len() >= sessions.len(),
+        "every session needs a connection"
+    );
+    let rounds = sessions.iter().map(Vec::len).max().unwrap_or(0);
+    let barrier = Barrier::new(sessions.len());
+    let interval = schedule.rate_limit.filter(|rate| *rate > 0.0).map(|rate| {
+        Duration::try_from_secs_f64(1.0 / rate)
+            .unwrap_or_else(|_| panic!("rate limit {rate} is too small"))
+    });
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..)
+            .zip(connections.iter_mut().zip(sessions))
+            .map(|(i, (connection, session))| {
+                let (barrier, execute) = (&barrier, &execute);
+                scope.spawn(move || {
+                    let mut rng = StdRng::seed_from_u64(seed.wrapping_add(i));
+                    let mut last_start: Option<Instant> = None;
+                    let mut observed = Vec::with_capacity(session.len());
+                    let mut panicked = None;
+                    for round in 0..rounds {
+                        if let (None, Some(transaction)) = (&panicked, session.get(round)) {
+                            let delay = rng.gen_range(0..=schedule.jitter_millis);
+                            thread::sleep(Duration::from_millis(delay));
+                            if let (Some(interval), Some(last_start)) = (interval, last_start) {
+                                thread::sleep(interval.saturating_sub(last_start.elapsed()));
+                            }
+                            last_start = Some(Instant::now());
+                            let start = Local::now().to_rfc3339();
+                            match panic::catch_unwind(AssertUnwindSafe(|| {
+                                execute(connection, transaction)
+                            })) {
+                                Ok(executed) => observed.push(
+                                    executed
+                                        .with_meta(ROUND, round.to_string())
+                                        .with_meta(DELAY, delay.to_string())
+                                        .with_meta(START, start),
+                                ),
+                                Err(payload) => panicked = Some(payload),
+                            }
+                        }
+                        // a finished session still waits, so the others are not blocked
+                        if schedule.barrier {
+                            barrier.wait();
+                        }
+                    }
+                    if let Some(payload) = panicked {
+                        panic::resume_unwind(payload);
+                    }
+                    observed
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("a session thread panicked"))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use dbcop_core::history::non_atomic::types::Event;
+
+    use super::*;
+
+    #[test]
+    fn test_run_scheduled() {
+        let sessions: Vec<Session<u64, u64>> = (0..3)
+            .map(|session| {
+                (0..=session)
+                    .map(|round| Transaction::committed(vec![Event::write(session, round)]))
+                    .collect()
+            })
+            .collect();
+        // the rounds in the order the transactions ran
+        let log = Mutex::new(Vec::new());
+        let execute = |&mut (): &mut (), transaction: &Transaction<u64, u64>| {
+            if let [Event::Write { version, .. }] = transaction.events[..] {
+                log.lock().unwrap().push(version);
+            }
+            transaction.clone()
+        };
+
+        let schedule = Schedule {
+            barrier: true,
+            jitter_millis: 5,
+            rate_limit: Some(200.0),
+        };
+        let start = Instant::now();
+        let observed = run_scheduled(&mut [(), (), ()], &sessions, &schedule, 0, execute);
+        // the third session waits 5ms between its transactions
+        assert!(start.elapsed() >= Duration::from_millis(10));
+        assert_eq!(log.lock().unwrap().clone(), vec![0, 0, 0, 1, 1, 2]);
+
+        assert_eq!(observed.len(), 3);
+        for (session, planned) in observed.iter().zip(&sessions) {
+            assert_eq!(session.len(), planned.len());
+            for (round, transaction) in session.iter().enumerate() {
+                assert_eq!(transaction.events, planned[round].events);
+                assert_eq!(transaction.meta[ROUND], round.to_string());
+                assert!(transaction.meta[DELAY].parse::<u64>().unwrap() <= 5);
+                assert!(transaction.meta.contains_key(START));
+            }
+        }
+
+        // the jitter is reproducible
+        let delays = |observed: &[Session<u64, u64>]| -> Vec<String> {
+            observed
+                .iter()
+                .flatten()
+                .map(|transaction| transaction.meta[DELAY].clone())
+                .collect()
+        };
+        let again = run_scheduled(&mut [(), (), ()], &sessions, &schedule, 0, execute);
+        assert_eq!(delays(&observed), delays(&again));
+    }
+
+    #[test]
+    fn test_run_scheduled_panic() {
+        let sessions: Vec<Session<u64, u64>> = (0..3)
+            .map(|session| {
+                (0..3)
+                    .map(|round| Transaction::committed(vec![Event::write(session, round)]))
+                    .collect()
+            })
+            .collect();
+        let schedule = Schedule {
+            barrier: true,
+            ..Schedule::default()
+        };
+        // the first session panics in its second round, and the others still finish
+        let finished = Mutex::new(0);
+        let execute = |&mut (): &mut (), transaction: &Transaction<u64, u64>| {
+            if let [Event::Write {
+                variable: 0,
+                version: 1,
+            }] = transaction.events[..]
+            {
+                panic!("lost the connection");
+            }
+            *finished.lock().unwrap() += 1;
+            transaction.clone()
+        };
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            run_scheduled(&mut [(), (), ()], &sessions, &schedule, 0, execute)
+        }));
+        assert!(result.is_err());
+        assert_eq!(*finished.lock().unwrap(), 7);
+
+        let schedule = Schedule {
+            rate_limit: Some(f64::MIN_POSITIVE),
+            ..Schedule::default()
+        };
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            run_scheduled(
+                &mut [()],
+                &sessions[..1],
+                &schedule,
+                0,
+                |&mut (), transaction| transaction.clone(),
+            )
+        }));
+        assert!(result.is_err());
+    }
+}
@@ -1,13 +1,16 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use chrono::{DateTime, Duration, Local};
 use dbcop_core::history::non_atomic::types::{Event, Session, Transaction};
-use rand::distributions::{Distribution, Uniform};
-use rand::Rng;
+use rand::distributions::Distribution;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use serde::{Deserialize, Serialize};
 use typed_builder::TypedBuilder;
 
+use crate::driver::faults::InjectedFault;
+
 #[derive(Clone, Debug, Default, Deserialize, Serialize, TypedBuilder)]
 pub struct HistParams {
     pub id: u64,
@@ -15,6 +18,10 @@ pub struct HistParams {
     pub n_variable: u64,
     pub n_transaction: u64,
     pub n_event: u64,
+    /// The configuration that reproduces the history, with the seed of its random generator.
+    #[builder(default)]
+    #[serde(default)]
+    pub config: GeneratorConfig,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -24,11 +31,13 @@ pub struct History {
     start: DateTime<Local>,
     end: DateTime<Local>,
     data: Vec<Session<u64, u64>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    faults: Vec<InjectedFault>,
 }
 
 impl History {
     #[must_use]
-    pub fn new(
+    pub const fn new(
         params: HistParams,
         info: String,
         start: DateTime<Local>,
@@ -41,9 +50,17 @@ impl History {
             start,
             end,
             data,
+            faults: Vec::new(),
         }
     }
 
+    /// Attaches the faults injected while this history was observed.
+    #[must_use]
+    pub fn with_faults(mut self, faults: Vec<InjectedFault>) -> Self {
+        self.faults = faults;
+        self
+    }
+
     #[must_use]
     pub const fn get_id(&self) -> u64 {
         self.params.id
@@ -64,10 +81,174 @@ impl History {
         self.params.clone()
     }
 
+    #[must_use]
+    pub fn get_faults(&self) -> &[InjectedFault] {
+        &self.faults
+    }
+
     #[must_use]
     pub fn get_duration(&self) -> Duration {
         self.end - self.start
     }
+
+    /// Serializes everything but the sessions as JSON.
+    #[cfg(feature = "compact-binary")]
+    pub(crate) fn metadata_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(&MetadataRef {
+            params: &self.params,
+            info: &self.info,
+            start: &self.start,
+            end: &self.end,
+            faults: &self.faults,
+        })
+    }
+
+    /// Rebuilds a history from the output of [`History::metadata_json`] and its sessions.
+    #[cfg(feature = "compact-binary")]
+    pub(crate) fn from_metadata_json(
+        metadata: &str,
+        data: Vec<Session<u64, u64>>,
+    ) -> serde_json::Result<Self> {
+        let Metadata {
+            params,
+            info,
+            start,
+            end,
+            faults,
+        } = serde_json::from_str(metadata)?;
+        Ok(Self::new(params, info, start, end, data).with_faults(faults))
+    }
+}
+
+#[cfg(feature = "compact-binary")]
+#[derive(Serialize)]
+struct MetadataRef<'a> {
+    params: &'a HistParams,
+    info: &'a str,
+    start: &'a DateTime<Local>,
+    end: &'a DateTime<Local>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    faults: &'a [InjectedFault],
+}
+
+#[cfg(feature = "compact-binary")]
+#[derive(Deserialize)]
+struct Metadata {
+    params: HistParams,
+    info: String,
+    start: DateTime<Local>,
+    end: DateTime<Local>,
+    #[serde(default)]
+    faults: Vec<InjectedFault>,
+}
+
+/// Template of the generated transactions.
+///
+/// Random read and write mixes rarely line up the accesses that real anomalies need, so the other
+/// templates generate transactions with the structure of a known anomaly. Their variables follow
+/// the access pattern of the [`GeneratorConfig`], and only [`Workload::Random`] uses `n_event`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Workload {
+    /// `n_event` reads and writes of random variables
+    #[default]
+    Random,
+    /// Transfers between two accounts: reads both, then writes both
+    Bank,
+    /// Reads of a single register, or compare-and-sets of it: a read, then a write
+    Register,
+    /// Reads of a whole group of variables, or a blind write of one of them, as in a long fork
+    LongFork,
+    /// Reads two variables, then writes one of them, as in a write skew
+    WriteSkew,
+}
+
+/// Shape of the generated workload.
+#[derive(Clone, Debug, Deserialize, Serialize, TypedBuilder)]
+pub struct GeneratorConfig {
+    /// Template of the transactions.
+    #[builder(default)]
+    #[serde(default)]
+    pub workload: Workload,
+    /// Probability of an event being a read. Must be in `[0, 1]`.
+    #[builder(default = 0.5)]
+    pub read_ratio: f64,
+    /// Exponent of the Zipfian distribution over the variables.
+    /// Variable `0` is the hottest. `0` gives uniform access.
+    #[builder(default = 0.0)]
+    pub zipf_exponent: f64,
+    /// Probability of an access going to the variables of the session's own partition.
+    /// The variables are split into one contiguous partition per session. Must be in `[0, 1]`.
+    #[builder(default = 0.0)]
+    pub session_affinity: f64,
+    /// Seed for the random generator. A random seed is drawn if it is absent.
+    #[builder(default, setter(strip_option))]
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// Zipfian distribution over `0..n`, sampled by inverting its cumulative distribution.
+struct Zipf {
+    cumulative: Vec<f64>,
+}
+
+impl Zipf {
+    fn new(n: u64, exponent: f64) -> Self {
+        let mut total = 0.0;
+        let cumulative = (1..=n)
+            .map(|rank| {
+                #[allow(clippy::cast_precision_loss)]
+                let rank = rank as f64;
+                total += rank.powf(-exponent);
+                total
+            })
+            .collect();
+        Self { cumulative }
+    }
+}
+
+impl Distribution<u64> for Zipf {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> u64 {
+        let total = self.cumulative.last().copied().unwrap_or_default();
+        let target = rng.gen::<f64>() * total;
+        let index = self.cumulative.partition_point(|&weight| weight <= target);
+        // guards against rounding at the upper end
+        (index.min(self.cumulative.len().saturating_sub(1))) as u64
+    }
+}
+
+/// Access pattern of a session over the variables.
+struct Variables {
+    global: Zipf,
+    partition: Zipf,
+    partition_start: u64,
+    session_affinity: f64,
+}
+
+impl Variables {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> u64 {
+        if rng.gen_bool(self.session_affinity) {
+            self.partition_start + self.partition.sample(rng)
+        } else {
+            self.global.sample(rng)
+        }
+    }
+
+    /// Samples two variables, distinct if there are several.
+    fn sample_pair<R: Rng + ?Sized>(&self, rng: &mut R, n_variable: u64) -> (u64, u64) {
+        let first = self.sample(rng);
+        let mut second = self.sample(rng);
+        while n_variable > 1 && second == first {
+            second = self.sample(rng);
+        }
+        (first, second)
+    }
 }
 
 #[must_use]
@@ -77,35 +258,136 @@ pub fn generate_single_history(
     n_transaction: u64,
     n_event: u64,
 ) -> Vec<Session<u64, u64>> {
-    let mut counters = HashMap::new();
-    let mut random_generator = rand::thread_rng();
-    let read_variable_range = Uniform::from(0..n_variable);
-    // let jump = (n_variable as f64 / n_node as f64).ceil();
+    generate_single_history_with_config(
+        n_node,
+        n_variable,
+        n_transaction,
+        n_event,
+        &GeneratorConfig::default(),
+    )
+}
+
+/// Generates a history with the access pattern described by `config`.
+///
+/// # Panics
+///
+/// Panics if `config.read_ratio` or `config.session_affinity` is not in `[0, 1]`.
+#[must_use]
+pub fn generate_single_history_with_config(
+    n_node: u64,
+    n_variable: u64,
+    n_transaction: u64,
+    n_event: u64,
+    config: &GeneratorConfig,
+) -> Vec<Session<u64, u64>> {
+    let seed = config.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    generate_single_history_with_rng(
+        n_node,
+        n_variable,
+        n_transaction,
+        n_event,
+        config,
+        &mut StdRng::seed_from_u64(seed),
+    )
+}
+
+/// Generates a history with the access pattern described by `config`, drawing from `random_generator`.
+/// `config.seed` is ignored.
+///
+/// # Panics
+///
+/// Panics if `config.read_ratio` or `config.session_affinity` is not in `[0, 1]`.
+pub fn generate_single_history_with_rng<R>(
+    n_node: u64,
+    n_variable: u64,
+    n_transaction: u64,
+    n_event: u64,
+    config: &GeneratorConfig,
+    random_generator: &mut R,
+) -> Vec<Session<u64, u64>>
+where
+    R: Rng + ?Sized,
+{
+    let mut counters: HashMap<u64, u64> = HashMap::new();
+    let mut write = |variable: u64| {
+        let version = counters.entry(variable).or_default();
+        *version += 1;
+        Event::write(variable, *version)
+    };
+    let partition_size = n_variable.div_ceil(n_node.max(1)).max(1);
+    // the variables read together by a long fork read
+    let group_size = n_event.max(2);
     (0..n_node)
-        .map(|_i_node| {
-            // let i = i_node * jump;
-            // let j = std::cmp::min((i_node + 1) * jump, n_variable);
-            // let write_variable_range = Uniform::from(i..j);
+        .map(|i_node| {
+            let partition_start = (i_node * partition_size).min(n_variable.saturating_sub(1));
+            let partition_end = ((i_node + 1) * partition_size).min(n_variable);
+            let variables = Variables {
+                global: Zipf::new(n_variable, config.zipf_exponent),
+                partition: Zipf::new(
+                    partition_end.saturating_sub(partition_start).max(1),
+                    config.zipf_exponent,
+                ),
+                partition_start,
+                session_affinity: config.session_affinity,
+            };
             (0..n_transaction)
-                .map(|_| Transaction {
-                    events: (0..n_event)
-                        .map(|_| {
-                            if random_generator.gen() {
-                                let variable = read_variable_range.sample(&mut random_generator);
-                                Event::read_empty(variable)
+                .map(|_| {
+                    let rng = &mut *random_generator;
+                    let events = match config.workload {
+                        Workload::Random => (0..n_event)
+                            .map(|_| {
+                                let variable = variables.sample(rng);
+                                if rng.gen_bool(config.read_ratio) {
+                                    Event::read_empty(variable)
+                                } else {
+                                    write(variable)
+                                }
+                            })
+                            .collect(),
+                        Workload::Bank => {
+                            let (from, to) = variables.sample_pair(rng, n_variable);
+                            vec![
+                                Event::read_empty(from),
+                                Event::read_empty(to),
+                                write(from),
+                                write(to),
+                            ]
+                        }
+                        Workload::Register => {
+                            let variable = variables.sample(rng);
+                            if rng.gen_bool(config.read_ratio) {
+                                vec![Event::read_empty(variable)]
                             } else {
-                                let variable = read_variable_range.sample(&mut random_generator);
-                                // let variable = write_variable_range.sample(&mut random_generator);
-                                let version = {
-                                    let entry = counters.entry(variable).or_default();
-                                    *entry += 1;
-                                    *entry
-                                };
-                                Event::write(variable, version)
+                                vec![Event::read_empty(variable), write(variable)]
                             }
-                        })
-                        .collect(),
-                    committed: false,
+                        }
+                        Workload::LongFork => {
+                            let variable = variables.sample(rng);
+                            if rng.gen_bool(config.read_ratio) {
+                                let start = variable / group_size * group_size;
+                                (start..(start + group_size).min(n_variable))
+                                    .map(Event::read_empty)
+                                    .collect()
+                            } else {
+                                vec![write(variable)]
+                            }
+                        }
+                        Workload::WriteSkew => {
+                            let (first, second) = variables.sample_pair(rng, n_variable);
+                            vec![
+                                Event::read_empty(first),
+                                Event::read_empty(second),
+                                write(first),
+                            ]
+                        }
+                    };
+                    Transaction {
+                        events,
+                        committed: false,
+                        unknown: false,
+                        predecessors: None,
+                        meta: BTreeMap::new(),
+                    }
                 })
                 .collect::<Vec<_>>()
         })
@@ -120,11 +402,47 @@ pub fn generate_mult_histories(
     n_transaction: u64,
     n_event: u64,
 ) -> Vec<History> {
+    generate_mult_histories_with_config(
+        n_hist,
+        n_node,
+        n_variable,
+        n_transaction,
+        n_event,
+        &GeneratorConfig::default(),
+    )
+}
+
+/// Generates `n_hist` histories with the access pattern described by `config`.
+///
+/// The `i`-th history is generated from seed `config.seed + i`, which is recorded as the seed of the
+/// configuration in its [`HistParams`].
+///
+/// # Panics
+///
+/// Panics if `config.read_ratio` or `config.session_affinity` is not in `[0, 1]`.
+#[must_use]
+pub fn generate_mult_histories_with_config(
+    n_hist: u64,
+    n_node: u64,
+    n_variable: u64,
+    n_transaction: u64,
+    n_event: u64,
+    config: &GeneratorConfig,
+) -> Vec<History> {
+    let base_seed = config.seed.unwrap_or_else(|| rand::thread_rng().gen());
     (0..n_hist)
         .into_par_iter()
         .map(|i_hist| {
+            let seed = base_seed.wrapping_add(i_hist);
             let start_time = Local::now();
-            let hist = generate_single_history(n_node, n_variable, n_transaction, n_event);
+            let hist = generate_single_history_with_rng(
+                n_node,
+                n_variable,
+                n_transaction,
+                n_event,
+                config,
+                &mut StdRng::seed_from_u64(seed),
+            );
             let end_time = Local::now();
             History {
                 params: HistParams {
@@ -133,12 +451,161 @@ pub fn generate_mult_histories(
                     n_variable,
                     n_transaction,
                     n_event,
+                    config: GeneratorConfig {
+                        seed: Some(seed),
+                        ..config.clone()
+                    },
                 },
                 info: "generated".to_string(),
                 start: start_time,
                 end: end_time,
                 data: hist,
+                faults: Vec::new(),
             }
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_ratio() {
+        let config = GeneratorConfig::builder().read_ratio(1.0).build();
+        let history = generate_single_history_with_config(3, 10, 5, 4, &config);
+        assert!(history
+            .iter()
+            .flatten()
+            .flat_map(|transaction| &transaction.events)
+            .all(|event| matches!(event, Event::Read { .. })));
+
+        let config = GeneratorConfig::builder().read_ratio(0.0).build();
+        let history = generate_single_history_with_config(3, 10, 5, 4, &config);
+        assert!(history
+            .iter()
+            .flatten()
+            .flat_map(|transaction| &transaction.events)
+            .all(|event| matches!(event, Event::Write { .. })));
+    }
+
+    #[test]
+    fn test_workloads() {
+        let generate = |workload| {
+            let config = GeneratorConfig::builder()
+                .workload(workload)
+                .seed(7)
+                .build();
+            generate_single_history_with_config(3, 10, 20, 4, &config)
+        };
+        let shapes = |history: &[Session<u64, u64>]| -> Vec<Vec<bool>> {
+            history
+                .iter()
+                .flatten()
+                .map(|transaction| {
+                    transaction
+                        .events
+                        .iter()
+                        .map(|event| matches!(event, Event::Write { .. }))
+                        .collect()
+                })
+                .collect()
+        };
+
+        let history = generate(Workload::Bank);
+        assert!(shapes(&history)
+            .iter()
+            .all(|shape| shape == &[false, false, true, true]));
+        for transaction in history.iter().flatten() {
+            let events = &transaction.events;
+            assert_ne!(events[0].variable(), events[1].variable());
+            assert_eq!(events[0].variable(), events[2].variable());
+            assert_eq!(events[1].variable(), events[3].variable());
+        }
+
+        let history = generate(Workload::WriteSkew);
+        assert!(shapes(&history)
+            .iter()
+            .all(|shape| shape == &[false, false, true]));
+
+        let shapes_of = shapes(&generate(Workload::Register));
+        assert!(shapes_of.contains(&vec![false]));
+        assert!(shapes_of.contains(&vec![false, true]));
+        assert!(shapes_of
+            .iter()
+            .all(|shape| shape == &[false] || shape == &[false, true]));
+
+        // the reads cover groups of 4 variables, and the last group has the 2 remaining ones
+        for transaction in generate(Workload::LongFork).iter().flatten() {
+            let variables: Vec<u64> = transaction.events.iter().map(Event::variable).collect();
+            if let [Event::Write { .. }] = transaction.events[..] {
+                continue;
+            }
+            assert!(transaction
+                .events
+                .iter()
+                .all(|event| matches!(event, Event::Read { .. })));
+            assert_eq!(variables[0] % 4, 0);
+            assert_eq!(variables.len(), if variables[0] == 8 { 2 } else { 4 });
+        }
+    }
+
+    #[test]
+    fn test_session_affinity() {
+        let config = GeneratorConfig::builder().session_affinity(1.0).build();
+        let history = generate_single_history_with_config(4, 20, 10, 5, &config);
+        for (i_node, session) in (0..).zip(&history) {
+            assert!(session
+                .iter()
+                .flat_map(|transaction| &transaction.events)
+                .all(|event| (i_node * 5..(i_node + 1) * 5).contains(&event.variable())));
+        }
+    }
+
+    #[test]
+    fn test_zipf_skew() {
+        let config = GeneratorConfig::builder().zipf_exponent(3.0).build();
+        let history = generate_single_history_with_config(4, 100, 50, 5, &config);
+        let events: Vec<_> = history
+            .iter()
+            .flatten()
+            .flat_map(|transaction| &transaction.events)
+            .collect();
+        let hottest = events.iter().filter(|event| event.variable() == 0).count();
+        // variable 0 receives ~83% of the accesses with exponent 3
+        assert!(hottest * 2 > events.len());
+        assert!(events.iter().all(|event| event.variable() < 100));
+    }
+
+    #[test]
+    fn test_seeded_generation() {
+        let config = GeneratorConfig::builder().seed(42).build();
+        let histories = generate_mult_histories_with_config(3, 3, 10, 5, 4, &config);
+        let again = generate_mult_histories_with_config(3, 3, 10, 5, 4, &config);
+
+        for (history, other) in histories.iter().zip(&again) {
+            let params = history.get_params();
+            assert_eq!(params.config.seed, Some(42 + history.get_id()));
+            assert_eq!(params.config.seed, other.get_params().config.seed);
+            assert_eq!(history.get_data(), other.get_data());
+            // the recorded parameters generate the history again
+            let regenerated = generate_single_history_with_config(
+                params.n_node,
+                params.n_variable,
+                params.n_transaction,
+                params.n_event,
+                &params.config,
+            );
+            assert_eq!(&regenerated, history.get_data());
+        }
+
+        let single = generate_single_history_with_config(
+            3,
+            10,
+            5,
+            4,
+            &GeneratorConfig::builder().seed(43).build(),
+        );
+        assert_eq!(&single, histories[1].get_data());
+    }
+}
@@ -0,0 +1,324 @@
+//! Tabular export of histories, one row per event, and canonical JSON.
+//!
+//! Each row has the columns `session`, `transaction`, `committed`, `event`, `kind`, `variable` and
+//! `version`. Sessions are numbered from 1, as in [`TransactionId`], and transactions and events
+//! from 0 in their session and transaction. `kind` is `r` or `w`, and `version` is empty for a read
+//! of the initial value. The rows are ordered, so the file loads as is in pandas or duckdb and
+//! reads back into the same history.
+//!
+//! Sessions and transactions without events have no row, so they are not exported. A transaction
+//! of unknown commit status is exported as not committed.
+//!
+//! [`normalize_json`] rewrites a JSON history with sorted keys and a fixed indentation, so formatted
+//! histories diff cleanly.
+//!
+//! [`read_order_hints`] reads the happened-before pairs given to
+//! [`CheckSession::with_order_hints`].
+//!
+//! [`CheckSession::with_order_hints`]: dbcop_core::check::CheckSession::with_order_hints
+//! [`TransactionId`]: dbcop_core::history::atomic::types::TransactionId
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
+
+use dbcop_core::check::OrderHint;
+use dbcop_core::history::non_atomic::types::{Event, Session, Transaction};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum EventKind {
+    #[serde(rename = "r")]
+    Read,
+    #[serde(rename = "w")]
+    Write,
+}
+
+/// A row of the tabular format.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EventRow {
+    pub session: u64,
+    pub transaction: u64,
+    pub committed: bool,
+    pub event: u64,
+    pub kind: EventKind,
+    pub variable: u64,
+    pub version: Option<u64>,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Csv(csv::Error),
+    Json(serde_json::Error),
+    /// A row that does not follow the previous one in session, transaction and event order
+    UnorderedRow {
+        row: usize,
+    },
+    /// A write without a version
+    MissingVersion {
+        row: usize,
+    },
+    /// A row whose commit status differs from the previous rows of its transaction
+    InconsistentCommit {
+        row: usize,
+    },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Csv(error) => write!(f, "invalid csv: {error}"),
+            Self::Json(error) => write!(f, "invalid json: {error}"),
+            Self::UnorderedRow { row } => write!(f, "row {row} is out of order"),
+            Self::MissingVersion { row } => write!(f, "write without version at row {row}"),
+            Self::InconsistentCommit { row } => {
+                write!(f, "commit status at row {row} differs from its transaction")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<csv::Error> for Error {
+    fn from(error: csv::Error) -> Self {
+        Self::Csv(error)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Json(error)
+    }
+}
+
+/// Returns the rows of a history, in order.
+pub fn to_rows(histories: &[Session<u64, u64>]) -> impl Iterator<Item = EventRow> + '_ {
+    (1..).zip(histories).flat_map(|(session, transactions)| {
+        (0..).zip(transactions).flat_map(move |(transaction, txn)| {
+            (0..).zip(&txn.events).map(move |(event, ev)| {
+                let (kind, variable, version) = match ev {
+                    Event::Read { variable, version } => (EventKind::Read, *variable, *version),
+                    Event::Write { variable, version } => {
+                        (EventKind::Write, *variable, Some(*version))
+                    }
+                };
+                EventRow {
+                    session,
+                    transaction,
+                    committed: txn.committed,
+                    event,
+                    kind,
+                    variable,
+                    version,
+                }
+            })
+        })
+    })
+}
+
+/// Rebuilds a history from its rows. Sessions and transactions skipped by the rows are empty.
+///
+/// # Errors
+///
+/// Returns an [`Error`] if the rows are not in order or are inconsistent.
+pub fn from_rows(
+    rows: impl IntoIterator<Item = Result<EventRow, Error>>,
+) -> Result<Vec<Session<u64, u64>>, Error> {
+    let mut histories: Vec<Session<u64, u64>> = Vec::new();
+    for (row, event_row) in rows.into_iter().enumerate() {
+        let EventRow {
+            session,
+            transaction,
+            committed,
+            event,
+            kind,
+            variable,
+            version,
+        } = event_row?;
+        let (Ok(session), Ok(transaction), Ok(event)) = (
+            usize::try_from(session),
+            usize::try_from(transaction),
+            usize::try_from(event),
+        ) else {
+            return Err(Error::UnorderedRow { row });
+        };
+
+        if session == 0 || session < histories.len() {
+            return Err(Error::UnorderedRow { row });
+        }
+        histories.resize_with(session, Vec::new);
+        let transactions = &mut histories[session - 1];
+
+        if transaction + 1 < transactions.len() {
+            return Err(Error::UnorderedRow { row });
+        }
+        if transaction + 1 > transactions.len() {
+            transactions.resize_with(transaction, || Transaction::committed(Vec::new()));
+            transactions.push(Transaction {
+                events: Vec::new(),
+                committed,
+                unknown: false,
+                predecessors: None,
+                meta: BTreeMap::new(),
+            });
+        }
+        let txn = &mut transactions[transaction];
+        if txn.committed != committed {
+            return Err(Error::InconsistentCommit { row });
+        }
+        if event != txn.events.len() {
+            return Err(Error::UnorderedRow { row });
+        }
+
+        txn.events.push(match kind {
+            EventKind::Read => Event::Read { variable, version },
+            EventKind::Write => {
+                Event::write(variable, version.ok_or(Error::MissingVersion { row })?)
+            }
+        });
+    }
+    Ok(histories)
+}
+
+/// Writes a history as CSV, with a header row.
+///
+/// # Errors
+///
+/// Returns [`Error::Csv`] if writing fails.
+pub fn write_csv<W: Write>(histories: &[Session<u64, u64>], writer: W) -> Result<(), Error> {
+    let mut writer = csv::Writer::from_writer(writer);
+    for row in to_rows(histories) {
+        writer.serialize(row)?;
+    }
+    writer.flush().map_err(csv::Error::from)?;
+    Ok(())
+}
+
+/// Reads a history written by [`write_csv`].
+///
+/// # Errors
+///
+/// Returns an [`Error`] if the input is not valid CSV or its rows are not in order.
+pub fn read_csv<R: Read>(reader: R) -> Result<Vec<Session<u64, u64>>, Error> {
+    from_rows(
+        csv::Reader::from_reader(reader)
+            .into_deserialize()
+            .map(|row| row.map_err(Error::from)),
+    )
+}
+
+/// Rewrites a JSON document, such as a [`History`] or its sessions, in canonical form.
+///
+/// Object keys are sorted, with two-space indentation and a final newline. Arrays keep their order,
+/// as the order of sessions, transactions and events is meaningful. Reading from standard input and writing to standard output formats a stream.
+///
+/// # Errors
+///
+/// Returns [`Error::Json`] if the input is not valid JSON or writing fails.
+///
+/// [`History`]: crate::generator::History
+pub fn normalize_json<R: Read, W: Write>(reader: R, mut writer: W) -> Result<(), Error> {
+    let value: serde_json::Value = serde_json::from_reader(reader)?;
+    serde_json::to_writer_pretty(&mut writer, &value)?;
+    writer
+        .write_all(b"\n")
+        .and_then(|()| writer.flush())
+        .map_err(serde_json::Error::io)?;
+    Ok(())
+}
+
+/// Reads order hints from a JSON array of `{"before": .., "after": ..}` objects, whose transactions
+/// are `{"session_id": .., "session_height": ..}` objects.
+///
+/// # Errors
+///
+/// Returns [`Error::Json`] if the input is not a valid list of hints.
+pub fn read_order_hints<R: Read>(reader: R) -> Result<Vec<OrderHint>, Error> {
+    Ok(serde_json::from_reader(reader)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_roundtrip() {
+        let histories = vec![
+            vec![
+                Transaction::committed(vec![Event::write(0, 1), Event::read_empty(1)]),
+                Transaction::uncommitted(vec![Event::write(1, 2)]),
+            ],
+            vec![Transaction::committed(vec![
+                Event::read(0, 1),
+                Event::write(1, 3),
+            ])],
+        ];
+
+        let mut buffer = Vec::new();
+        write_csv(&histories, &mut buffer).unwrap();
+        let csv = String::from_utf8(buffer).unwrap();
+
+        assert!(csv.starts_with(
+            "session,transaction,committed,event,kind,variable,version\n\
+             1,0,true,0,w,0,1\n\
+             1,0,true,1,r,1,\n\
+             1,1,false,0,w,1,2\n"
+        ));
+        let read = read_csv(csv.as_bytes()).unwrap();
+        assert_eq!(read.len(), histories.len());
+        for (read, session) in read.iter().zip(&histories) {
+            assert_eq!(read.len(), session.len());
+            for (read, transaction) in read.iter().zip(session) {
+                assert_eq!(read.events, transaction.events);
+                assert_eq!(read.committed, transaction.committed);
+            }
+        }
+    }
+
+    #[test]
+    fn test_unordered_rows() {
+        let csv = "session,transaction,committed,event,kind,variable,version\n\
+                   2,0,true,0,w,0,1\n\
+                   1,0,true,0,w,0,2\n";
+
+        assert!(matches!(
+            read_csv(csv.as_bytes()),
+            Err(Error::UnorderedRow { row: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_normalize_json() {
+        let input = r#"{"end": 2, "data": [[{"events": [{"Write": {"version": 1, "variable": 0}}], "committed": true}]], "start": 1}"#;
+
+        let mut output = Vec::new();
+        normalize_json(input.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.starts_with("{\n  \"data\": [\n"));
+        assert!(output.find("\"committed\"") < output.find("\"events\""));
+        assert!(output.find("\"variable\"") < output.find("\"version\""));
+        assert!(output.ends_with("\"start\": 1\n}\n"));
+
+        let mut again = Vec::new();
+        normalize_json(output.as_bytes(), &mut again).unwrap();
+        assert_eq!(again, output.as_bytes());
+    }
+
+    #[test]
+    fn test_read_order_hints() {
+        let hints = read_order_hints(
+            br#"[{"before": {"session_id": 1, "session_height": 0},
+                  "after": {"session_id": 2, "session_height": 3}}]"#
+                .as_slice(),
+        )
+        .unwrap();
+        assert_eq!(hints.len(), 1);
+        assert_eq!(hints[0].after.session_height, 3);
+        assert!(matches!(
+            read_order_hints(br#"[{"before": {"session_id": 1}}]"#.as_slice()),
+            Err(Error::Json(_))
+        ));
+    }
+}
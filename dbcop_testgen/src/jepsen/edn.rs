@@ -0,0 +1,279 @@
+//! A reader of the EDN that Jepsen writes its histories in, into JSON values.
+//!
+//! Keywords and symbols become strings without their colon, and maps become objects whose keys are
+//! printed as strings. Vectors, lists and sets become arrays. A tagged element, such as the
+//! `#jepsen.history.Op{...}` records of recent Jepsen versions or an `#inst "..."` instant, becomes
+//! its value, and `#_` discards the next element. Characters become strings.
+
+use serde_json::{Map, Number, Value};
+
+use super::Error;
+
+/// Reads all the top-level elements of `input`.
+pub fn read_all(input: &str) -> Result<Vec<Value>, Error> {
+    let mut reader = Reader {
+        input: input.as_bytes(),
+        offset: 0,
+    };
+    let mut values = Vec::new();
+    while let Some(value) = reader.next()? {
+        values.push(value);
+    }
+    Ok(values)
+}
+
+struct Reader<'a> {
+    input: &'a [u8],
+    offset: usize,
+}
+
+impl Reader<'_> {
+    const fn error(&self, reason: &'static str) -> Error {
+        Error::Edn {
+            offset: self.offset,
+            reason,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.offset).copied()
+    }
+
+    /// Skips whitespace, commas, comments and discarded elements.
+    fn skip_ignored(&mut self) -> Result<(), Error> {
+        while let Some(byte) = self.peek() {
+            match byte {
+                b';' => {
+                    while self.peek().is_some_and(|byte| byte != b'\n') {
+                        self.offset += 1;
+                    }
+                }
+                b'#' if self.input.get(self.offset + 1) == Some(&b'_') => {
+                    self.offset += 2;
+                    self.element()?;
+                }
+                byte if byte == b',' || byte.is_ascii_whitespace() => self.offset += 1,
+                _ => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the next element, or `None` at the end of the input.
+    fn next(&mut self) -> Result<Option<Value>, Error> {
+        self.skip_ignored()?;
+        let Some(byte) = self.peek() else {
+            return Ok(None);
+        };
+        let value = match byte {
+            b'[' => {
+                self.offset += 1;
+                Value::Array(self.elements(b']')?)
+            }
+            b'(' => {
+                self.offset += 1;
+                Value::Array(self.elements(b')')?)
+            }
+            b'{' => {
+                self.offset += 1;
+                self.map()?
+            }
+            b'"' => Value::String(self.string()?),
+            b'#' => self.dispatch()?,
+            b']' | b')' | b'}' => return Err(self.error("unexpected closing delimiter")),
+            _ => self.atom()?,
+        };
+        Ok(Some(value))
+    }
+
+    fn element(&mut self) -> Result<Value, Error> {
+        self.next()?
+            .ok_or_else(|| self.error("unexpected end of input"))
+    }
+
+    /// Reads the elements of a collection, up to its `close` delimiter.
+    fn elements(&mut self, close: u8) -> Result<Vec<Value>, Error> {
+        let mut elements = Vec::new();
+        loop {
+            self.skip_ignored()?;
+            match self.peek() {
+                Some(byte) if byte == close => {
+                    self.offset += 1;
+                    return Ok(elements);
+                }
+                Some(_) => elements.push(self.element()?),
+                None => return Err(self.error("unclosed collection")),
+            }
+        }
+    }
+
+    fn map(&mut self) -> Result<Value, Error> {
+        let elements = self.elements(b'}')?;
+        if elements.len() % 2 != 0 {
+            return Err(self.error("map with an odd number of elements"));
+        }
+        let mut map = Map::new();
+        let mut elements = elements.into_iter();
+        while let (Some(key), Some(value)) = (elements.next(), elements.next()) {
+            let key = match key {
+                Value::String(key) => key,
+                key => key.to_string(),
+            };
+            map.insert(key, value);
+        }
+        Ok(Value::Object(map))
+    }
+
+    /// Reads a set, or the value of a tagged element.
+    fn dispatch(&mut self) -> Result<Value, Error> {
+        self.offset += 1;
+        if self.peek() == Some(b'{') {
+            self.offset += 1;
+            return Ok(Value::Array(self.elements(b'}')?));
+        }
+        // the tag, such as `inst` or `jepsen.history.Op`, is dropped
+        let tag = self.token();
+        if tag.is_empty() {
+            return Err(self.error("malformed tag"));
+        }
+        self.element()
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        self.offset += 1;
+        let mut bytes = Vec::new();
+        loop {
+            let byte = self.peek().ok_or_else(|| self.error("unclosed string"))?;
+            self.offset += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let escaped = self.peek().ok_or_else(|| self.error("unclosed string"))?;
+                    self.offset += 1;
+                    match escaped {
+                        b'n' => bytes.push(b'\n'),
+                        b't' => bytes.push(b'\t'),
+                        b'r' => bytes.push(b'\r'),
+                        b'u' => {
+                            let code = self
+                                .input
+                                .get(self.offset..self.offset + 4)
+                                .and_then(|hex| std::str::from_utf8(hex).ok())
+                                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.error("malformed unicode escape"))?;
+                            self.offset += 4;
+                            bytes.extend_from_slice(code.encode_utf8(&mut [0; 4]).as_bytes());
+                        }
+                        escaped => bytes.push(escaped),
+                    }
+                }
+                byte => bytes.push(byte),
+            }
+        }
+        // the input is valid UTF-8, and it is only split at ASCII bytes
+        String::from_utf8(bytes).map_err(|_| self.error("malformed string"))
+    }
+
+    /// Reads the text of a keyword, symbol, number or character.
+    fn token(&mut self) -> &str {
+        let start = self.offset;
+        while self
+            .peek()
+            .is_some_and(|byte| !byte.is_ascii_whitespace() && !b",;\"()[]{}".contains(&byte))
+        {
+            self.offset += 1;
+        }
+        // a token ends at an ASCII byte, or at the end of the input
+        std::str::from_utf8(&self.input[start..self.offset]).unwrap_or_default()
+    }
+
+    fn atom(&mut self) -> Result<Value, Error> {
+        let token = self.token();
+        if let Some(keyword) = token.strip_prefix(':') {
+            return Ok(Value::String(keyword.to_owned()));
+        }
+        if let Some(character) = token.strip_prefix('\\') {
+            let character = match character {
+                "newline" => "\n",
+                "space" => " ",
+                "tab" => "\t",
+                "return" => "\r",
+                character => character,
+            };
+            return Ok(Value::String(character.to_owned()));
+        }
+        let value = match token {
+            "nil" => Value::Null,
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            token if is_number(token) => {
+                number(token).ok_or_else(|| self.error("malformed number"))?
+            }
+            symbol => Value::String(symbol.to_owned()),
+        };
+        Ok(value)
+    }
+}
+
+fn is_number(token: &str) -> bool {
+    let digits = token.strip_prefix(['+', '-']).unwrap_or(token);
+    digits.starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// Parses an integer, with an optional arbitrary precision `N` suffix, or a floating point number,
+/// with an optional `M` suffix.
+fn number(token: &str) -> Option<Value> {
+    if let Ok(integer) = token.strip_suffix('N').unwrap_or(token).parse::<i64>() {
+        return Some(Value::from(integer));
+    }
+    if let Ok(integer) = token
+        .strip_suffix('N')
+        .unwrap_or(token)
+        .trim_start_matches('+')
+        .parse::<u64>()
+    {
+        return Some(Value::from(integer));
+    }
+    token
+        .strip_suffix('M')
+        .unwrap_or(token)
+        .parse::<f64>()
+        .ok()
+        .and_then(Number::from_f64)
+        .map(Value::Number)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_read_all() {
+        let input = r#"
+{:a/b [1 -2 3N 1.5M], "k" #{nil true}, (false \x) :v} ; a comment
+"é \"q\"\n" #inst "2024-01-01T00:00:00Z" #_ {:discarded 1} sym
+"#;
+
+        assert_eq!(
+            read_all(input).unwrap(),
+            vec![
+                json!({"a/b": [1, -2, 3, 1.5], "k": [null, true], "[false,\"x\"]": "v"}),
+                json!("é \"q\"\n"),
+                json!("2024-01-01T00:00:00Z"),
+                json!("sym"),
+            ]
+        );
+
+        assert!(matches!(
+            read_all("[1 2"),
+            Err(Error::Edn {
+                offset: 4,
+                reason: "unclosed collection"
+            })
+        ));
+        assert!(matches!(read_all("{:a 1 :b}"), Err(Error::Edn { .. })));
+    }
+}
@@ -0,0 +1,564 @@
+//! Imports Jepsen transactional histories, over registers or lists.
+//!
+//! A Jepsen history is a sequence of operations, each an `invoke` followed by its completion by the
+//! same process: `ok`, `fail` or `info`. Transactions have `f = "txn"` and a list of micro-operations
+//! `[f, key, value]`. The value of a read is only known on completion.
+//! - In a register history, `f` is `r` or `w`, and a read returns a value, or `null` when the key
+//!   was never written.
+//! - In a list-append history, `f` is `r` or `append`, and a read returns the whole list. It is
+//!   reduced to a register history by [`list_append::to_register_history`].
+//!
+//! Each process becomes a session, in process order. Completions are handled as follows.
+//! - `ok`: a committed transaction with the completed micro-operations.
+//! - `fail`: an aborted transaction with the invoked writes or appends. Its reads are unknown and
+//!   dropped.
+//! - `info`: the transaction may or may not have committed. It is kept with an unknown commit
+//!   status and the invoked writes or appends, so the checkers take it as committed if another
+//!   transaction reads one of its writes, and as aborted otherwise. Jepsen never reuses the process
+//!   of an `info` operation, so it is the last transaction of its session.
+//! - no completion, when the history ends first: the same as `info`.
+//!
+//! Operations of non-numeric processes, such as the nemesis, are skipped.
+//!
+//! The input is either EDN, as in the `history.edn` of a Jepsen test, or JSON. Either is an array
+//! of operations or one operation per line.
+
+mod edn;
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+use dbcop_core::history::list_append;
+use dbcop_core::history::list_append::types::{ListEvent, ListSession, ListTransaction};
+use dbcop_core::history::non_atomic::types::{Event, Session, Transaction};
+use dbcop_core::Consistency;
+use serde::Deserialize;
+use serde_json::Value;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationType {
+    Invoke,
+    Ok,
+    Fail,
+    Info,
+}
+
+/// A Jepsen operation, with its EDN keywords as strings.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Operation {
+    #[serde(rename = "type")]
+    pub kind: OperationType,
+    pub process: Value,
+    pub f: String,
+    #[serde(default)]
+    pub value: Value,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Json(serde_json::Error),
+    /// Malformed EDN, at a byte offset of the input
+    Edn {
+        offset: usize,
+        reason: &'static str,
+    },
+    /// A completion without a pending invocation on its process
+    UnmatchedCompletion {
+        process: u64,
+    },
+    /// An operation other than a transaction
+    UnsupportedOperation {
+        f: String,
+    },
+    /// A micro-operation other than `r` and `w`, or `r` and `append`
+    UnsupportedMicroOperation {
+        f: String,
+    },
+    /// A micro-operation that is not `[f, key, value]` with an unsigned integer value, a list of
+    /// them for a list read, or `null` for a read
+    MalformedMicroOperation {
+        value: Value,
+    },
+    /// A list-append history without a consistent reduction to registers
+    ListAppend(list_append::error::Error<String, u64>),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Json(error) => write!(f, "invalid json: {error}"),
+            Self::Edn { offset, reason } => write!(f, "invalid edn at byte {offset}: {reason}"),
+            Self::UnmatchedCompletion { process } => {
+                write!(f, "completion without invocation on process {process}")
+            }
+            Self::UnsupportedOperation { f: op } => write!(f, "unsupported operation {op:?}"),
+            Self::UnsupportedMicroOperation { f: op } => {
+                write!(f, "unsupported micro-operation {op:?}")
+            }
+            Self::MalformedMicroOperation { value } => {
+                write!(f, "malformed micro-operation {value}")
+            }
+            Self::ListAppend(error) => write!(f, "invalid list-append history: {error:?}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Json(error)
+    }
+}
+
+impl From<list_append::error::Error<String, u64>> for Error {
+    fn from(error: list_append::error::Error<String, u64>) -> Self {
+        Self::ListAppend(error)
+    }
+}
+
+/// Parses a JSON array of operations, or one JSON operation per line.
+///
+/// # Errors
+///
+/// Returns [`Error::Json`] if the input is not a valid Jepsen JSON history.
+pub fn parse_json(input: &str) -> Result<Vec<Operation>, Error> {
+    if input.trim_start().starts_with('[') {
+        return Ok(serde_json::from_str(input)?);
+    }
+    input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Ok(serde_json::from_str(line)?))
+        .collect()
+}
+
+/// Parses an EDN vector of operations, or a sequence of EDN operations.
+///
+/// # Errors
+///
+/// Returns [`Error::Edn`] if the input is not valid EDN, and [`Error::Json`] if an element is not
+/// a Jepsen operation.
+pub fn parse_edn(input: &str) -> Result<Vec<Operation>, Error> {
+    let mut elements = edn::read_all(input)?;
+    if let [Value::Array(operations)] = elements.as_mut_slice() {
+        elements = std::mem::take(operations);
+    }
+    elements
+        .into_iter()
+        .map(|element| Ok(serde_json::from_value(element)?))
+        .collect()
+}
+
+/// Parses an EDN or a JSON history, whichever `input` starts like.
+///
+/// # Errors
+///
+/// Returns an [`Error`] if the input is not a valid Jepsen history.
+pub fn parse(input: &str) -> Result<Vec<Operation>, Error> {
+    if is_edn(input) {
+        parse_edn(input)
+    } else {
+        parse_json(input)
+    }
+}
+
+/// Converts a Jepsen history into sessions to check at `level`. A list-append history is reduced
+/// to registers for `level`, and a register history does not depend on it. See the module
+/// documentation.
+///
+/// # Errors
+///
+/// Returns an [`Error`] if the history is malformed or uses unsupported operations.
+pub fn import(input: &str, level: Consistency) -> Result<Vec<Session<String, u64>>, Error> {
+    let operations = parse(input)?;
+    if is_list_append(&operations) {
+        let histories = from_list_operations(operations)?;
+        Ok(list_append::to_register_history(&histories, level)?)
+    } else {
+        from_operations(operations)
+    }
+}
+
+/// Converts a JSON Jepsen register history into sessions. See the module documentation.
+///
+/// # Errors
+///
+/// Returns an [`Error`] if the history is malformed or uses unsupported operations.
+pub fn from_json(input: &str) -> Result<Vec<Session<String, u64>>, Error> {
+    from_operations(parse_json(input)?)
+}
+
+/// Whether the input starts like an EDN history: a map, possibly in a vector, with a keyword key,
+/// such as `{:type :invoke, ...}`, or a tagged record, such as `#jepsen.history.Op{...}`.
+fn is_edn(input: &str) -> bool {
+    let input = input.trim_start();
+    let input = input.strip_prefix('[').unwrap_or(input).trim_start();
+    input.starts_with('#')
+        || input
+            .strip_prefix('{')
+            .is_some_and(|map| map.trim_start().starts_with(':'))
+}
+
+/// Whether the transactions append to lists or read lists, rather than registers.
+fn is_list_append(operations: &[Operation]) -> bool {
+    operations
+        .iter()
+        .filter_map(|operation| operation.value.as_array())
+        .flatten()
+        .filter_map(Value::as_array)
+        .any(|micro| match micro.as_slice() {
+            [f, _, value] => f == "append" || (f == "r" && value.is_array()),
+            _ => false,
+        })
+}
+
+fn key(key: &Value) -> String {
+    match key {
+        Value::String(key) => key.clone(),
+        key => key.to_string(),
+    }
+}
+
+/// A micro-operation, with its function, key and value.
+type MicroOperation<'a> = (&'a Value, &'a str, &'a Value, &'a Value);
+
+/// Splits the micro-operations of a transaction.
+fn micro_operations(value: &Value) -> Result<Vec<MicroOperation<'_>>, Error> {
+    value
+        .as_array()
+        .ok_or_else(|| malformed(value))?
+        .iter()
+        .map(|micro| match micro.as_array().map(Vec::as_slice) {
+            Some([f, variable, value]) => {
+                let f = f.as_str().ok_or_else(|| malformed(micro))?;
+                Ok((micro, f, variable, value))
+            }
+            _ => Err(malformed(micro)),
+        })
+        .collect()
+}
+
+fn malformed(value: &Value) -> Error {
+    Error::MalformedMicroOperation {
+        value: value.clone(),
+    }
+}
+
+/// Parses the micro-operations of a register transaction.
+fn events(value: &Value) -> Result<Vec<Event<String, u64>>, Error> {
+    micro_operations(value)?
+        .into_iter()
+        .map(|(micro, f, variable, version)| match f {
+            "r" => Ok(Event::Read {
+                variable: key(variable),
+                version: match version {
+                    Value::Null => None,
+                    version => Some(version.as_u64().ok_or_else(|| malformed(micro))?),
+                },
+            }),
+            // the value of an invoked write is known
+            "w" => Ok(Event::write(
+                key(variable),
+                version.as_u64().ok_or_else(|| malformed(micro))?,
+            )),
+            f => Err(Error::UnsupportedMicroOperation { f: f.to_owned() }),
+        })
+        .collect()
+}
+
+/// Parses the micro-operations of a list-append transaction.
+fn list_events(value: &Value) -> Result<Vec<ListEvent<String, u64>>, Error> {
+    micro_operations(value)?
+        .into_iter()
+        .map(|(micro, f, variable, element)| match f {
+            // the list of an invoked read is unknown, and dropped with the read
+            "r" => Ok(ListEvent::read(
+                key(variable),
+                element
+                    .as_array()
+                    .map_or_else(
+                        || element.is_null().then(Vec::new),
+                        |values| values.iter().map(Value::as_u64).collect(),
+                    )
+                    .ok_or_else(|| malformed(micro))?,
+            )),
+            "append" => Ok(ListEvent::append(
+                key(variable),
+                element.as_u64().ok_or_else(|| malformed(micro))?,
+            )),
+            f => Err(Error::UnsupportedMicroOperation { f: f.to_owned() }),
+        })
+        .collect()
+}
+
+/// The micro-operations of a transaction, with the type of its completion.
+type Completed<E> = (OperationType, Vec<E>);
+
+/// Groups the transactions of `operations` by process, with the type of their completion and
+/// their micro-operations: the completed ones for `ok`, and the invoked `writes` otherwise. A
+/// transaction without completion is completed as `info`.
+fn transactions<E>(
+    operations: impl IntoIterator<Item = Operation>,
+    events: fn(&Value) -> Result<Vec<E>, Error>,
+    writes: fn(&E) -> bool,
+) -> Result<Vec<Vec<Completed<E>>>, Error> {
+    let mut pending: BTreeMap<u64, Operation> = BTreeMap::new();
+    let mut sessions: BTreeMap<u64, Vec<Completed<E>>> = BTreeMap::new();
+    let invoked_writes = |invocation: &Operation| -> Result<Vec<E>, Error> {
+        let mut events = events(&invocation.value)?;
+        events.retain(writes);
+        Ok(events)
+    };
+
+    for operation in operations {
+        let Some(process) = operation.process.as_u64() else {
+            continue;
+        };
+        if operation.f != "txn" {
+            return Err(Error::UnsupportedOperation { f: operation.f });
+        }
+        if operation.kind == OperationType::Invoke {
+            pending.insert(process, operation);
+            continue;
+        }
+        let invocation = pending
+            .remove(&process)
+            .ok_or(Error::UnmatchedCompletion { process })?;
+        let events = if operation.kind == OperationType::Ok {
+            events(&operation.value)?
+        } else {
+            invoked_writes(&invocation)?
+        };
+        sessions
+            .entry(process)
+            .or_default()
+            .push((operation.kind, events));
+    }
+
+    // the invocations that never completed may have committed
+    for (process, invocation) in pending {
+        sessions
+            .entry(process)
+            .or_default()
+            .push((OperationType::Info, invoked_writes(&invocation)?));
+    }
+
+    Ok(sessions.into_values().collect())
+}
+
+/// Converts the operations of a Jepsen register history into sessions. See the module
+/// documentation.
+///
+/// # Errors
+///
+/// Returns an [`Error`] if the history is malformed or uses unsupported operations.
+pub fn from_operations(
+    operations: impl IntoIterator<Item = Operation>,
+) -> Result<Vec<Session<String, u64>>, Error> {
+    let sessions = transactions(operations, events, |event| {
+        matches!(event, Event::Write { .. })
+    })?;
+    Ok(sessions
+        .into_iter()
+        .map(|session| {
+            session
+                .into_iter()
+                .map(|(kind, events)| match kind {
+                    OperationType::Ok => Transaction::committed(events),
+                    OperationType::Fail => Transaction::uncommitted(events),
+                    _ => Transaction::unknown(events),
+                })
+                .collect()
+        })
+        .collect())
+}
+
+/// Converts the operations of a Jepsen list-append history into list sessions. See the module
+/// documentation.
+///
+/// # Errors
+///
+/// Returns an [`Error`] if the history is malformed or uses unsupported operations.
+pub fn from_list_operations(
+    operations: impl IntoIterator<Item = Operation>,
+) -> Result<Vec<ListSession<String, u64>>, Error> {
+    let sessions = transactions(operations, list_events, |event| {
+        matches!(event, ListEvent::Append { .. })
+    })?;
+    Ok(sessions
+        .into_iter()
+        .map(|session| {
+            session
+                .into_iter()
+                .map(|(kind, events)| match kind {
+                    OperationType::Ok => ListTransaction::committed(events),
+                    OperationType::Fail => ListTransaction::uncommitted(events),
+                    _ => ListTransaction::unknown(events),
+                })
+                .collect()
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use dbcop_core::check::check;
+    use dbcop_core::solver::causal::check_causal_read;
+    use dbcop_core::Consistency;
+
+    use super::*;
+
+    #[test]
+    fn test_from_json() {
+        let input = r#"
+{"type": "invoke", "process": 0, "f": "txn", "value": [["w", "x", 1], ["r", "y", null]]}
+{"type": "invoke", "process": 1, "f": "txn", "value": [["r", "x", null], ["w", "y", 2]]}
+{"type": "invoke", "process": "nemesis", "f": "kill", "value": null}
+{"type": "ok", "process": 0, "f": "txn", "value": [["w", "x", 1], ["r", "y", null]]}
+{"type": "info", "process": 1, "f": "txn", "value": [["r", "x", null], ["w", "y", 2]]}
+{"type": "invoke", "process": 0, "f": "txn", "value": [["w", "y", 3]]}
+{"type": "fail", "process": 0, "f": "txn", "value": [["w", "y", 3]]}
+{"type": "invoke", "process": 2, "f": "txn", "value": [["r", "x", null]]}
+{"type": "ok", "process": 2, "f": "txn", "value": [["r", "x", 1]]}
+"#;
+
+        let sessions = from_json(input).unwrap();
+
+        assert_eq!(sessions.len(), 3);
+        assert_eq!(
+            sessions[0][0].events,
+            vec![
+                Event::write("x".to_owned(), 1),
+                Event::read_empty("y".to_owned())
+            ]
+        );
+        assert!(sessions[0][0].committed);
+        assert_eq!(sessions[0][1].events, vec![Event::write("y".to_owned(), 3)]);
+        assert!(!sessions[0][1].committed);
+        // the indeterminate transaction of process 1 keeps its writes only
+        assert_eq!(sessions[1][0].events, vec![Event::write("y".to_owned(), 2)]);
+        assert!(sessions[1][0].unknown);
+        assert_eq!(sessions[2][0].events, vec![Event::read("x".to_owned(), 1)]);
+
+        assert!(check_causal_read(&sessions).is_ok());
+    }
+
+    #[test]
+    fn test_observed_indeterminate_write() {
+        let input = r#"[
+{"type": "invoke", "process": 0, "f": "txn", "value": [["w", 1, 1]]},
+{"type": "info", "process": 0, "f": "txn", "value": null},
+{"type": "invoke", "process": 1, "f": "txn", "value": [["r", 1, null]]},
+{"type": "ok", "process": 1, "f": "txn", "value": [["r", 1, 1]]}
+]"#;
+
+        let sessions = from_json(input).unwrap();
+
+        assert_eq!(sessions[0][0].events, vec![Event::write("1".to_owned(), 1)]);
+        assert!(sessions[0][0].unknown);
+        assert!(check(&sessions, Consistency::Serializable).is_ok());
+    }
+
+    #[test]
+    fn test_pending_invocation() {
+        // the history ends before the write of process 0 completes, but process 1 reads it
+        let input = r#"[
+{"type": "invoke", "process": 0, "f": "txn", "value": [["r", "x", null], ["w", "x", 1]]},
+{"type": "invoke", "process": 1, "f": "txn", "value": [["r", "x", null]]},
+{"type": "ok", "process": 1, "f": "txn", "value": [["r", "x", 1]]}
+]"#;
+
+        let sessions = from_json(input).unwrap();
+
+        assert_eq!(sessions[0][0].events, vec![Event::write("x".to_owned(), 1)]);
+        assert!(sessions[0][0].unknown);
+        assert!(check(&sessions, Consistency::Serializable).is_ok());
+    }
+
+    #[test]
+    fn test_edn() {
+        // as written by Jepsen, with the fields that are not needed
+        let input = r"
+{:index 0, :time 1021, :type :invoke, :process 0, :f :txn, :value [[:w :x 1] [:r :y nil]]}
+{:index 1, :time 1500, :type :invoke, :process :nemesis, :f :start, :value nil}
+{:index 2, :time 2210, :type :ok, :process 0, :f :txn, :value [[:w :x 1] [:r :y nil]]}
+; a record of recent Jepsen versions
+#jepsen.history.Op{:index 3, :time 2300, :type :invoke, :process 1, :f :txn, :value [[:r :x nil]]}
+{:index 4, :time 2400, :type :ok, :process 1, :f :txn, :value [[:r :x 1]], :error #_ignored nil}
+";
+
+        let sessions = import(input, Consistency::Serializable).unwrap();
+
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(
+            sessions[0][0].events,
+            vec![
+                Event::write("x".to_owned(), 1),
+                Event::read_empty("y".to_owned())
+            ]
+        );
+        assert_eq!(sessions[1][0].events, vec![Event::read("x".to_owned(), 1)]);
+        // the same history, in a vector
+        assert_eq!(parse(&format!("[{input}]")).unwrap().len(), 5);
+
+        assert!(matches!(
+            import("{:type :invoke, :process 0", Consistency::Serializable),
+            Err(Error::Edn { .. })
+        ));
+    }
+
+    #[test]
+    fn test_list_append() {
+        let input = r"
+{:type :invoke, :process 0, :f :txn, :value [[:append :x 1] [:r :x nil]]}
+{:type :ok, :process 0, :f :txn, :value [[:append :x 1] [:r :x [1]]]}
+{:type :invoke, :process 1, :f :txn, :value [[:append :x 2]]}
+{:type :invoke, :process 2, :f :txn, :value [[:append :x 3]]}
+{:type :fail, :process 2, :f :txn, :value [[:append :x 3]]}
+{:type :invoke, :process 3, :f :txn, :value [[:r :x nil]]}
+{:type :ok, :process 3, :f :txn, :value [[:r :x [1 2]]]}
+";
+
+        let histories = from_list_operations(parse(input).unwrap()).unwrap();
+        assert_eq!(
+            histories[0][0].events,
+            vec![
+                ListEvent::append("x".to_owned(), 1),
+                ListEvent::read("x".to_owned(), vec![1])
+            ]
+        );
+        // the append of process 1 never completed, but process 3 reads it
+        assert_eq!(
+            histories[1][0].events,
+            vec![ListEvent::append("x".to_owned(), 2)]
+        );
+        assert!(histories[1][0].unknown);
+        assert!(!histories[2][0].committed && !histories[2][0].unknown);
+
+        for level in Consistency::LEVELS {
+            let sessions = import(input, level).unwrap();
+            assert!(sessions[1][0].unknown);
+            assert!(check(&sessions, level).is_ok(), "{level:?}");
+        }
+    }
+
+    #[test]
+    fn test_unsupported_micro_operation() {
+        let input = r#"{"type": "invoke", "process": 0, "f": "txn", "value": [["cas", "x", [1, 2]]]}
+{"type": "ok", "process": 0, "f": "txn", "value": [["cas", "x", [1, 2]]]}"#;
+
+        assert!(matches!(
+            from_json(input),
+            Err(Error::UnsupportedMicroOperation { f }) if f == "cas"
+        ));
+        // appends are only supported in list-append histories
+        let input = input.replace("cas", "append").replace("[1, 2]", "1");
+        assert!(matches!(
+            from_json(&input),
+            Err(Error::UnsupportedMicroOperation { f }) if f == "append"
+        ));
+        assert!(import(&input, Consistency::Serializable).is_ok());
+    }
+}
@@ -2,5 +2,16 @@
 
 #![cfg_attr(not(test), no_main)]
 
+#[cfg(feature = "compact-binary")]
+pub mod binary;
+pub mod cache;
+pub mod checkpoint;
+pub mod corpus;
+#[cfg(feature = "compression")]
+pub mod compress;
 pub mod driver;
 pub mod generator;
+pub mod io;
+pub mod jepsen;
+pub mod separating;
+pub mod simulator;
@@ -0,0 +1,116 @@
+//! Histories that satisfy a consistency level but violate the next stronger one.
+//!
+//! Each level has a smallest anomaly that separates it from the next level:
+//!
+//! | satisfies          | violates           | anomaly                                        |
+//! |--------------------|--------------------|------------------------------------------------|
+//! | committed read     | repeatable read    | a transaction reads two versions of a variable |
+//! | repeatable read    | atomic read        | a transaction sees only some writes of another |
+//! | atomic read        | causal             | a read misses a write it causally depends on   |
+//! | causal             | prefix             | long fork of two concurrent writes             |
+//! | prefix             | snapshot isolation | lost update                                    |
+//! | snapshot isolation | serializable       | write skew                                     |
+//!
+//! A history of size `n` has `n` copies of the anomaly, each on its own sessions and variables, so
+//! the copies do not interact and the history is as strong as a single copy.
+
+use dbcop_core::history::non_atomic::types::{Event, Session, Transaction};
+use dbcop_core::Consistency;
+
+/// The sessions of one copy of the anomaly of `level`, on the variables `x` and `y`.
+fn anomaly(level: Consistency, x: u64, y: u64) -> Option<Vec<Session<u64, u64>>> {
+    let sessions = match level {
+        Consistency::CommittedRead => vec![
+            vec![vec![Event::write(x, 1)]],
+            vec![vec![Event::write(x, 2)]],
+            vec![vec![Event::read(x, 1), Event::read(x, 2)]],
+        ],
+        Consistency::RepeatableRead => vec![
+            vec![vec![Event::write(x, 1), Event::write(y, 1)]],
+            vec![vec![Event::read(x, 1), Event::read_empty(y)]],
+        ],
+        Consistency::AtomicRead => vec![
+            vec![vec![Event::write(x, 1)]],
+            vec![vec![Event::read(x, 1), Event::write(y, 1)]],
+            vec![vec![Event::read(y, 1), Event::read_empty(x)]],
+        ],
+        Consistency::Causal => vec![
+            vec![vec![Event::write(x, 1)]],
+            vec![vec![Event::write(y, 1)]],
+            vec![vec![Event::read(x, 1), Event::read_empty(y)]],
+            vec![vec![Event::read(y, 1), Event::read_empty(x)]],
+        ],
+        Consistency::Prefix => vec![
+            vec![vec![Event::read_empty(x), Event::write(x, 1)]],
+            vec![vec![Event::read_empty(x), Event::write(x, 2)]],
+        ],
+        Consistency::SnapshotIsolation => vec![
+            vec![vec![
+                Event::read_empty(x),
+                Event::read_empty(y),
+                Event::write(x, 1),
+            ]],
+            vec![vec![
+                Event::read_empty(x),
+                Event::read_empty(y),
+                Event::write(y, 1),
+            ]],
+        ],
+        Consistency::Serializable => return None,
+    };
+    Some(
+        sessions
+            .into_iter()
+            .map(|session| session.into_iter().map(Transaction::committed).collect())
+            .collect(),
+    )
+}
+
+/// Generates `size` copies of the anomaly that separates `level` from the next stronger level.
+///
+/// Returns `None` for [`Consistency::Serializable`], the strongest level.
+#[must_use]
+pub fn generate_separating_history(
+    level: Consistency,
+    size: u64,
+) -> Option<Vec<Session<u64, u64>>> {
+    let mut histories = Vec::new();
+    for copy in 0..size {
+        histories.extend(anomaly(level, 2 * copy, 2 * copy + 1)?);
+    }
+    Some(histories)
+}
+
+#[cfg(test)]
+mod tests {
+    use dbcop_core::check::check;
+
+    use super::*;
+
+    #[test]
+    fn test_separating_histories() {
+        let levels = [
+            Consistency::CommittedRead,
+            Consistency::RepeatableRead,
+            Consistency::AtomicRead,
+            Consistency::Causal,
+            Consistency::Prefix,
+            Consistency::SnapshotIsolation,
+            Consistency::Serializable,
+        ];
+        for (level, stronger) in levels.iter().zip(&levels[1..]) {
+            for size in 1..=3 {
+                let histories = generate_separating_history(*level, size).unwrap();
+                assert!(
+                    check(&histories, *level).is_ok(),
+                    "{level:?} of size {size}"
+                );
+                assert!(
+                    check(&histories, *stronger).is_err(),
+                    "{stronger:?} of size {size}"
+                );
+            }
+        }
+        assert!(generate_separating_history(Consistency::Serializable, 1).is_none());
+    }
+}
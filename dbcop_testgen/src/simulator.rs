@@ -0,0 +1,176 @@
+//! A buggy in-memory database, to generate histories with known verdicts.
+//!
+//! [`simulate`] executes the transactions of generated sessions one at a time, picking the next
+//! session at random, on a store of the latest committed version of each variable. Some
+//! transactions abort, and their writes are not installed. A read returns the latest version
+//! written by its own transaction, or else, with some probability, a version written by an aborted
+//! transaction, or else the latest committed version.
+//!
+//! Without dirty reads, the committed transactions execute serially, so the history satisfies every
+//! level. A dirty read reads a write of an aborted transaction, which violates every level. The
+//! verdicts of [`Simulated::expected`] are known without running a checker.
+
+use std::collections::HashMap;
+
+use dbcop_core::history::non_atomic::types::{Event, Session, Transaction};
+use dbcop_core::Consistency;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use typed_builder::TypedBuilder;
+
+/// Faults of the simulated database.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, TypedBuilder)]
+pub struct SimulatorConfig {
+    /// Probability of a transaction aborting. Must be in `[0, 1]`.
+    #[builder(default = 0.0)]
+    #[serde(default)]
+    pub abort_ratio: f64,
+    /// Probability of a read returning a write of an aborted transaction, if there is one on its
+    /// variable. Must be in `[0, 1]`.
+    #[builder(default = 0.0)]
+    #[serde(default)]
+    pub dirty_read_ratio: f64,
+}
+
+/// A simulated history, with its ground truth.
+#[derive(Clone, Debug)]
+pub struct Simulated {
+    pub histories: Vec<Session<u64, u64>>,
+    /// Number of aborted transactions
+    pub aborted: usize,
+    /// Number of reads of a write of an aborted transaction
+    pub dirty_reads: usize,
+}
+
+impl Simulated {
+    /// Returns whether the history satisfies `level`.
+    #[must_use]
+    pub const fn expected(&self, _level: Consistency) -> bool {
+        self.dirty_reads == 0
+    }
+
+    /// Returns the verdict of every level of [`Consistency::LEVELS`].
+    #[must_use]
+    pub fn expected_verdicts(&self) -> Vec<(Consistency, bool)> {
+        Consistency::LEVELS
+            .iter()
+            .map(|&level| (level, self.expected(level)))
+            .collect()
+    }
+}
+
+/// Executes the transactions of `plan`, such as a generated history, and returns the observed
+/// history. The versions read in `plan` are ignored.
+pub fn simulate<R: Rng + ?Sized>(
+    plan: &[Session<u64, u64>],
+    config: &SimulatorConfig,
+    rng: &mut R,
+) -> Simulated {
+    let mut histories: Vec<Session<u64, u64>> = plan.iter().map(|_| Vec::new()).collect();
+    let mut committed: HashMap<u64, u64> = HashMap::new();
+    let mut aborted_writes: HashMap<u64, Vec<u64>> = HashMap::new();
+    let mut simulated = Simulated {
+        histories: Vec::new(),
+        aborted: 0,
+        dirty_reads: 0,
+    };
+
+    loop {
+        let pending: Vec<usize> = (0..plan.len())
+            .filter(|&i| histories[i].len() < plan[i].len())
+            .collect();
+        if pending.is_empty() {
+            break;
+        }
+        let session = pending[rng.gen_range(0..pending.len())];
+        let planned = &plan[session][histories[session].len()];
+
+        let mut own: HashMap<u64, u64> = HashMap::new();
+        let events = planned
+            .events
+            .iter()
+            .map(|event| match *event {
+                Event::Write { variable, version } => {
+                    own.insert(variable, version);
+                    Event::write(variable, version)
+                }
+                Event::Read { variable, .. } => {
+                    if let Some(&version) = own.get(&variable) {
+                        return Event::read(variable, version);
+                    }
+                    let dirty = aborted_writes.get(&variable).filter(|versions| {
+                        !versions.is_empty() && rng.gen_bool(config.dirty_read_ratio)
+                    });
+                    if let Some(versions) = dirty {
+                        simulated.dirty_reads += 1;
+                        Event::read(variable, versions[rng.gen_range(0..versions.len())])
+                    } else {
+                        committed.get(&variable).map_or_else(
+                            || Event::read_empty(variable),
+                            |&version| Event::read(variable, version),
+                        )
+                    }
+                }
+            })
+            .collect();
+
+        let transaction = if rng.gen_bool(config.abort_ratio) {
+            simulated.aborted += 1;
+            for (variable, version) in own {
+                aborted_writes.entry(variable).or_default().push(version);
+            }
+            Transaction::uncommitted(events)
+        } else {
+            committed.extend(own);
+            Transaction::committed(events)
+        };
+        histories[session].push(transaction);
+    }
+
+    simulated.histories = histories;
+    simulated
+}
+
+#[cfg(test)]
+mod tests {
+    use dbcop_core::check::check;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::generator::{generate_single_history_with_config, GeneratorConfig};
+
+    #[test]
+    fn test_simulate() {
+        let plan = generate_single_history_with_config(
+            4,
+            4,
+            10,
+            4,
+            &GeneratorConfig::builder().seed(3).build(),
+        );
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let correct = simulate(&plan, &SimulatorConfig::default(), &mut rng);
+        assert_eq!((correct.aborted, correct.dirty_reads), (0, 0));
+
+        let aborting = SimulatorConfig::builder().abort_ratio(0.3).build();
+        let aborted = simulate(&plan, &aborting, &mut rng);
+        assert!(aborted.aborted > 0);
+        assert_eq!(aborted.dirty_reads, 0);
+
+        let buggy = SimulatorConfig::builder()
+            .abort_ratio(0.3)
+            .dirty_read_ratio(1.0)
+            .build();
+        let dirty = simulate(&plan, &buggy, &mut rng);
+        assert!(dirty.dirty_reads > 0);
+
+        for simulated in [correct, aborted, dirty] {
+            assert_eq!(simulated.histories.len(), plan.len());
+            for (level, expected) in simulated.expected_verdicts() {
+                assert_eq!(check(&simulated.histories, level).is_ok(), expected);
+            }
+        }
+    }
+}